@@ -0,0 +1,139 @@
+//! Parsing of the `#[influxdb(...)]` attributes
+
+use syn::{
+    Attribute, Data, DeriveInput, Error, Field, Fields, GenericArgument, Lit, Meta, NestedMeta,
+    PathArguments, Result, Type,
+};
+
+/// How a struct field is written to InfluxDB
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum FieldKind {
+    Field,
+    Tag,
+    Timestamp,
+}
+
+/// Attributes of a struct
+#[derive(Debug, Default)]
+pub(crate) struct StructAttributes {
+    /// Set with `#[influxdb(measurement = "name")]`
+    pub measurement: Option<String>,
+}
+
+/// Attributes of a struct field
+#[derive(Debug)]
+pub(crate) struct FieldAttributes {
+    /// Set with `#[influxdb(field)]`, `#[influxdb(tag)]` or `#[influxdb(timestamp)]`
+    pub kind: FieldKind,
+    /// Set with `#[influxdb(rename = "name")]`
+    pub rename: Option<String>,
+}
+
+/// Parses the attributes of the struct `input`
+pub(crate) fn struct_attributes(input: &DeriveInput) -> Result<StructAttributes> {
+    let mut attributes = StructAttributes::default();
+    for meta in influxdb_metas(&input.attrs)? {
+        match &meta {
+            Meta::NameValue(name_value) if name_value.path.is_ident("measurement") => {
+                attributes.measurement = Some(string_value(&name_value.lit)?);
+            }
+            _ => return Err(Error::new_spanned(meta, "unknown influxdb attribute")),
+        }
+    }
+    Ok(attributes)
+}
+
+/// Parses the attributes of `field`
+pub(crate) fn field_attributes(field: &Field) -> Result<FieldAttributes> {
+    let mut kind = None;
+    let mut rename = None;
+    for meta in influxdb_metas(&field.attrs)? {
+        let meta_kind = match &meta {
+            Meta::NameValue(name_value) if name_value.path.is_ident("rename") => {
+                rename = Some(string_value(&name_value.lit)?);
+                continue;
+            }
+            Meta::Path(path) if path.is_ident("field") => FieldKind::Field,
+            Meta::Path(path) if path.is_ident("tag") => FieldKind::Tag,
+            Meta::Path(path) if path.is_ident("timestamp") => FieldKind::Timestamp,
+            _ => return Err(Error::new_spanned(meta, "unknown influxdb attribute")),
+        };
+        if kind.is_some() {
+            return Err(Error::new_spanned(
+                meta,
+                "a field can only be one of field, tag or timestamp",
+            ));
+        }
+        kind = Some(meta_kind);
+    }
+    Ok(FieldAttributes {
+        kind: kind.unwrap_or(FieldKind::Field),
+        rename,
+    })
+}
+
+/// Returns the named fields of the struct `input`
+pub(crate) fn named_fields(input: &DeriveInput) -> Result<Vec<&Field>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(fields.named.iter().collect()),
+            _ => Err(Error::new_spanned(
+                &input.ident,
+                "only structs with named fields are supported",
+            )),
+        },
+        _ => Err(Error::new_spanned(
+            &input.ident,
+            "only structs with named fields are supported",
+        )),
+    }
+}
+
+/// Returns the type wrapped in `Option`, if `ty` is an `Option`
+pub(crate) fn option_inner(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match &arguments.args[0] {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the contents of all `#[influxdb(...)]` attributes
+fn influxdb_metas(attrs: &[Attribute]) -> Result<Vec<Meta>> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("influxdb")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(meta) => metas.push(meta),
+                        NestedMeta::Lit(lit) => {
+                            return Err(Error::new_spanned(lit, "unknown influxdb attribute"))
+                        }
+                    }
+                }
+            }
+            meta => return Err(Error::new_spanned(meta, "expected #[influxdb(...)]")),
+        }
+    }
+    Ok(metas)
+}
+
+fn string_value(lit: &Lit) -> Result<String> {
+    match lit {
+        Lit::Str(string) => Ok(string.value()),
+        _ => Err(Error::new_spanned(lit, "expected a string")),
+    }
+}
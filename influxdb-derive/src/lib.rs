@@ -0,0 +1,65 @@
+//! Derive macros for the [influxdb](https://crates.io/crates/influxdb) crate
+//!
+//! Enable the `derive` feature of `influxdb` to use them, instead of depending on this crate directly.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use influxdb::query::write_query::InfluxDbWritable;
+//! use influxdb::query::Timestamp;
+//!
+//! #[derive(influxdb_derive::InfluxDbWritable)]
+//! #[influxdb(measurement = "weather")]
+//! struct Weather {
+//!     #[influxdb(timestamp)]
+//!     time: Timestamp,
+//!     temperature: i32,
+//!     #[influxdb(tag)]
+//!     location: String,
+//! }
+//!
+//! let weather = Weather {
+//!     time: Timestamp::HOURS(11),
+//!     temperature: 82,
+//!     location: "us-midwest".to_string(),
+//! };
+//! assert_eq!(
+//!     weather.into_query(Timestamp::NOW).to_line_protocol(),
+//!     "weather,location=us-midwest temperature=82i 11"
+//! );
+//! ```
+
+extern crate proc_macro;
+
+mod attributes;
+mod queryable;
+mod writable;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Derives `serde::Deserialize` and a `from_series` method for a struct with named fields
+///
+/// Struct fields are matched to columns of their name, or the name set with
+/// `#[influxdb(rename = "column")]`. The field annotated with `#[influxdb(timestamp)]` receives
+/// the `time` column. `from_series` deserializes all rows of an
+/// `influxdb::integrations::serde_integration::InfluxDbSeries<serde_json::Value>` by column name,
+/// including the tags of the series. Generic structs are not supported.
+#[proc_macro_derive(InfluxDbQueryable, attributes(influxdb))]
+pub fn derive_queryable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    queryable::expand(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Derives `InfluxDbWritable` for a struct with named fields
+///
+/// See `influxdb::query::write_query::InfluxDbWritable` for the supported attributes.
+#[proc_macro_derive(InfluxDbWritable, attributes(influxdb))]
+pub fn derive_writable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    writable::expand(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
@@ -0,0 +1,89 @@
+//! Implementation of `#[derive(InfluxDbQueryable)]`
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Error, Result};
+
+use crate::attributes::{field_attributes, named_fields, FieldKind};
+
+/// Name of the column InfluxDB returns the timestamp of a point in
+const TIME_COLUMN: &str = "time";
+
+pub(crate) fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "generic structs are not supported",
+        ));
+    }
+
+    let mut has_timestamp = false;
+    let mut row_fields = Vec::new();
+    let mut names = Vec::new();
+    for field in named_fields(input)? {
+        let attributes = field_attributes(field)?;
+        let column = match (attributes.kind, attributes.rename) {
+            (FieldKind::Timestamp, _) if has_timestamp => {
+                return Err(Error::new_spanned(
+                    field,
+                    "only one field can be annotated with #[influxdb(timestamp)]",
+                ));
+            }
+            (FieldKind::Timestamp, None) => {
+                has_timestamp = true;
+                TIME_COLUMN.to_string()
+            }
+            (FieldKind::Timestamp, Some(column)) => {
+                has_timestamp = true;
+                column
+            }
+            (_, Some(column)) => column,
+            (_, None) => match &field.ident {
+                Some(name) => name.to_string(),
+                None => continue,
+            },
+        };
+        let name = &field.ident;
+        let ty = &field.ty;
+        row_fields.push(quote! {
+            #[serde(rename = #column)]
+            #name: #ty
+        });
+        names.push(name);
+    }
+
+    let row = format_ident!("__{}InfluxDbRow", ident);
+    Ok(quote! {
+        // Carries the column names, so they do not have to be repeated with serde attributes
+        #[doc(hidden)]
+        #[derive(::influxdb::serde::Deserialize)]
+        #[serde(crate = "::influxdb::serde")]
+        struct #row {
+            #(#row_fields,)*
+        }
+
+        impl<'de> ::influxdb::serde::Deserialize<'de> for #ident {
+            fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+            where
+                D: ::influxdb::serde::Deserializer<'de>,
+            {
+                let row = <#row as ::influxdb::serde::Deserialize>::deserialize(deserializer)?;
+                ::std::result::Result::Ok(#ident {
+                    #(#names: row.#names,)*
+                })
+            }
+        }
+
+        impl #ident {
+            /// Deserializes all rows of `series`, matching values to fields by column name
+            pub fn from_series(
+                series: ::influxdb::integrations::serde_integration::InfluxDbSeries<
+                    ::influxdb::serde_json::Value,
+                >,
+            ) -> ::std::result::Result<::std::vec::Vec<Self>, ::influxdb::error::InfluxDbError> {
+                series.deserialize_rows()
+            }
+        }
+    })
+}
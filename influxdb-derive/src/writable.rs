@@ -0,0 +1,84 @@
+//! Implementation of `#[derive(InfluxDbWritable)]`
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, Result};
+
+use crate::attributes::{
+    field_attributes, named_fields, option_inner, struct_attributes, FieldKind,
+};
+
+pub(crate) fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let measurement = struct_attributes(input)?
+        .measurement
+        .unwrap_or_else(|| ident.to_string());
+
+    let mut timestamp = None;
+    let mut calls = Vec::new();
+    for field in named_fields(input)? {
+        let name = &field.ident;
+        let attributes = field_attributes(field)?;
+        let key = attributes
+            .rename
+            .or_else(|| name.as_ref().map(ToString::to_string));
+        let optional = option_inner(&field.ty).is_some();
+        match attributes.kind {
+            FieldKind::Timestamp if timestamp.is_some() => {
+                return Err(Error::new_spanned(
+                    field,
+                    "only one field can be annotated with #[influxdb(timestamp)]",
+                ));
+            }
+            FieldKind::Timestamp => {
+                timestamp = Some(quote! {
+                    ::std::convert::Into::<::influxdb::query::Timestamp>::into(self.#name)
+                });
+            }
+            FieldKind::Tag if optional => calls.push(quote!(.add_optional_tag(#key, self.#name))),
+            FieldKind::Tag => calls.push(quote!(.add_tag(#key, self.#name))),
+            FieldKind::Field if optional => {
+                calls.push(quote!(.add_optional_field(#key, self.#name)))
+            }
+            FieldKind::Field => calls.push(quote!(.add_field(#key, self.#name))),
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (timestamp_argument, timestamp, from_impl) = match timestamp {
+        Some(timestamp) => (
+            quote!(_),
+            timestamp,
+            // The timestamp field makes the timestamp argument redundant
+            Some(quote! {
+                impl #impl_generics ::std::convert::From<#ident #ty_generics>
+                    for ::influxdb::query::write_query::InfluxDbWriteQuery #where_clause
+                {
+                    fn from(value: #ident #ty_generics) -> Self {
+                        ::influxdb::query::write_query::InfluxDbWritable::into_query(
+                            value,
+                            ::influxdb::query::Timestamp::NOW,
+                        )
+                    }
+                }
+            }),
+        ),
+        None => (quote!(timestamp), quote!(timestamp), None),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::influxdb::query::write_query::InfluxDbWritable
+            for #ident #ty_generics #where_clause
+        {
+            fn into_query(
+                self,
+                #timestamp_argument: ::influxdb::query::Timestamp,
+            ) -> ::influxdb::query::write_query::InfluxDbWriteQuery {
+                ::influxdb::query::write_query::InfluxDbWriteQuery::new(#timestamp, #measurement)
+                    #(#calls)*
+            }
+        }
+
+        #from_impl
+    })
+}
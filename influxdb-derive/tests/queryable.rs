@@ -0,0 +1,119 @@
+use influxdb::integrations::serde_integration::InfluxDbSeries;
+use influxdb_derive::InfluxDbQueryable;
+use serde_json::Value;
+
+#[derive(InfluxDbQueryable, Debug, PartialEq)]
+struct Weather {
+    #[influxdb(timestamp)]
+    timestamp: String,
+    temperature: i32,
+    #[influxdb(tag)]
+    location: String,
+}
+
+#[derive(InfluxDbQueryable, Debug, PartialEq)]
+struct RenamedWeather {
+    #[influxdb(timestamp)]
+    time: u64,
+    #[influxdb(rename = "temp")]
+    temperature: f64,
+    #[influxdb(tag, rename = "loc")]
+    location: String,
+    humidity: Option<f64>,
+}
+
+fn series(json: &str) -> InfluxDbSeries<Value> {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn test_from_series() {
+    let weather = Weather::from_series(series(
+        r#"{"name":"weather","columns":["time","temperature","location"],"values":[["1970-01-01T11:00:00Z",82,"berlin"],["1970-01-01T12:00:00Z",84,"london"]]}"#,
+    ))
+    .unwrap();
+    assert_eq!(
+        weather,
+        vec![
+            Weather {
+                timestamp: "1970-01-01T11:00:00Z".to_string(),
+                temperature: 82,
+                location: "berlin".to_string(),
+            },
+            Weather {
+                timestamp: "1970-01-01T12:00:00Z".to_string(),
+                temperature: 84,
+                location: "london".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_from_series_column_order() {
+    // Columns are matched by name, not by position
+    let weather = Weather::from_series(series(
+        r#"{"name":"weather","columns":["location","temperature","time"],"values":[["berlin",82,"1970-01-01T11:00:00Z"]]}"#,
+    ))
+    .unwrap();
+    assert_eq!(weather[0].timestamp, "1970-01-01T11:00:00Z");
+    assert_eq!(weather[0].location, "berlin");
+}
+
+#[test]
+fn test_from_series_grouped_by_tag() {
+    let weather = Weather::from_series(series(
+        r#"{"name":"weather","tags":{"location":"berlin"},"columns":["time","temperature"],"values":[["1970-01-01T11:00:00Z",82]]}"#,
+    ))
+    .unwrap();
+    assert_eq!(weather[0].location, "berlin");
+    assert_eq!(weather[0].temperature, 82);
+}
+
+#[test]
+fn test_rename() {
+    let weather = RenamedWeather::from_series(series(
+        r#"{"name":"weather","tags":{"loc":"berlin"},"columns":["time","temp","humidity"],"values":[[39600,82.5,0.5]]}"#,
+    ))
+    .unwrap();
+    assert_eq!(
+        weather,
+        vec![RenamedWeather {
+            time: 39600,
+            temperature: 82.5,
+            location: "berlin".to_string(),
+            humidity: Some(0.5),
+        }]
+    );
+}
+
+#[test]
+fn test_optional_fields() {
+    let weather = RenamedWeather::from_series(series(
+        r#"{"name":"weather","columns":["time","temp","loc"],"values":[[39600,82.5,"berlin"]]}"#,
+    ))
+    .unwrap();
+    assert_eq!(weather[0].humidity, None);
+
+    let weather = RenamedWeather::from_series(series(
+        r#"{"name":"weather","columns":["time","temp","loc","humidity"],"values":[[39600,82.5,"berlin",null]]}"#,
+    ))
+    .unwrap();
+    assert_eq!(weather[0].humidity, None);
+}
+
+#[test]
+fn test_missing_timestamp() {
+    let result = Weather::from_series(series(
+        r#"{"name":"weather","columns":["temperature","location"],"values":[[82,"berlin"]]}"#,
+    ));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_deserialize_row_by_position() {
+    // Rows deserialized without column names are matched by position
+    let weather: RenamedWeather = serde_json::from_str(r#"[39600,82.5,"berlin",null]"#).unwrap();
+    assert_eq!(weather.time, 39600);
+    assert_eq!(weather.location, "berlin");
+}
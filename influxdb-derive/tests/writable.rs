@@ -0,0 +1,83 @@
+use influxdb::query::write_query::{InfluxDbWritable, InfluxDbWriteQuery};
+use influxdb::query::Timestamp;
+
+#[derive(influxdb_derive::InfluxDbWritable)]
+#[influxdb(measurement = "weather")]
+struct Weather {
+    #[influxdb(timestamp)]
+    time: Timestamp,
+    #[influxdb(field)]
+    temperature: i32,
+    humidity: f64,
+    #[influxdb(tag)]
+    location: String,
+}
+
+#[derive(influxdb_derive::InfluxDbWritable)]
+struct Traffic {
+    cars: u32,
+    trucks: Option<u32>,
+    #[influxdb(tag)]
+    street: &'static str,
+    #[influxdb(tag)]
+    direction: Option<&'static str>,
+}
+
+#[test]
+fn test_tags_and_fields() {
+    let traffic = Traffic {
+        cars: 12,
+        trucks: Some(3),
+        street: "main",
+        direction: Some("north"),
+    };
+    assert_eq!(
+        traffic.into_query(Timestamp::HOURS(11)).to_line_protocol(),
+        "Traffic,street=main,direction=north cars=12i,trucks=3i 11"
+    );
+}
+
+#[test]
+fn test_optional_values() {
+    let traffic = Traffic {
+        cars: 12,
+        trucks: None,
+        street: "main",
+        direction: None,
+    };
+    assert_eq!(
+        traffic.into_query(Timestamp::NOW).to_line_protocol(),
+        "Traffic,street=main cars=12i"
+    );
+}
+
+#[test]
+fn test_timestamp_field() {
+    let weather = Weather {
+        time: Timestamp::HOURS(11),
+        temperature: 82,
+        humidity: 0.5,
+        location: "us-midwest".to_string(),
+    };
+    // The timestamp field takes precedence over the argument
+    assert_eq!(
+        weather.into_query(Timestamp::SECONDS(1)).to_line_protocol(),
+        "weather,location=us-midwest temperature=82i,humidity=0.5 11"
+    );
+}
+
+#[test]
+fn test_from_with_timestamp_field() {
+    let weather = Weather {
+        time: Timestamp::MINUTES(3),
+        temperature: 82,
+        humidity: 0.5,
+        location: "us-midwest".to_string(),
+    };
+    let query = InfluxDbWriteQuery::from(weather);
+    assert_eq!(query.get_precision(), "m");
+    assert_eq!(
+        query.to_line_protocol(),
+        "weather,location=us-midwest temperature=82i,humidity=0.5 3"
+    );
+}
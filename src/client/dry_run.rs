@@ -0,0 +1,236 @@
+//! Client which builds the requests of an [`InfluxDbClient`](crate::client::InfluxDbClient)
+//! without sending them
+//!
+//! Returned by [`InfluxDbClient::dry_run`](crate::client::InfluxDbClient::dry_run).
+
+use reqwest::header::{HeaderMap, USER_AGENT};
+use reqwest::{Method, Url};
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::client::InfluxDbClient;
+use crate::error::InfluxDbError;
+#[cfg(feature = "use-serde")]
+use crate::query::read_query::InfluxDbReadQuery;
+use crate::query::write_query::InfluxDbWriteQuery;
+use crate::query::InfluxDbQuery;
+
+/// Request which would have been sent to InfluxDB, as returned by [`DryRunClient`]
+///
+/// Header names are lowercase. The body is shown uncompressed, even if it would be sent
+/// gzip compressed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DryRunResult {
+    pub url: String,
+    pub method: String,
+    pub body: Option<String>,
+    pub headers: Vec<(String, String)>,
+}
+
+impl fmt::Display for DryRunResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", self.method, self.url)?;
+        for (name, value) in &self.headers {
+            writeln!(f, "{}: {}", name, value)?;
+        }
+        if let Some(body) = &self.body {
+            write!(f, "\n{}", body)?;
+        }
+        Ok(())
+    }
+}
+
+/// Wrapper around an [`InfluxDbClient`] which returns the requests the client would send
+///
+/// # Examples
+///
+/// ```rust
+/// use influxdb::client::InfluxDbClient;
+/// use influxdb::query::InfluxDbQuery;
+///
+/// let client = InfluxDbClient::new("http://localhost:8086", "test").dry_run();
+/// let request = client
+///     .query(&InfluxDbQuery::raw_read_query("SELECT * FROM weather"))
+///     .unwrap();
+///
+/// assert_eq!(request.method, "GET");
+/// assert_eq!(
+///     request.url,
+///     "http://localhost:8086/query?db=test&q=SELECT+*+FROM+weather"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct DryRunClient {
+    client: InfluxDbClient,
+}
+
+impl DryRunClient {
+    pub(crate) fn new(client: InfluxDbClient) -> Self {
+        DryRunClient { client }
+    }
+
+    /// Returns the request [`InfluxDbClient::query`](crate::client::InfluxDbClient::query)
+    /// would send for `q`
+    pub fn query<Q>(&self, q: &Q) -> Result<DryRunResult, InfluxDbError>
+    where
+        Q: Any + InfluxDbQuery,
+    {
+        let (method, url, body) = self.client.query_request(q)?;
+        self.dry_run(method, url, body)
+    }
+
+    /// Returns the request [`InfluxDbClient::json_query`](crate::client::InfluxDbClient::json_query)
+    /// would send for `q`
+    #[cfg(feature = "use-serde")]
+    pub fn json_query(&self, q: &InfluxDbReadQuery) -> Result<DryRunResult, InfluxDbError> {
+        let (_, url) = self.client.json_query_url(q)?;
+        self.dry_run(Method::GET, url, None)
+    }
+
+    /// Returns the request [`InfluxDbClient::write_many`](crate::client::InfluxDbClient::write_many)
+    /// would send for `queries`
+    pub fn write_many<I>(&self, queries: I) -> Result<DryRunResult, InfluxDbError>
+    where
+        I: IntoIterator<Item = InfluxDbWriteQuery>,
+    {
+        let (url, body) = self.client.write_many_request(queries)?;
+        self.dry_run(Method::POST, url, Some(body))
+    }
+
+    fn dry_run(
+        &self,
+        method: Method,
+        url: Url,
+        body: Option<String>,
+    ) -> Result<DryRunResult, InfluxDbError> {
+        let request = self
+            .client
+            .prepare_request(method, url, HeaderMap::new(), body.clone())
+            .build()
+            .map_err(|err| InfluxDbError::ConnectionError {
+                error: Arc::new(err),
+            })?;
+        // The User-Agent is a default header of the HTTP client, so it is not part of the request
+        let mut headers: Vec<(String, String)> = self
+            .client
+            .user_agent
+            .iter()
+            .map(|user_agent| (USER_AGENT.as_str().to_string(), user_agent.clone()))
+            .collect();
+        headers.extend(request.headers().iter().map(|(name, value)| {
+            (
+                name.as_str().to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        }));
+        Ok(DryRunResult {
+            url: request.url().to_string(),
+            method: request.method().to_string(),
+            body,
+            headers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DryRunResult;
+    use crate::client::InfluxDbClient;
+    use crate::query::{InfluxDbQuery, Timestamp};
+
+    #[test]
+    fn test_query_read() {
+        let client = InfluxDbClient::new("http://localhost:8086", "database")
+            .with_auth("user", "pass")
+            .dry_run();
+        let result = client
+            .query(&InfluxDbQuery::raw_read_query("CREATE DATABASE weather"))
+            .unwrap();
+        assert_eq!(
+            result,
+            DryRunResult {
+                url: "http://localhost:8086/query?db=database&u=user&p=pass&q=CREATE+DATABASE+weather"
+                    .to_string(),
+                method: "POST".to_string(),
+                body: None,
+                headers: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_query_write() {
+        let client = InfluxDbClient::new("http://localhost:8086", "database")
+            .with_token("token")
+            .with_user_agent("my-app/1.0")
+            .unwrap()
+            .dry_run();
+        let result = client
+            .query(
+                &InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                    .add_field("temperature", 82),
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            DryRunResult {
+                url: "http://localhost:8086/write?db=database&precision=s".to_string(),
+                method: "POST".to_string(),
+                body: Some("weather temperature=82i 1".to_string()),
+                headers: vec![
+                    ("user-agent".to_string(), "my-app/1.0".to_string()),
+                    ("authorization".to_string(), "Token token".to_string()),
+                ],
+            }
+        );
+        assert_eq!(
+            result.to_string(),
+            "POST http://localhost:8086/write?db=database&precision=s\nuser-agent: my-app/1.0\nauthorization: Token token\n\nweather temperature=82i 1"
+        );
+    }
+
+    #[test]
+    fn test_write_many() {
+        let client = InfluxDbClient::new("http://localhost:8086", "database").dry_run();
+        let result = client
+            .write_many(vec![
+                InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                    .add_field("temperature", 82),
+                InfluxDbQuery::write_query(Timestamp::SECONDS(2), "weather")
+                    .add_field("temperature", 83),
+            ])
+            .unwrap();
+        assert_eq!(
+            result.url,
+            "http://localhost:8086/write?db=database&precision=s"
+        );
+        assert_eq!(
+            result.body,
+            Some("weather temperature=82i 1\nweather temperature=83i 2".to_string())
+        );
+
+        assert!(client.write_many(Vec::new()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "use-serde")]
+    fn test_json_query() {
+        let client = InfluxDbClient::new("http://localhost:8086", "database").dry_run();
+        let result = client
+            .json_query(&InfluxDbQuery::raw_read_query("SELECT * FROM weather"))
+            .unwrap();
+        assert_eq!(result.method, "GET");
+        assert_eq!(
+            result.url,
+            "http://localhost:8086/query?db=database&q=SELECT+*+FROM+weather"
+        );
+        assert!(client
+            .json_query(&InfluxDbQuery::raw_read_query("CREATE DATABASE weather"))
+            .is_err());
+        assert_eq!(
+            result.to_string(),
+            "GET http://localhost:8086/query?db=database&q=SELECT+*+FROM+weather\n"
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Parsing of the annotated CSV InfluxDB responds to Flux queries with.
+//! See [InfluxDB Docs](https://docs.influxdata.com/influxdb/v2.0/reference/syntax/annotated-csv/).
+
+use crate::error::InfluxDbError;
+
+/// Table of a [`FluxQueryResult`](crate::client::FluxQueryResult)
+///
+/// Values are kept as returned by InfluxDB. Their types are available in `data_types` if the
+/// response contains a `#datatype` annotation.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FluxTable {
+    pub columns: Vec<String>,
+    /// Data type of each column, e.g. `long` or `dateTime:RFC3339`
+    pub data_types: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl FluxTable {
+    /// Returns the index of the column called `name`
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == name)
+    }
+}
+
+/// Result of a Flux query, as returned by
+/// [`InfluxDbClient::flux_query`](crate::client::InfluxDbClient::flux_query) in annotated CSV
+///
+/// # Examples
+///
+/// ```rust
+/// use influxdb::client::FluxQueryResult;
+///
+/// let csv = "#datatype,string,long,double\n,result,table,_value\n,_result,0,82.5\n";
+/// let result = FluxQueryResult::parse(csv).unwrap();
+///
+/// assert_eq!(result.tables[0].rows[0][2], "82.5");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FluxQueryResult {
+    /// Tables in the order of the response. Tables separated by an empty line are returned
+    /// separately, as they may have different columns.
+    pub tables: Vec<FluxTable>,
+}
+
+impl FluxQueryResult {
+    /// Parses annotated CSV
+    ///
+    /// The first column, which only holds annotations, is removed from every table.
+    ///
+    /// # Errors
+    ///
+    /// If a row has a different number of values than its table has columns,
+    /// a [`InfluxDbError::DeserializationError`] will be returned.
+    ///
+    /// [`InfluxDbError::DeserializationError`]: crate::error::InfluxDbError::DeserializationError
+    pub fn parse(csv: &str) -> Result<Self, InfluxDbError> {
+        let mut tables = Vec::new();
+        let mut table: Option<FluxTable> = None;
+        let mut data_types = Vec::new();
+        for record in parse_records(csv)? {
+            let record = match record {
+                Some(record) => record,
+                // An empty line ends the current table
+                None => {
+                    tables.extend(table.take());
+                    continue;
+                }
+            };
+            let (annotation, values) = match record.split_first() {
+                Some(split) => split,
+                None => continue,
+            };
+            if annotation == "#datatype" {
+                data_types = values.to_vec();
+            } else if annotation.starts_with('#') {
+                // `#group` and `#default` annotations are not needed to read the values
+            } else if let Some(table) = &mut table {
+                if values.len() != table.columns.len() {
+                    return Err(InfluxDbError::DeserializationError {
+                        error: format!(
+                            "expected {} values per row, found {}",
+                            table.columns.len(),
+                            values.len()
+                        ),
+                    });
+                }
+                table.rows.push(values.to_vec());
+            } else {
+                table = Some(FluxTable {
+                    columns: values.to_vec(),
+                    data_types: std::mem::take(&mut data_types),
+                    rows: Vec::new(),
+                });
+            }
+        }
+        tables.extend(table);
+        Ok(FluxQueryResult { tables })
+    }
+}
+
+/// Splits CSV into records, returning `None` for empty lines
+fn parse_records(csv: &str) -> Result<Vec<Option<Vec<String>>>, InfluxDbError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                value.push('"');
+            }
+            '"' => quoted = !quoted,
+            _ if quoted => value.push(c),
+            ',' => record.push(std::mem::take(&mut value)),
+            '\r' => {}
+            '\n' => {
+                if record.is_empty() && value.is_empty() {
+                    records.push(None);
+                } else {
+                    record.push(std::mem::take(&mut value));
+                    records.push(Some(std::mem::take(&mut record)));
+                }
+            }
+            _ => value.push(c),
+        }
+    }
+    if quoted {
+        return Err(InfluxDbError::DeserializationError {
+            error: "unterminated quoted value".to_string(),
+        });
+    }
+    if !record.is_empty() || !value.is_empty() {
+        record.push(value);
+        records.push(Some(record));
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FluxQueryResult, FluxTable};
+
+    #[test]
+    fn test_parse() {
+        let csv = "#group,false,false,true,false\r\n#datatype,string,long,string,double\r\n#default,_result,,,\r\n,result,table,_measurement,_value\r\n,,0,weather,82\r\n,,0,weather,83.5\r\n\r\n#datatype,string,long,string\r\n,result,table,location\r\n,,1,\"us, midwest\"\r\n\r\n";
+        let result = FluxQueryResult::parse(csv).unwrap();
+        assert_eq!(
+            result.tables,
+            vec![
+                FluxTable {
+                    columns: vec!["result", "table", "_measurement", "_value"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                    data_types: vec!["string", "long", "string", "double"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                    rows: vec![
+                        vec!["", "0", "weather", "82"]
+                            .into_iter()
+                            .map(String::from)
+                            .collect(),
+                        vec!["", "0", "weather", "83.5"]
+                            .into_iter()
+                            .map(String::from)
+                            .collect(),
+                    ],
+                },
+                FluxTable {
+                    columns: vec!["result", "table", "location"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                    data_types: vec!["string", "long", "string"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                    rows: vec![vec!["", "1", "us, midwest"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect()],
+                },
+            ]
+        );
+        assert_eq!(result.tables[0].column_index("_value"), Some(3));
+    }
+
+    #[test]
+    fn test_parse_quoted_values() {
+        let csv = ",name\n,\"say \"\"hi\"\"\nthere\"\n";
+        let result = FluxQueryResult::parse(csv).unwrap();
+        assert_eq!(result.tables[0].rows[0][0], "say \"hi\"\nthere");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(FluxQueryResult::parse(",a,b\n,1\n").is_err());
+        assert!(FluxQueryResult::parse(",a\n,\"1\n").is_err());
+        assert_eq!(FluxQueryResult::parse("").unwrap().tables, vec![]);
+    }
+}
@@ -15,39 +15,355 @@
 //! assert_eq!(client.database_name(), "test");
 //! ```
 
+mod dry_run;
+mod flux;
+mod rate_limiter;
+#[cfg(feature = "tracing")]
+mod trace;
+mod write_buffer;
+mod write_stream;
+
+use futures::future::{self, Loop};
 use futures::{Future, Stream};
-use reqwest::r#async::{Client, Decoder};
-use reqwest::{StatusCode, Url};
+#[cfg(feature = "gzip")]
+use reqwest::header::CONTENT_ENCODING;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER,
+    USER_AGENT,
+};
+use reqwest::r#async::{Chunk, Client, ClientBuilder, Decoder, RequestBuilder, Response};
+use reqwest::{Certificate, IntoUrl, Method, Proxy, StatusCode, Url};
 
+use std::collections::HashMap;
+use std::fmt;
 use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::timer::{Delay, Timeout};
 
+use crate::client::rate_limiter::RateLimiter;
+use crate::client::write_stream::WriteStream;
 use crate::error::InfluxDbError;
-use crate::query::read_query::InfluxDbReadQuery;
+use crate::query::read_query::{
+    has_into_clause, is_duration_literal, InfluxDbReadQuery, InfluxDbValue,
+};
+use crate::query::write_batch::InfluxDbWriteBatch;
 use crate::query::write_query::InfluxDbWriteQuery;
-use crate::query::InfluxDbQuery;
+use crate::query::{quote_identifier, InfluxDbQuery, Precision, QueryType};
 
 use std::any::Any;
 
-#[derive(Clone, Debug)]
-/// Internal Authentication representation
-pub(crate) struct InfluxDbAuthentication {
-    pub username: String,
-    pub password: String,
+pub use crate::client::dry_run::{DryRunClient, DryRunResult};
+pub use crate::client::flux::{FluxQueryResult, FluxTable};
+pub use crate::client::write_buffer::InfluxDbWriteBuffer;
+
+#[derive(Clone, Debug, PartialEq)]
+/// Username and password a [`InfluxDbClient`](crate::client::InfluxDbClient) authenticates with
+///
+/// # Examples
+///
+/// ```rust
+/// use influxdb::client::{InfluxDbAuthentication, InfluxDbClient};
+///
+/// let client = InfluxDbClient::new("http://localhost:8086", "test").with_auth("admin", "password");
+///
+/// assert_eq!(client.auth(), Some(&InfluxDbAuthentication::new("admin", "password")));
+/// ```
+pub struct InfluxDbAuthentication {
+    username: String,
+    password: String,
+}
+
+impl InfluxDbAuthentication {
+    /// Creates the credentials of the user `username` with the password `password`
+    pub fn new<S1, S2>(username: S1, password: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        InfluxDbAuthentication {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Returns the name of the user
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Returns the password of the user
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+/// Internal Timeout configuration
+pub(crate) struct InfluxDbTimeouts {
+    pub connect: Option<Duration>,
+    pub request: Option<Duration>,
+}
+
+impl InfluxDbTimeouts {
+    /// Returns the configured timeout which elapsed, if `err` was caused by a timeout
+    pub fn elapsed(&self, err: &reqwest::Error) -> Option<Duration> {
+        if !err.is_timeout() {
+            return None;
+        }
+        // reqwest reports connect timeouts as hyper errors and request timeouts as io errors
+        if err.is_http() {
+            self.connect.or(self.request)
+        } else {
+            self.request.or(self.connect)
+        }
+    }
+
+    /// Converts an error which occurred while reading a response body
+    pub fn body_error(&self, err: reqwest::Error) -> InfluxDbError {
+        match self.elapsed(&err) {
+            Some(elapsed) => InfluxDbError::TimeoutError { elapsed },
+            None => InfluxDbError::ProtocolError {
+                error: format!("{}", err),
+            },
+        }
+    }
+}
+
+/// Build type and version of an InfluxDB server, as returned by
+/// [`InfluxDbClient::ping`](crate::client::InfluxDbClient::ping)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingResponse {
+    /// Build type, e.g. `OSS`. Empty if the server does not report it
+    pub build: String,
+    /// Version number, e.g. `1.7.6`
+    pub version: String,
+}
+
+impl PingResponse {
+    /// Returns `true` if the server is an InfluxDB Enterprise build
+    pub fn is_enterprise(&self) -> bool {
+        self.build.eq_ignore_ascii_case("Enterprise")
+    }
+}
+
+/// Privilege of a user, granted with [`InfluxDbClient::grant_privilege`](crate::client::InfluxDbClient::grant_privilege)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Privilege {
+    Read,
+    Write,
+    All,
+}
+
+impl fmt::Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Privilege::Read => write!(f, "READ"),
+            Privilege::Write => write!(f, "WRITE"),
+            Privilege::All => write!(f, "ALL"),
+        }
+    }
+}
+
+/// Which destinations of a subscription receive the written data, see
+/// [`InfluxDbClient::create_subscription`](crate::client::InfluxDbClient::create_subscription)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubscriptionMode {
+    /// Each point is sent to one of the destinations, in round robin
+    Any,
+    /// Each point is sent to all destinations
+    All,
+}
+
+impl fmt::Display for SubscriptionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubscriptionMode::Any => write!(f, "ANY"),
+            SubscriptionMode::All => write!(f, "ALL"),
+        }
+    }
+}
+
+/// Major version of an InfluxDB server, which decides the endpoints the client uses
+///
+/// Returned by [`InfluxDbClient::detect_version`](crate::client::InfluxDbClient::detect_version).
+#[derive(Debug, Clone, PartialEq)]
+pub enum InfluxDbVersion {
+    V1 { version: String },
+    V2 { version: String },
+}
+
+impl From<PingResponse> for InfluxDbVersion {
+    /// Determines the major version from the `X-Influxdb-Version` header, e.g. `1.7.6` or
+    /// `v2.0.0`. If it is not a version number, servers reporting no build type are assumed to
+    /// be InfluxDB 2.x, as only InfluxDB 1.x always sends the `X-Influxdb-Build` header.
+    fn from(ping: PingResponse) -> Self {
+        let major = ping
+            .version
+            .trim_start_matches('v')
+            .split('.')
+            .next()
+            .and_then(|major| major.parse::<u32>().ok());
+        let is_v2 = match major {
+            Some(major) => major >= 2,
+            None => ping.build.is_empty(),
+        };
+        if is_v2 {
+            InfluxDbVersion::V2 {
+                version: ping.version,
+            }
+        } else {
+            InfluxDbVersion::V1 {
+                version: ping.version,
+            }
+        }
+    }
+}
+
+/// Callback invoked by a [`RetryPolicy`](crate::client::RetryPolicy) before every retry
+pub type RetryCallback = Box<dyn Fn(u32, &InfluxDbError) + Send + Sync>;
+
+/// Policy for retrying requests to InfluxDB which failed due to a transient error
+///
+/// Requests are retried when they fail with an error for which
+/// [`InfluxDbError::is_retryable`](crate::error::InfluxDbError::is_retryable) returns `true`.
+/// After a [`RateLimitError`](crate::error::InfluxDbError::RateLimitError), the delay InfluxDB
+/// asked for with a `Retry-After` header is used instead of the delay of the policy, up to
+/// `max_delay`.
+/// Write queries are sent with the same body on every attempt.
+///
+/// # Examples
+///
+/// ```rust
+/// use influxdb::client::{InfluxDbClient, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let client = InfluxDbClient::new("http://localhost:8086", "test").with_retry_policy(RetryPolicy {
+///     max_attempts: 5,
+///     initial_delay: Duration::from_millis(50),
+///     on_retry: Some(Box::new(|attempt, err| println!("retry {}: {}", attempt, err))),
+///     ..RetryPolicy::default()
+/// });
+/// assert!(client.is_ok());
+/// ```
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Factor the delay is multiplied with after every retry, a finite number of at least 0
+    pub backoff_factor: f64,
+    /// Longest delay between two attempts, also limiting the delay asked for by InfluxDB
+    pub max_delay: Duration,
+    /// Called before every retry with the number of the retry and the error which caused it
+    pub on_retry: Option<RetryCallback>,
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the retry following `attempt`, starting at zero, which is at
+    /// most `max_delay`
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let factor = self
+            .backoff_factor
+            .powi(attempt.min(i32::MAX as u32) as i32);
+        Duration::try_from_secs_f64(self.initial_delay.as_secs_f64() * factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Checks that `backoff_factor` is a finite number of at least 0
+    fn validate(&self) -> Result<(), InfluxDbError> {
+        if !self.backoff_factor.is_finite() || self.backoff_factor < 0.0 {
+            return Err(InfluxDbError::ProtocolError {
+                error: format!(
+                    "backoff factor must be a finite number of at least 0, but is {}",
+                    self.backoff_factor
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, with the delay starting at 100ms and doubling after every retry, up to 30s
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(100),
+            backoff_factor: 2.0,
+            max_delay: Duration::from_secs(30),
+            on_retry: None,
+        }
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_delay", &self.initial_delay)
+            .field("backoff_factor", &self.backoff_factor)
+            .field("max_delay", &self.max_delay)
+            .field("on_retry", &self.on_retry.is_some())
+            .finish()
+    }
+}
+
+/// Customization of the `reqwest` [`ClientBuilder`](reqwest::r#async::ClientBuilder), kept so
+/// that it can be applied again when the HTTP client is rebuilt
+#[derive(Clone)]
+struct HttpClientConfig(Arc<dyn Fn(ClientBuilder) -> ClientBuilder + Send + Sync>);
+
+impl fmt::Debug for HttpClientConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HttpClientConfig")
+    }
 }
 
 #[derive(Clone, Debug)]
 /// Internal Representation of a Client
 pub struct InfluxDbClient {
     url: String,
+    base_path: String,
     database: String,
     auth: Option<InfluxDbAuthentication>,
+    token: Option<String>,
+    org: Option<String>,
+    bucket: Option<String>,
+    version: Option<InfluxDbVersion>,
+    pub(crate) timeouts: InfluxDbTimeouts,
+    pub(crate) inner_client: Client,
+    retry_policy: Option<Arc<RetryPolicy>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    precision: Precision,
+    chunk_size: Option<usize>,
+    user_agent: Option<String>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    response_compression: bool,
+    #[cfg(feature = "gzip")]
+    write_compression: bool,
+    #[cfg(feature = "tracing")]
+    tracing_propagation: bool,
+    default_tags: Vec<(String, String)>,
+    proxies: Vec<Proxy>,
+    /// PEM encoded, as `Certificate` cannot be cloned
+    root_certificates: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+    connection_pool_size: Option<usize>,
+    http_client_config: Vec<HttpClientConfig>,
+    /// Whether `inner_client` was supplied with `with_http_client` and must be kept as is
+    custom_http_client: bool,
 }
 
 impl Into<Vec<(String, String)>> for InfluxDbClient {
     fn into(self) -> Vec<(String, String)> {
         let mut vec: Vec<(String, String)> = Vec::new();
         vec.push(("db".to_string(), self.database));
-        if let Some(auth) = self.auth {
+        if let Some(org) = self.org {
+            vec.push(("org".to_string(), org));
+        }
+        if let (None, Some(auth)) = (self.token, self.auth) {
             vec.push(("u".to_string(), auth.username));
             vec.push(("p".to_string(), auth.password));
         }
@@ -59,7 +375,10 @@ impl<'a> Into<Vec<(String, String)>> for &'a InfluxDbClient {
     fn into(self) -> Vec<(String, String)> {
         let mut vec: Vec<(String, String)> = Vec::new();
         vec.push(("db".to_string(), self.database.to_owned()));
-        if let Some(auth) = &self.auth {
+        if let Some(org) = &self.org {
+            vec.push(("org".to_string(), org.to_owned()));
+        }
+        if let (None, Some(auth)) = (&self.token, &self.auth) {
             vec.push(("u".to_string(), auth.username.to_owned()));
             vec.push(("p".to_string(), auth.password.to_owned()));
         }
@@ -67,6 +386,426 @@ impl<'a> Into<Vec<(String, String)>> for &'a InfluxDbClient {
     }
 }
 
+/// Builder for a [`InfluxDbClient`](crate::client::InfluxDbClient)
+///
+/// Offers the same configuration as the builder methods on [`InfluxDbClient`](crate::client::InfluxDbClient),
+/// and additionally gives access to the `reqwest` [`ClientBuilder`](reqwest::r#async::ClientBuilder)
+/// used to create the underlying HTTP client.
+///
+/// # Examples
+///
+/// ```rust
+/// use influxdb::client::InfluxDbClient;
+/// use std::time::Duration;
+///
+/// let client = InfluxDbClient::builder("http://localhost:8086", "test")
+///     .with_auth("admin", "password")
+///     .with_request_timeout(Duration::from_secs(30))
+///     .with_http_client_builder(|builder| builder.max_idle_per_host(4))
+///     .build();
+///
+/// assert_eq!(client.database_name(), "test");
+/// ```
+#[derive(Debug)]
+pub struct InfluxDbClientBuilder {
+    url: String,
+    base_path: String,
+    database: String,
+    auth: Option<InfluxDbAuthentication>,
+    token: Option<String>,
+    org: Option<String>,
+    bucket: Option<String>,
+    version: Option<InfluxDbVersion>,
+    timeouts: InfluxDbTimeouts,
+    retry_policy: Option<Arc<RetryPolicy>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    precision: Precision,
+    chunk_size: Option<usize>,
+    user_agent: Option<String>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    response_compression: bool,
+    #[cfg(feature = "gzip")]
+    write_compression: bool,
+    #[cfg(feature = "tracing")]
+    tracing_propagation: bool,
+    default_tags: Vec<(String, String)>,
+    proxies: Vec<Proxy>,
+    root_certificates: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+    connection_pool_size: Option<usize>,
+    http_client_config: Vec<HttpClientConfig>,
+    http_client: Option<Client>,
+}
+
+impl InfluxDbClientBuilder {
+    /// Instantiates a new [`InfluxDbClientBuilder`](crate::client::InfluxDbClientBuilder)
+    ///
+    /// # Arguments
+    ///
+    ///  * `url`: The URL where InfluxDB is running (ex. `http://localhost:8086`).
+    ///  * `database`: The Database against which queries and writes will be run.
+    pub fn new<S1, S2>(url: S1, database: S2) -> Self
+    where
+        S1: ToString,
+        S2: ToString,
+    {
+        InfluxDbClientBuilder {
+            url: url.to_string(),
+            base_path: String::new(),
+            database: database.to_string(),
+            auth: None,
+            token: None,
+            org: None,
+            bucket: None,
+            version: None,
+            timeouts: InfluxDbTimeouts::default(),
+            retry_policy: None,
+            rate_limiter: None,
+            precision: Precision::default(),
+            chunk_size: None,
+            user_agent: None,
+            headers: Vec::new(),
+            response_compression: true,
+            #[cfg(feature = "gzip")]
+            write_compression: false,
+            #[cfg(feature = "tracing")]
+            tracing_propagation: false,
+            default_tags: Vec::new(),
+            proxies: Vec::new(),
+            root_certificates: Vec::new(),
+            accept_invalid_certs: false,
+            connection_pool_size: None,
+            http_client_config: Vec::new(),
+            http_client: None,
+        }
+    }
+
+    /// See [`InfluxDbClient::with_auth`](crate::client::InfluxDbClient::with_auth)
+    pub fn with_auth<S1, S2>(mut self, username: S1, password: S2) -> Self
+    where
+        S1: ToString,
+        S2: ToString,
+    {
+        self.auth = Some(InfluxDbAuthentication::new(
+            username.to_string(),
+            password.to_string(),
+        ));
+        self
+    }
+
+    /// See [`InfluxDbClient::with_token`](crate::client::InfluxDbClient::with_token)
+    pub fn with_token<S>(mut self, token: S) -> Self
+    where
+        S: ToString,
+    {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// See [`InfluxDbClient::with_base_path`](crate::client::InfluxDbClient::with_base_path)
+    pub fn with_base_path(mut self, path: &str) -> Self {
+        self.base_path = normalize_base_path(path);
+        self
+    }
+
+    /// See [`InfluxDbClient::with_org`](crate::client::InfluxDbClient::with_org)
+    pub fn with_org<S>(mut self, org: S) -> Self
+    where
+        S: ToString,
+    {
+        self.org = Some(org.to_string());
+        self
+    }
+
+    /// See [`InfluxDbClient::with_bucket`](crate::client::InfluxDbClient::with_bucket)
+    pub fn with_bucket<S>(mut self, bucket: S) -> Self
+    where
+        S: ToString,
+    {
+        self.bucket = Some(bucket.to_string());
+        self
+    }
+
+    /// See [`InfluxDbClient::with_version`](crate::client::InfluxDbClient::with_version)
+    pub fn with_version(mut self, version: InfluxDbVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// See [`InfluxDbClient::with_request_timeout`](crate::client::InfluxDbClient::with_request_timeout)
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.request = Some(timeout);
+        self
+    }
+
+    /// See [`InfluxDbClient::with_connect_timeout`](crate::client::InfluxDbClient::with_connect_timeout)
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.connect = Some(timeout);
+        self
+    }
+
+    /// See [`InfluxDbClient::with_retry_policy`](crate::client::InfluxDbClient::with_retry_policy)
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Result<Self, InfluxDbError> {
+        retry_policy.validate()?;
+        self.retry_policy = Some(Arc::new(retry_policy));
+        Ok(self)
+    }
+
+    /// See [`InfluxDbClient::with_rate_limiter`](crate::client::InfluxDbClient::with_rate_limiter)
+    pub fn with_rate_limiter(mut self, requests_per_second: f64) -> Self {
+        self.rate_limiter = RateLimiter::new(requests_per_second).map(Arc::new);
+        self
+    }
+
+    /// See [`InfluxDbClient::with_precision`](crate::client::InfluxDbClient::with_precision)
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// See [`InfluxDbClient::with_chunk_size`](crate::client::InfluxDbClient::with_chunk_size)
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// See [`InfluxDbClient::with_user_agent`](crate::client::InfluxDbClient::with_user_agent)
+    pub fn with_user_agent<S>(mut self, user_agent: S) -> Result<Self, InfluxDbError>
+    where
+        S: Into<String>,
+    {
+        let user_agent = user_agent.into();
+        user_agent_header(&user_agent)?;
+        self.user_agent = Some(user_agent);
+        Ok(self)
+    }
+
+    /// See [`InfluxDbClient::with_response_compression`](crate::client::InfluxDbClient::with_response_compression)
+    pub fn with_response_compression(mut self, enabled: bool) -> Self {
+        self.response_compression = enabled;
+        self
+    }
+
+    /// See [`InfluxDbClient::with_write_compression`](crate::client::InfluxDbClient::with_write_compression)
+    #[cfg(feature = "gzip")]
+    pub fn with_write_compression(mut self, enabled: bool) -> Self {
+        self.write_compression = enabled;
+        self
+    }
+
+    /// See [`InfluxDbClient::with_tracing_propagation`](crate::client::InfluxDbClient::with_tracing_propagation)
+    #[cfg(feature = "tracing")]
+    pub fn with_tracing_propagation(mut self, enabled: bool) -> Self {
+        self.tracing_propagation = enabled;
+        self
+    }
+
+    /// See [`InfluxDbClient::with_default_tags`](crate::client::InfluxDbClient::with_default_tags)
+    pub fn with_default_tags<I, K, V>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.default_tags = collect_tags(tags);
+        self
+    }
+
+    /// See [`InfluxDbClient::with_header`](crate::client::InfluxDbClient::with_header)
+    pub fn with_header<S1, S2>(mut self, name: S1, value: S2) -> Result<Self, InfluxDbError>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        self.headers.push(parse_header(name.into(), value.into())?);
+        Ok(self)
+    }
+
+    /// See [`InfluxDbClient::with_proxy`](crate::client::InfluxDbClient::with_proxy)
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// See [`InfluxDbClient::with_http_proxy`](crate::client::InfluxDbClient::with_http_proxy)
+    pub fn with_http_proxy(self, proxy_url: &str) -> Result<Self, InfluxDbError> {
+        Ok(self.with_proxy(Proxy::http(proxy_url).map_err(proxy_error)?))
+    }
+
+    /// See [`InfluxDbClient::with_https_proxy`](crate::client::InfluxDbClient::with_https_proxy)
+    pub fn with_https_proxy(self, proxy_url: &str) -> Result<Self, InfluxDbError> {
+        Ok(self.with_proxy(Proxy::https(proxy_url).map_err(proxy_error)?))
+    }
+
+    /// See [`InfluxDbClient::use_tls_with_custom_ca`](crate::client::InfluxDbClient::use_tls_with_custom_ca)
+    pub fn use_tls_with_custom_ca(mut self, cert_pem: &[u8]) -> Result<Self, InfluxDbError> {
+        Certificate::from_pem(cert_pem).map_err(certificate_error)?;
+        self.root_certificates.push(cert_pem.to_vec());
+        Ok(self)
+    }
+
+    /// See [`InfluxDbClient::accept_invalid_certs`](crate::client::InfluxDbClient::accept_invalid_certs)
+    pub fn accept_invalid_certs(mut self, allow: bool) -> Self {
+        self.accept_invalid_certs = allow;
+        self
+    }
+
+    /// See [`InfluxDbClient::with_connection_pool_size`](crate::client::InfluxDbClient::with_connection_pool_size)
+    pub fn with_connection_pool_size(mut self, max: usize) -> Self {
+        self.connection_pool_size = Some(max);
+        self
+    }
+
+    /// Uses `client` to send requests to InfluxDB
+    ///
+    /// The client is used as is, so HTTP settings of this builder like timeouts are not applied to
+    /// it, including when they are changed on the built [`InfluxDbClient`](crate::client::InfluxDbClient).
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Configures the `reqwest` [`ClientBuilder`](reqwest::r#async::ClientBuilder) the HTTP client is built with
+    ///
+    /// `configure` is called again whenever the HTTP client is rebuilt, e.g. by
+    /// [`InfluxDbClient::with_user_agent`](crate::client::InfluxDbClient::with_user_agent), so the
+    /// customization is kept. Settings of this builder like timeouts take precedence.
+    pub fn with_http_client_builder<F>(mut self, configure: F) -> Self
+    where
+        F: Fn(ClientBuilder) -> ClientBuilder + Send + Sync + 'static,
+    {
+        self.http_client_config
+            .push(HttpClientConfig(Arc::new(configure)));
+        self
+    }
+
+    /// Builds the [`InfluxDbClient`](crate::client::InfluxDbClient)
+    ///
+    /// # Panics
+    ///
+    /// Like [`reqwest::r#async::Client::new`], this panics if the HTTP client cannot be built,
+    /// e.g. because the TLS backend cannot be initialized.
+    pub fn build(self) -> InfluxDbClient {
+        let timeouts = self.timeouts;
+        let custom_http_client = self.http_client.is_some();
+        let inner_client = match self.http_client {
+            Some(client) => client,
+            None => {
+                let mut builder = Client::builder();
+                for config in &self.http_client_config {
+                    builder = (config.0)(builder);
+                }
+                if let Some(timeout) = timeouts.connect {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = timeouts.request {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(user_agent) = &self.user_agent {
+                    let mut headers = HeaderMap::new();
+                    headers.insert(
+                        USER_AGENT,
+                        user_agent_header(user_agent).expect("User-Agent was validated before"),
+                    );
+                    builder = builder.default_headers(headers);
+                }
+                // Leave compression to the builder unless it has been disabled explicitly
+                if !self.response_compression {
+                    builder = builder.gzip(false);
+                }
+                for proxy in &self.proxies {
+                    builder = builder.proxy(proxy.clone());
+                }
+                for pem in &self.root_certificates {
+                    let certificate =
+                        Certificate::from_pem(pem).expect("certificate was parsed before");
+                    builder = builder.add_root_certificate(certificate);
+                }
+                if self.accept_invalid_certs {
+                    builder = builder.danger_accept_invalid_certs(true);
+                }
+                if let Some(max) = self.connection_pool_size {
+                    builder = builder.max_idle_per_host(max);
+                }
+                builder.build().expect("failed to build HTTP client")
+            }
+        };
+        InfluxDbClient {
+            url: self.url,
+            base_path: self.base_path,
+            database: self.database,
+            auth: self.auth,
+            token: self.token,
+            org: self.org,
+            bucket: self.bucket,
+            version: self.version,
+            timeouts,
+            inner_client,
+            retry_policy: self.retry_policy,
+            rate_limiter: self.rate_limiter,
+            precision: self.precision,
+            chunk_size: self.chunk_size,
+            user_agent: self.user_agent,
+            headers: self.headers,
+            response_compression: self.response_compression,
+            #[cfg(feature = "gzip")]
+            write_compression: self.write_compression,
+            #[cfg(feature = "tracing")]
+            tracing_propagation: self.tracing_propagation,
+            default_tags: self.default_tags,
+            proxies: self.proxies,
+            root_certificates: self.root_certificates,
+            accept_invalid_certs: self.accept_invalid_certs,
+            connection_pool_size: self.connection_pool_size,
+            http_client_config: self.http_client_config,
+            custom_http_client,
+        }
+    }
+}
+
+impl From<InfluxDbClient> for InfluxDbClientBuilder {
+    /// Creates a builder with the configuration of `client`
+    ///
+    /// The customizations of [`with_http_client_builder`](crate::client::InfluxDbClientBuilder::with_http_client_builder)
+    /// are applied again when the builder is built, and a client supplied with
+    /// [`with_http_client`](crate::client::InfluxDbClient::with_http_client) is kept.
+    fn from(client: InfluxDbClient) -> Self {
+        InfluxDbClientBuilder {
+            url: client.url,
+            base_path: client.base_path,
+            database: client.database,
+            auth: client.auth,
+            token: client.token,
+            org: client.org,
+            bucket: client.bucket,
+            version: client.version,
+            timeouts: client.timeouts,
+            retry_policy: client.retry_policy,
+            rate_limiter: client.rate_limiter,
+            precision: client.precision,
+            chunk_size: client.chunk_size,
+            user_agent: client.user_agent,
+            headers: client.headers,
+            response_compression: client.response_compression,
+            #[cfg(feature = "gzip")]
+            write_compression: client.write_compression,
+            #[cfg(feature = "tracing")]
+            tracing_propagation: client.tracing_propagation,
+            default_tags: client.default_tags,
+            proxies: client.proxies,
+            root_certificates: client.root_certificates,
+            accept_invalid_certs: client.accept_invalid_certs,
+            connection_pool_size: client.connection_pool_size,
+            http_client_config: client.http_client_config,
+            http_client: if client.custom_http_client {
+                Some(client.inner_client)
+            } else {
+                None
+            },
+        }
+    }
+}
+
 impl InfluxDbClient {
     /// Instantiates a new [`InfluxDbClient`](crate::client::InfluxDbClient)
     ///
@@ -87,11 +826,22 @@ impl InfluxDbClient {
         S1: ToString,
         S2: ToString,
     {
-        InfluxDbClient {
-            url: url.to_string(),
-            database: database.to_string(),
-            auth: None,
-        }
+        InfluxDbClientBuilder::new(url, database).build()
+    }
+
+    /// Returns a [`InfluxDbClientBuilder`](crate::client::InfluxDbClientBuilder), which allows
+    /// configuring the underlying HTTP client before the [`InfluxDbClient`](crate::client::InfluxDbClient) is built
+    ///
+    /// # Arguments
+    ///
+    ///  * `url`: The URL where InfluxDB is running (ex. `http://localhost:8086`).
+    ///  * `database`: The Database against which queries and writes will be run.
+    pub fn builder<S1, S2>(url: S1, database: S2) -> InfluxDbClientBuilder
+    where
+        S1: ToString,
+        S2: ToString,
+    {
+        InfluxDbClientBuilder::new(url, database)
     }
 
     /// Add authentication/authorization information to [`InfluxDbClient`](crate::client::InfluxDbClient)
@@ -113,245 +863,4819 @@ impl InfluxDbClient {
         S1: ToString,
         S2: ToString,
     {
-        self.auth = Some(InfluxDbAuthentication {
-            username: username.to_string(),
-            password: password.to_string(),
-        });
+        self.auth = Some(InfluxDbAuthentication::new(
+            username.to_string(),
+            password.to_string(),
+        ));
         self
     }
 
-    /// Returns the name of the database the client is using
-    pub fn database_name(&self) -> &str {
-        &self.database
-    }
-
-    /// Returns the URL of the InfluxDB installation the client is using
-    pub fn database_url(&self) -> &str {
-        &self.url
-    }
-
-    /// Pings the InfluxDB Server
-    ///
-    /// Returns a tuple of build type and version number
-    pub fn ping(&self) -> impl Future<Item = (String, String), Error = InfluxDbError> {
-        Client::new()
-            .get(format!("{}/ping", self.url).as_str())
-            .send()
-            .map(|res| {
-                let build = res
-                    .headers()
-                    .get("X-Influxdb-Build")
-                    .unwrap()
-                    .to_str()
-                    .unwrap();
-                let version = res
-                    .headers()
-                    .get("X-Influxdb-Version")
-                    .unwrap()
-                    .to_str()
-                    .unwrap();
-
-                (String::from(build), String::from(version))
-            })
-            .map_err(|err| InfluxDbError::ProtocolError {
-                error: format!("{}", err),
-            })
-    }
-
-    /// Sends a [`InfluxDbReadQuery`](crate::query::read_query::InfluxDbReadQuery) or [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery) to the InfluxDB Server.
+    /// Add token authentication to [`InfluxDbClient`](crate::client::InfluxDbClient), as used by InfluxDB 2.x
     ///
-    /// A version capable of parsing the returned string is available under the [serde_integration](crate::integrations::serde_integration)
+    /// The token is sent as `Authorization: Token <token>` header. When a token is set, username and
+    /// password supplied with [`with_auth`](crate::client::InfluxDbClient::with_auth) are not sent.
     ///
     /// # Arguments
     ///
-    ///  * `q`: Query of type [`InfluxDbReadQuery`](crate::query::read_query::InfluxDbReadQuery) or [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery)
+    /// * token: The API Token for InfluxDB.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use influxdb::client::InfluxDbClient;
-    /// use influxdb::query::{InfluxDbQuery, Timestamp};
     ///
-    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
-    /// let _future = client.query(
-    ///     &InfluxDbQuery::write_query(Timestamp::NOW, "weather")
-    ///         .add_field("temperature", 82)
-    /// );
+    /// let _client = InfluxDbClient::new("http://localhost:9086", "test").with_token("my-token");
     /// ```
-    /// # Errors
+    pub fn with_token<S>(mut self, token: S) -> Self
+    where
+        S: ToString,
+    {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Sets the organization used by InfluxDB 2.x
     ///
-    /// If the function can not finish the query,
-    /// a [`InfluxDbError`] variant will be returned.
+    /// When both organization and bucket are set, write queries are sent to the
+    /// `/api/v2/write` endpoint instead of `/write`. Otherwise, the organization is sent as `org`
+    /// parameter alongside the database, as expected by the InfluxDB 1.x compatibility API.
     ///
-    /// [`InfluxDbError`]: enum.InfluxDbError.html
-    pub fn query<Q>(&self, q: &Q) -> Box<dyn Future<Item = String, Error = InfluxDbError>>
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:9999", "test")
+    ///     .with_org("openvelo")
+    ///     .with_bucket("weather");
+    /// ```
+    pub fn with_org<S>(mut self, org: S) -> Self
     where
-        Q: Any + InfluxDbQuery,
+        S: ToString,
+    {
+        self.org = Some(org.to_string());
+        self
+    }
+
+    /// Sets the path InfluxDB is served at, e.g. by a reverse proxy, which is prepended to the
+    /// paths of all endpoints
+    ///
+    /// Leading and trailing slashes are optional.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// // Queries are sent to https://monitoring.example.com/influxdb/query
+    /// let client = InfluxDbClient::new("https://monitoring.example.com", "test")
+    ///     .with_base_path("/influxdb/");
+    ///
+    /// assert_eq!(client.base_path(), "/influxdb");
+    /// ```
+    pub fn with_base_path(mut self, path: &str) -> Self {
+        self.base_path = normalize_base_path(path);
+        self
+    }
+
+    /// Sets the bucket used by InfluxDB 2.x
+    ///
+    /// See [`with_org`](crate::client::InfluxDbClient::with_org) for when the bucket is used.
+    pub fn with_bucket<S>(mut self, bucket: S) -> Self
+    where
+        S: ToString,
+    {
+        self.bucket = Some(bucket.to_string());
+        self
+    }
+
+    /// Sets the major version of the InfluxDB server, so write queries are sent to its endpoint
+    /// regardless of whether organization and bucket are set
+    ///
+    /// With [`InfluxDbVersion::V1`](crate::client::InfluxDbVersion::V1), write queries are sent
+    /// to `/write`. With [`InfluxDbVersion::V2`](crate::client::InfluxDbVersion::V2) and an
+    /// organization, they are sent to `/api/v2/write`, using the database as bucket unless
+    /// [`with_bucket`](crate::client::InfluxDbClient::with_bucket) is set. Read queries are sent
+    /// to `/query` for both versions, which InfluxDB 2.x serves for compatibility.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::{InfluxDbClient, InfluxDbVersion};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:9999", "weather")
+    ///     .with_org("openvelo")
+    ///     .with_version(InfluxDbVersion::V2 {
+    ///         version: "2.0.0".to_string(),
+    ///     });
+    ///
+    /// assert!(client.version().is_some());
+    /// ```
+    pub fn with_version(mut self, version: InfluxDbVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Detects the version of the InfluxDB server with [`detect_version`](crate::client::InfluxDbClient::detect_version)
+    /// and sets it with [`with_version`](crate::client::InfluxDbClient::with_version)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    /// let client = rt
+    ///     .block_on(InfluxDbClient::new("http://localhost:8086", "test").with_auto_detect())
+    ///     .unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`ping`](crate::client::InfluxDbClient::ping) if the server can not be reached.
+    pub fn with_auto_detect(self) -> impl Future<Item = Self, Error = InfluxDbError> {
+        self.detect_version()
+            .map(move |version| self.with_version(version))
+    }
+
+    /// Returns the version of the InfluxDB server set with [`with_version`](crate::client::InfluxDbClient::with_version)
+    /// or [`with_auto_detect`](crate::client::InfluxDbClient::with_auto_detect)
+    pub fn version(&self) -> Option<&InfluxDbVersion> {
+        self.version.as_ref()
+    }
+
+    /// Sets the time after which a request to InfluxDB is aborted with a
+    /// [`InfluxDbError::TimeoutError`](crate::error::InfluxDbError::TimeoutError)
+    ///
+    /// The timeout covers the whole request, from connecting until the response body has been read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use std::time::Duration;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test")
+    ///     .with_request_timeout(Duration::from_secs(30));
+    /// ```
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.request = Some(timeout);
+        self.rebuild_http_client()
+    }
+
+    /// Sets the time after which connecting to InfluxDB is aborted with a
+    /// [`InfluxDbError::TimeoutError`](crate::error::InfluxDbError::TimeoutError)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use std::time::Duration;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test")
+    ///     .with_connect_timeout(Duration::from_secs(5));
+    /// ```
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.connect = Some(timeout);
+        self.rebuild_http_client()
+    }
+
+    /// Retries requests which failed due to a transient error according to `retry_policy`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::{InfluxDbClient, RetryPolicy};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test")
+    ///     .with_retry_policy(RetryPolicy::default());
+    /// assert!(client.is_ok());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the backoff factor of `retry_policy` is negative, infinite or NaN, a
+    /// [`InfluxDbError::ProtocolError`] will be returned.
+    ///
+    /// [`InfluxDbError::ProtocolError`]: crate::error::InfluxDbError::ProtocolError
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Result<Self, InfluxDbError> {
+        retry_policy.validate()?;
+        self.retry_policy = Some(Arc::new(retry_policy));
+        Ok(self)
+    }
+
+    /// Sends at most `requests_per_second` requests to InfluxDB, delaying requests which would
+    /// exceed the limit
+    ///
+    /// Requests are spaced evenly, e.g. 100ms apart for a limit of 10 requests per second, and
+    /// each retry counts as a request. Clones of the client share the limit. A limit which is
+    /// not positive removes the rate limit.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test").with_rate_limiter(10.0);
+    /// ```
+    pub fn with_rate_limiter(mut self, requests_per_second: f64) -> Self {
+        self.rate_limiter = RateLimiter::new(requests_per_second).map(Arc::new);
+        self
+    }
+
+    /// Sets the format of the timestamps InfluxDB returns for read queries
+    ///
+    /// By default, timestamps are returned as RFC3339 strings. Any other precision makes InfluxDB
+    /// return epoch timestamps in that unit instead. Write queries are not affected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::Precision;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test")
+    ///     .with_precision(Precision::Milliseconds);
+    /// ```
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Makes InfluxDB return the results of `SELECT` queries in chunks of at most `chunk_size` points
+    ///
+    /// Chunked responses consist of one JSON object per line. [`query`](crate::client::InfluxDbClient::query)
+    /// returns them as is, while `json_query` merges them into a single result. To process the chunks
+    /// while they are received, use `query_chunked` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test").with_chunk_size(10_000);
+    /// ```
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Replaces the `reqwest` client used to send requests to InfluxDB
+    ///
+    /// This allows configuring TLS, proxies or connection pools. The client is used as is and
+    /// kept when settings are changed afterwards, so settings which configure the HTTP client,
+    /// such as [`with_request_timeout`](crate::client::InfluxDbClient::with_request_timeout) or
+    /// [`with_user_agent`](crate::client::InfluxDbClient::with_user_agent), have to be set on the
+    /// supplied client instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use reqwest::r#async::Client;
+    ///
+    /// let http_client = Client::builder().build().unwrap();
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test").with_http_client(http_client);
+    /// ```
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.inner_client = client;
+        self.custom_http_client = true;
+        self
+    }
+
+    /// Requests gzip compressed responses with an `Accept-Encoding: gzip` header, which are
+    /// decompressed transparently
+    ///
+    /// Enabled by default. Like [`with_user_agent`](crate::client::InfluxDbClient::with_user_agent),
+    /// this rebuilds the HTTP client, which does not affect a client supplied with
+    /// [`with_http_client`](crate::client::InfluxDbClient::with_http_client).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test").with_response_compression(false);
+    /// ```
+    pub fn with_response_compression(mut self, enabled: bool) -> Self {
+        self.response_compression = enabled;
+        self.rebuild_http_client()
+    }
+
+    /// Compresses the body of write requests with gzip, which reduces the size of large batches
+    ///
+    /// Disabled by default. Requires the `gzip` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test").with_write_compression(true);
+    /// ```
+    #[cfg(feature = "gzip")]
+    pub fn with_write_compression(mut self, enabled: bool) -> Self {
+        self.write_compression = enabled;
+        self
+    }
+
+    /// Propagates the current OpenTelemetry trace context to InfluxDB, e.g. with a `traceparent`
+    /// header, using the propagator registered with `opentelemetry::global::set_text_map_propagator`
+    ///
+    /// [`query`](crate::client::InfluxDbClient::query) and `json_query` additionally record each
+    /// query in a child span named `influxdb.query`, with the `influxdb.query_type` (`read` or
+    /// `write`) and `influxdb.measurement` attributes, and propagate that span instead.
+    ///
+    /// Disabled by default. Requires the `tracing` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test").with_tracing_propagation(true);
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub fn with_tracing_propagation(mut self, enabled: bool) -> Self {
+        self.tracing_propagation = enabled;
+        self
+    }
+
+    /// Adds `tags` to every write query sent by [`query`](crate::client::InfluxDbClient::query)
+    /// and [`write_many`](crate::client::InfluxDbClient::write_many), e.g. to tag all points with
+    /// the service or host writing them
+    ///
+    /// Tags set on a write query take precedence over default tags with the same key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test")
+    ///     .with_default_tags(vec![("service", "api"), ("env", "production")]);
+    /// let request = client
+    ///     .dry_run()
+    ///     .query(
+    ///         &InfluxDbQuery::write_query(Timestamp::HOURS(1), "requests")
+    ///             .add_tag("env", "staging")
+    ///             .add_field("count", 1),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     request.body,
+    ///     Some("requests,env=staging,service=api count=1i 1".to_string())
+    /// );
+    /// ```
+    pub fn with_default_tags<I, K, V>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.default_tags = collect_tags(tags);
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request, e.g. to identify the application
+    /// in proxies or InfluxDB's logs
+    ///
+    /// This rebuilds the HTTP client, keeping the customizations of
+    /// [`InfluxDbClientBuilder::with_http_client_builder`](crate::client::InfluxDbClientBuilder::with_http_client_builder).
+    /// A client supplied with [`with_http_client`](crate::client::InfluxDbClient::with_http_client)
+    /// is kept as is, so set the header on the supplied client instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test")
+    ///     .with_user_agent(format!("my-app/1.0 {}", InfluxDbClient::default_user_agent()));
+    /// assert!(client.is_ok());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `user_agent` is not a valid header value, e.g. because it contains a newline, a
+    /// [`InfluxDbError::ProtocolError`] will be returned.
+    ///
+    /// [`InfluxDbError::ProtocolError`]: crate::error::InfluxDbError::ProtocolError
+    pub fn with_user_agent<S>(self, user_agent: S) -> Result<Self, InfluxDbError>
+    where
+        S: Into<String>,
+    {
+        Ok(InfluxDbClientBuilder::from(self)
+            .with_user_agent(user_agent)?
+            .build())
+    }
+
+    /// Sends the header `name` with `value` in every request, e.g. an API key required by a
+    /// gateway in front of InfluxDB
+    ///
+    /// Adding a header with the same name again sends both values.
+    ///
+    /// # Errors
+    ///
+    /// If `name` is not a valid header name or `value` is not a valid header value, a
+    /// [`InfluxDbError::ProtocolError`] will be returned.
+    ///
+    /// [`InfluxDbError::ProtocolError`]: crate::error::InfluxDbError::ProtocolError
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test")
+    ///     .with_header("X-API-Key", "secret")
+    ///     .unwrap();
+    /// ```
+    pub fn with_header<S1, S2>(mut self, name: S1, value: S2) -> Result<Self, InfluxDbError>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
     {
-        use futures::future;
+        self.headers.push(parse_header(name.into(), value.into())?);
+        Ok(self)
+    }
+
+    /// Sends requests through `proxy`, e.g. a corporate HTTP proxy
+    ///
+    /// Proxies are tried in the order they were added. Like
+    /// [`with_user_agent`](crate::client::InfluxDbClient::with_user_agent), this rebuilds the HTTP
+    /// client, which does not affect a client supplied with [`with_http_client`](crate::client::InfluxDbClient::with_http_client).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use reqwest::Proxy;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test")
+    ///     .with_proxy(Proxy::all("http://proxy.example.com:3128").unwrap());
+    /// ```
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self.rebuild_http_client()
+    }
+
+    /// Sends requests to `http` URLs through the proxy at `proxy_url`
+    ///
+    /// # Errors
+    ///
+    /// If `proxy_url` is not a valid URL, a [`InfluxDbError::UrlConstructionError`] will be returned.
+    ///
+    /// [`InfluxDbError::UrlConstructionError`]: crate::error::InfluxDbError::UrlConstructionError
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test")
+    ///     .with_http_proxy("http://proxy.example.com:3128")
+    ///     .unwrap();
+    /// ```
+    pub fn with_http_proxy(self, proxy_url: &str) -> Result<Self, InfluxDbError> {
+        Ok(self.with_proxy(Proxy::http(proxy_url).map_err(proxy_error)?))
+    }
+
+    /// Sends requests to `https` URLs through the proxy at `proxy_url`
+    ///
+    /// # Errors
+    ///
+    /// If `proxy_url` is not a valid URL, a [`InfluxDbError::UrlConstructionError`] will be returned.
+    ///
+    /// [`InfluxDbError::UrlConstructionError`]: crate::error::InfluxDbError::UrlConstructionError
+    pub fn with_https_proxy(self, proxy_url: &str) -> Result<Self, InfluxDbError> {
+        Ok(self.with_proxy(Proxy::https(proxy_url).map_err(proxy_error)?))
+    }
+
+    /// Trusts the PEM encoded CA certificate `cert_pem` in addition to the system's root
+    /// certificates, e.g. for InfluxDB installations with a self-signed or private CA certificate
+    ///
+    /// Like [`with_user_agent`](crate::client::InfluxDbClient::with_user_agent), this rebuilds the
+    /// HTTP client, which does not affect a client supplied with [`with_http_client`](crate::client::InfluxDbClient::with_http_client).
+    ///
+    /// # Errors
+    ///
+    /// If `cert_pem` is not a PEM encoded certificate, a [`InfluxDbError::ProtocolError`] will be returned.
+    ///
+    /// [`InfluxDbError::ProtocolError`]: crate::error::InfluxDbError::ProtocolError
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let cert_pem = std::fs::read("ca.pem").unwrap();
+    /// let _client = InfluxDbClient::new("https://influxdb.internal:8086", "test")
+    ///     .use_tls_with_custom_ca(&cert_pem)
+    ///     .unwrap();
+    /// ```
+    pub fn use_tls_with_custom_ca(self, cert_pem: &[u8]) -> Result<Self, InfluxDbError> {
+        Ok(InfluxDbClientBuilder::from(self)
+            .use_tls_with_custom_ca(cert_pem)?
+            .build())
+    }
+
+    /// Disables the verification of TLS certificates if `allow` is `true`
+    ///
+    /// This accepts any certificate, including expired ones and ones for other hosts, which makes
+    /// connections vulnerable to man-in-the-middle attacks. Only use it for testing, and prefer
+    /// [`use_tls_with_custom_ca`](crate::client::InfluxDbClient::use_tls_with_custom_ca) for
+    /// self-signed certificates. This rebuilds the HTTP client.
+    pub fn accept_invalid_certs(mut self, allow: bool) -> Self {
+        self.accept_invalid_certs = allow;
+        self.rebuild_http_client()
+    }
+
+    /// Keeps at most `max` idle connections to InfluxDB open for reuse
+    ///
+    /// Fewer idle connections save file descriptors, while more avoid opening new connections
+    /// when many requests are sent concurrently, e.g. by write pipelines. By default, the number
+    /// of idle connections is not limited. A `max` of 0 disables reusing connections. This
+    /// rebuilds the HTTP client.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let _client = InfluxDbClient::new("http://localhost:8086", "test").with_connection_pool_size(8);
+    /// ```
+    pub fn with_connection_pool_size(mut self, max: usize) -> Self {
+        self.connection_pool_size = Some(max);
+        self.rebuild_http_client()
+    }
+
+    /// Returns the name and version of this library, e.g. `influxdb-rust/0.0.4`, for use in a
+    /// `User-Agent` header
+    pub fn default_user_agent() -> &'static str {
+        concat!("influxdb-rust/", env!("CARGO_PKG_VERSION"))
+    }
+
+    /// Returns a client which returns the requests this client would send instead of sending them
+    ///
+    /// Useful to inspect the URL, headers and body of a request, e.g. to debug authentication or
+    /// the line protocol of write queries without a running InfluxDB.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let request = client
+    ///     .dry_run()
+    ///     .query(&InfluxDbQuery::write_query(Timestamp::HOURS(1), "weather").add_field("temperature", 82))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(request.body, Some("weather temperature=82i 1".to_string()));
+    /// ```
+    pub fn dry_run(&self) -> DryRunClient {
+        DryRunClient::new(self.clone())
+    }
+
+    /// Returns a copy of the client which uses the database `database`
+    ///
+    /// The copy shares the connection pool of this client.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let other_client = client.clone_with_database("other");
+    ///
+    /// assert_eq!(other_client.database_name(), "other");
+    /// ```
+    pub fn clone_with_database<S>(&self, database: S) -> Self
+    where
+        S: Into<String>,
+    {
+        InfluxDbClient {
+            database: database.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of the client which sends requests to the InfluxDB installation at `url`
+    ///
+    /// The copy shares the connection pool of this client.
+    pub fn clone_with_url<S>(&self, url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        InfluxDbClient {
+            url: url.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the username and password the client authenticates with, if any
+    pub fn auth(&self) -> Option<&InfluxDbAuthentication> {
+        self.auth.as_ref()
+    }
+
+    /// Returns the name of the database the client is using
+    pub fn database_name(&self) -> &str {
+        &self.database
+    }
+
+    /// Returns the URL of the InfluxDB installation the client is using
+    pub fn database_url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the path set with [`with_base_path`](crate::client::InfluxDbClient::with_base_path),
+    /// without trailing slash
+    pub fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    /// Returns the URL of `endpoint`, e.g. `/query`, including the base path
+    pub(crate) fn endpoint_url(&self, endpoint: &str) -> String {
+        format!(
+            "{}{}{}",
+            self.url.trim_end_matches('/'),
+            self.base_path,
+            endpoint
+        )
+    }
+
+    /// Returns the InfluxDB 2.x organization set with [`with_org`](crate::client::InfluxDbClient::with_org)
+    pub fn org(&self) -> Option<&str> {
+        self.org.as_deref()
+    }
+
+    /// Replaces the inner `reqwest` client with one using the current HTTP settings
+    fn rebuild_http_client(self) -> Self {
+        InfluxDbClientBuilder::from(self).build()
+    }
+
+    /// Creates a request against InfluxDB with the authentication headers of this client attached
+    pub(crate) fn request<U>(&self, method: Method, url: U) -> RequestBuilder
+    where
+        U: IntoUrl,
+    {
+        let mut request = self.inner_client.request(method, url);
+        for (name, value) in &self.headers {
+            request = request.header(name.clone(), value.clone());
+        }
+        match &self.token {
+            Some(token) => request.header(AUTHORIZATION, format!("Token {}", token)),
+            None => request,
+        }
+    }
+
+    /// Builds the URL `read_query` is sent to
+    ///
+    /// Requests epoch timestamps if a [`Precision`](crate::query::Precision) other than RFC3339
+    /// is set and `read_query` returns data. `epoch` overrides the precision of the client.
+    pub(crate) fn read_url(
+        &self,
+        read_query: &str,
+        epoch: Option<Precision>,
+    ) -> Result<Url, reqwest::UrlError> {
+        let precision = epoch.unwrap_or(self.precision);
+        let basic_parameters: Vec<(String, String)> = self.into();
+        let mut url =
+            Url::parse_with_params(self.endpoint_url("/query").as_str(), basic_parameters)?;
+        url.query_pairs_mut().append_pair("q", read_query);
+        if let (true, Some(epoch)) = (is_data_query(read_query), precision.epoch()) {
+            url.query_pairs_mut().append_pair("epoch", epoch);
+        }
+        if let (true, Some(chunk_size)) = (self.is_chunked(read_query), self.chunk_size) {
+            url.query_pairs_mut()
+                .append_pair("chunked", "true")
+                .append_pair("chunk_size", &chunk_size.to_string());
+        }
+        Ok(url)
+    }
+
+    /// Returns `true` if InfluxDB responds to `read_query` in chunks
+    pub(crate) fn is_chunked(&self, read_query: &str) -> bool {
+        self.chunk_size.is_some() && read_query.contains("SELECT")
+    }
+
+    /// Builds the URL write queries with the given precision and retention policy are sent to
+    ///
+    /// Targets the InfluxDB 2.x endpoint `/api/v2/write` when both organization and bucket
+    /// are set, otherwise the InfluxDB 1.x endpoint `/write`. A version set with
+    /// [`with_version`](crate::client::InfluxDbClient::with_version) overrides this, see there.
+    /// Retention policies only exist in InfluxDB 1.x, so `retention_policy` is ignored for
+    /// InfluxDB 2.x.
+    pub(crate) fn write_url(
+        &self,
+        precision: &str,
+        retention_policy: Option<&str>,
+    ) -> Result<Url, reqwest::UrlError> {
+        let v2_target = match (&self.version, &self.org, &self.bucket) {
+            (Some(InfluxDbVersion::V1 { .. }), _, _) => None,
+            (Some(InfluxDbVersion::V2 { .. }), Some(org), bucket) => {
+                Some((org, bucket.as_ref().unwrap_or(&self.database)))
+            }
+            (None, Some(org), Some(bucket)) => Some((org, bucket)),
+            _ => None,
+        };
+        match v2_target {
+            Some((org, bucket)) => {
+                let mut url = Url::parse_with_params(
+                    self.endpoint_url("/api/v2/write").as_str(),
+                    &[("org", org), ("bucket", bucket)],
+                )?;
+                // InfluxDB 2.x spells microseconds `us` and rejects an empty precision
+                match precision {
+                    "" => {}
+                    "u" => {
+                        url.query_pairs_mut().append_pair("precision", "us");
+                    }
+                    _ => {
+                        url.query_pairs_mut().append_pair("precision", precision);
+                    }
+                }
+                Ok(url)
+            }
+            _ => {
+                let basic_parameters: Vec<(String, String)> = self.into();
+                let mut url =
+                    Url::parse_with_params(self.endpoint_url("/write").as_str(), basic_parameters)?;
+                url.query_pairs_mut().append_pair("precision", precision);
+                if let Some(retention_policy) = retention_policy {
+                    url.query_pairs_mut().append_pair("rp", retention_policy);
+                }
+                Ok(url)
+            }
+        }
+    }
+
+    /// Builds the URL Flux queries are sent to
+    ///
+    /// Flux queries are only supported by InfluxDB 2.x, so this fails with a
+    /// [`InfluxDbError::InvalidQueryError`](crate::error::InfluxDbError::InvalidQueryError) unless
+    /// both token and organization are set.
+    pub(crate) fn flux_url(&self) -> Result<Url, InfluxDbError> {
+        let org = match (&self.token, &self.org) {
+            (Some(_), Some(org)) => org,
+            _ => {
+                return Err(InfluxDbError::InvalidQueryError {
+                    error: String::from("flux queries require a token and an organization"),
+                });
+            }
+        };
+        Url::parse_with_params(self.endpoint_url("/api/v2/query").as_str(), &[("org", org)])
+            .map_err(|err| InfluxDbError::UrlConstructionError {
+                error: format!("{}", err),
+            })
+    }
+
+    /// Builds the URL of the `/api/v2/buckets` endpoint, or of the bucket with the ID `bucket_id`
+    ///
+    /// Buckets only exist in InfluxDB 2.x, so this fails with a
+    /// [`InfluxDbError::InvalidQueryError`](crate::error::InfluxDbError::InvalidQueryError) unless
+    /// a token is set.
+    #[cfg(feature = "use-serde")]
+    pub(crate) fn buckets_url(
+        &self,
+        bucket_id: Option<&str>,
+        parameters: &[(&str, String)],
+    ) -> Result<Url, InfluxDbError> {
+        if self.token.is_none() {
+            return Err(InfluxDbError::InvalidQueryError {
+                error: String::from("managing buckets requires a token"),
+            });
+        }
+        let mut url = Url::parse(&self.endpoint_url("/api/v2/buckets")).map_err(|err| {
+            InfluxDbError::UrlConstructionError {
+                error: format!("{}", err),
+            }
+        })?;
+        if let Some(bucket_id) = bucket_id {
+            if let Ok(mut segments) = url.path_segments_mut() {
+                segments.push(bucket_id);
+            }
+        }
+        if !parameters.is_empty() {
+            url.query_pairs_mut().extend_pairs(parameters);
+        }
+        Ok(url)
+    }
+
+    /// Sends the Flux `script` to InfluxDB, asking for a response of the `accept` media type
+    pub(crate) fn send_flux_query(
+        &self,
+        script: &str,
+        accept: &'static str,
+    ) -> impl Future<Item = String, Error = InfluxDbError> {
+        let url = match self.flux_url() {
+            Ok(url) => url,
+            Err(err) => return future::Either::B(future::err(err)),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/vnd.flux"),
+        );
+        headers.insert(ACCEPT, HeaderValue::from_static(accept));
+        future::Either::A(
+            self.send_request_with_headers(Method::POST, url, headers, Some(script.to_string()))
+                .and_then(|(_, body)| utf8_body(&body)),
+        )
+    }
+
+    /// Pings the InfluxDB Server
+    ///
+    /// Returns the build type and version of the server
+    ///
+    /// # Errors
+    ///
+    /// If the server responds with an unsuccessful status code, a [`InfluxDbError::HttpError`]
+    /// will be returned. If it does not report its version, a [`InfluxDbError::ProtocolError`]
+    /// will be returned.
+    ///
+    /// [`InfluxDbError::HttpError`]: crate::error::InfluxDbError::HttpError
+    /// [`InfluxDbError::ProtocolError`]: crate::error::InfluxDbError::ProtocolError
+    pub fn ping(&self) -> impl Future<Item = PingResponse, Error = InfluxDbError> {
+        let timeouts = self.timeouts;
+        self.request(Method::GET, self.endpoint_url("/ping").as_str())
+            .send()
+            .map_err(move |err| match timeouts.elapsed(&err) {
+                Some(elapsed) => InfluxDbError::TimeoutError { elapsed },
+                None => InfluxDbError::ProtocolError {
+                    error: format!("{}", err),
+                },
+            })
+            .and_then(|res| {
+                if !res.status().is_success() {
+                    return Err(InfluxDbError::HttpError {
+                        status: res.status().as_u16(),
+                        body: String::new(),
+                    });
+                }
+                let header = |name| {
+                    res.headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .map(String::from)
+                };
+                let build = header("X-Influxdb-Build").unwrap_or_default();
+                match header("X-Influxdb-Version") {
+                    Some(version) => Ok(PingResponse { build, version }),
+                    None => Err(InfluxDbError::ProtocolError {
+                        error: "response is missing the X-Influxdb-Version header".to_string(),
+                    }),
+                }
+            })
+    }
+
+    /// Determines whether the server runs InfluxDB 1.x or 2.x from the headers of its response to
+    /// [`ping`](crate::client::InfluxDbClient::ping)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.detect_version();
+    /// ```
+    pub fn detect_version(&self) -> impl Future<Item = InfluxDbVersion, Error = InfluxDbError> {
+        self.ping().map(InfluxDbVersion::from)
+    }
+
+    /// Checks whether the InfluxDB server is healthy using [`ping`](crate::client::InfluxDbClient::ping)
+    ///
+    /// Returns `true` if the server responds successfully and `false` if it responds with an
+    /// unsuccessful status code, so it is reachable but unhealthy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.is_healthy();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the server cannot be reached, or does not respond like InfluxDB, the error of
+    /// [`ping`](crate::client::InfluxDbClient::ping) will be returned.
+    pub fn is_healthy(&self) -> impl Future<Item = bool, Error = InfluxDbError> {
+        self.ping().then(|result| match result {
+            Ok(_) => Ok(true),
+            Err(InfluxDbError::HttpError { .. }) => Ok(false),
+            Err(err) => Err(err),
+        })
+    }
+
+    /// Sends a [`InfluxDbReadQuery`](crate::query::read_query::InfluxDbReadQuery) or [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery) to the InfluxDB Server.
+    ///
+    /// A version capable of parsing the returned string is available under the [serde_integration](crate::integrations::serde_integration)
+    ///
+    /// # Arguments
+    ///
+    ///  * `q`: Query of type [`InfluxDbReadQuery`](crate::query::read_query::InfluxDbReadQuery) or [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.query(
+    ///     &InfluxDbQuery::write_query(Timestamp::NOW, "weather")
+    ///         .add_field("temperature", 82)
+    /// );
+    /// ```
+    /// # Errors
+    ///
+    /// If the function can not finish the query,
+    /// a [`InfluxDbError`] variant will be returned.
+    ///
+    /// [`InfluxDbError`]: enum.InfluxDbError.html
+    pub fn query<Q>(&self, q: &Q) -> Box<dyn Future<Item = String, Error = InfluxDbError>>
+    where
+        Q: Any + InfluxDbQuery,
+    {
+        let (method, url, body) = match self.query_request(q) {
+            Ok(request) => request,
+            Err(err) => return Box::new(future::err::<String, InfluxDbError>(err)),
+        };
+        Box::new(
+            self.send_query_request(q, method, url, HeaderMap::new(), body)
+                .and_then(|(_, body)| response_to_string(&body)),
+        )
+    }
+
+    /// Sends `q` like [`query`](crate::client::InfluxDbClient::query), letting InfluxDB replace
+    /// the placeholders `$key` of the query with the values of `params`
+    ///
+    /// Unlike [`InfluxDbReadQuery::bind`](crate::query::read_query::InfluxDbReadQuery::bind),
+    /// which replaces placeholders when the query is built, the values are sent as JSON in the
+    /// `params` parameter and bound by InfluxDB, which requires InfluxDB 1.3 or later.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::InfluxDbQuery;
+    /// use std::collections::HashMap;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let mut params = HashMap::new();
+    /// params.insert("location".to_string(), "berlin".into());
+    /// let _future = client.query_with_params(
+    ///     &InfluxDbQuery::raw_read_query("SELECT * FROM weather WHERE location = $location"),
+    ///     params,
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If a value cannot be represented in JSON, i.e. a float which is not finite or
+    /// [`Timestamp::NOW`](crate::query::Timestamp::NOW), a [`InfluxDbError::InvalidQueryError`]
+    /// will be returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn query_with_params(
+        &self,
+        q: &InfluxDbReadQuery,
+        params: HashMap<String, InfluxDbValue>,
+    ) -> Box<dyn Future<Item = String, Error = InfluxDbError>> {
+        let (method, url, body) = match self
+            .query_request(q)
+            .and_then(|(method, url, body)| Ok((method, with_params(url, &params)?, body)))
+        {
+            Ok(request) => request,
+            Err(err) => return Box::new(future::err::<String, InfluxDbError>(err)),
+        };
+        Box::new(
+            self.send_query_request(q, method, url, HeaderMap::new(), body)
+                .and_then(|(_, body)| response_to_string(&body)),
+        )
+    }
+
+    /// Sends the request of `q` like [`send_request_with_headers`](crate::client::InfluxDbClient::send_request_with_headers)
+    ///
+    /// With the `tracing` feature, the query is logged, and recorded in an `influxdb.query` span
+    /// if tracing propagation is enabled.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn send_query_request<Q>(
+        &self,
+        q: &Q,
+        method: Method,
+        url: Url,
+        headers: HeaderMap,
+        body: Option<String>,
+    ) -> Box<dyn Future<Item = (StatusCode, Chunk), Error = InfluxDbError>>
+    where
+        Q: Any + InfluxDbQuery,
+    {
+        #[cfg(feature = "tracing")]
+        {
+            trace::instrument(
+                trace::QueryInfo::new(q),
+                self.tracing_propagation,
+                headers,
+                |headers| self.send_request_with_headers(method, url, headers, body),
+            )
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            self.send_request_with_headers(method, url, headers, body)
+        }
+    }
+
+    /// Builds the method, URL and body of the request [`query`](crate::client::InfluxDbClient::query)
+    /// sends for `q`
+    pub(crate) fn query_request<Q>(
+        &self,
+        q: &Q,
+    ) -> Result<(Method, Url, Option<String>), InfluxDbError>
+    where
+        Q: Any + InfluxDbQuery,
+    {
+        let query = q.build().map_err(|err| InfluxDbError::InvalidQueryError {
+            error: format!("{}", err),
+        })?;
+
+        match q.get_type() {
+            QueryType::ReadQuery => {
+                let read_query = query.into_string();
+
+                let epoch = (q as &dyn Any)
+                    .downcast_ref::<InfluxDbReadQuery>()
+                    .and_then(InfluxDbReadQuery::get_epoch);
+                let url = self.read_url(&read_query, epoch).map_err(|err| {
+                    InfluxDbError::UrlConstructionError {
+                        error: format!("{}", err),
+                    }
+                })?;
+
+                if is_data_query(&read_query) {
+                    Ok((Method::GET, url, None))
+                } else {
+                    Ok((Method::POST, url, None))
+                }
+            }
+            QueryType::WriteQuery => {
+                // The precision and retention policy are only known for `InfluxDbWriteQuery`
+                let write_query = (q as &dyn Any)
+                    .downcast_ref::<InfluxDbWriteQuery>()
+                    .ok_or_else(|| InfluxDbError::InvalidQueryError {
+                        error: String::from("write queries must be of type InfluxDbWriteQuery"),
+                    })?;
+                let url = self
+                    .write_url(
+                        &write_query.get_precision(),
+                        write_query.get_retention_policy(),
+                    )
+                    .map_err(|err| InfluxDbError::InvalidQueryError {
+                        error: format!("{}", err),
+                    })?;
+                let line = write_query
+                    .build_with_default_tags(&self.default_tags)
+                    .map_err(|err| InfluxDbError::InvalidQueryError {
+                        error: format!("{}", err),
+                    })?;
+                Ok((Method::POST, url, Some(line.into_string())))
+            }
+        }
+    }
+
+    /// Sends a query like [`query`](crate::client::InfluxDbClient::query), but fails with a
+    /// [`InfluxDbError::TimeoutError`](crate::error::InfluxDbError::TimeoutError) if it does not
+    /// complete within `timeout`
+    ///
+    /// Unlike [`with_request_timeout`](crate::client::InfluxDbClient::with_request_timeout), this only
+    /// applies to a single query and includes the time spent on retries.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::InfluxDbQuery;
+    /// use std::time::Duration;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.query_with_timeout(
+    ///     &InfluxDbQuery::raw_read_query("SELECT * FROM weather"),
+    ///     Duration::from_millis(500),
+    /// );
+    /// ```
+    pub fn query_with_timeout<Q>(
+        &self,
+        q: &Q,
+        timeout: Duration,
+    ) -> impl Future<Item = String, Error = InfluxDbError>
+    where
+        Q: Any + InfluxDbQuery,
+    {
+        with_timeout(self.query(q), timeout)
+    }
+
+    /// Sends multiple [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery)s to the InfluxDB Server in a single request
+    ///
+    /// All queries must use the same timestamp precision and retention policy.
+    ///
+    /// # Arguments
+    ///
+    ///  * `queries`: The write queries to send
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.write_many(vec![
+    ///     InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather").add_field("temperature", 82),
+    ///     InfluxDbQuery::write_query(Timestamp::HOURS(12), "weather").add_field("temperature", 84),
+    /// ]);
+    /// ```
+    /// # Errors
+    ///
+    /// If no queries are supplied, any query is invalid or the queries use different precisions
+    /// or retention policies, a [`InfluxDbError::InvalidQueryError`] will be returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn write_many<I>(&self, queries: I) -> Box<dyn Future<Item = String, Error = InfluxDbError>>
+    where
+        I: IntoIterator<Item = InfluxDbWriteQuery>,
+    {
+        let (url, body) = match self.write_many_request(queries) {
+            Ok(request) => request,
+            Err(err) => return Box::new(future::err::<String, InfluxDbError>(err)),
+        };
+        Box::new(
+            self.send_request(Method::POST, url, Some(body))
+                .and_then(|(_, body)| response_to_string(&body)),
+        )
+    }
+
+    /// Builds the URL and body of the request [`write_many`](crate::client::InfluxDbClient::write_many)
+    /// sends for `queries`
+    pub(crate) fn write_many_request<I>(&self, queries: I) -> Result<(Url, String), InfluxDbError>
+    where
+        I: IntoIterator<Item = InfluxDbWriteQuery>,
+    {
+        let mut precision: Option<String> = None;
+        let mut retention_policy: Option<String> = None;
+        let mut lines = Vec::new();
+        for query in queries {
+            let query_precision = query.get_precision();
+            let query_retention_policy = query.get_retention_policy().map(String::from);
+            match &precision {
+                Some(precision) if precision != &query_precision => {
+                    return Err(InfluxDbError::InvalidQueryError {
+                        error: format!(
+                            "all queries must use the same precision, found \"{}\" and \"{}\"",
+                            precision, query_precision
+                        ),
+                    });
+                }
+                Some(_) if retention_policy != query_retention_policy => {
+                    return Err(InfluxDbError::InvalidQueryError {
+                        error: format!(
+                            "all queries must use the same retention policy, found {:?} and {:?}",
+                            retention_policy, query_retention_policy
+                        ),
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    precision = Some(query_precision);
+                    retention_policy = query_retention_policy;
+                }
+            }
+            let line = query
+                .build_with_default_tags(&self.default_tags)
+                .map_err(|err| InfluxDbError::InvalidQueryError {
+                    error: format!("{}", err),
+                })?;
+            lines.push(line.into_string());
+        }
+
+        let precision = precision.ok_or_else(|| InfluxDbError::InvalidQueryError {
+            error: "no write queries supplied".to_string(),
+        })?;
+        let url = self
+            .write_url(&precision, retention_policy.as_deref())
+            .map_err(|err| InfluxDbError::InvalidQueryError {
+                error: format!("{}", err),
+            })?;
+        Ok((url, lines.join("\n")))
+    }
+
+    /// Sends `line_protocol` to the InfluxDB Server as is, e.g. to import data which is already
+    /// formatted as line protocol
+    ///
+    /// The body is neither validated nor escaped, and default tags are not added.
+    ///
+    /// # Arguments
+    ///
+    ///  * `line_protocol`: Points in line protocol, separated by newlines
+    ///  * `precision`: The precision of the timestamps of the points. With
+    ///    [`Precision::Rfc3339`](crate::query::Precision::Rfc3339), InfluxDB expects nanoseconds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::Precision;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.write_line_protocol(
+    ///     "weather,location=berlin temperature=82 1\nweather,location=paris temperature=84 1",
+    ///     Precision::Seconds,
+    /// );
+    /// ```
+    pub fn write_line_protocol(
+        &self,
+        line_protocol: &str,
+        precision: Precision,
+    ) -> Box<dyn Future<Item = String, Error = InfluxDbError>> {
+        let url = match self.write_url(precision.epoch().unwrap_or(""), None) {
+            Ok(url) => url,
+            Err(err) => {
+                return Box::new(future::err::<String, InfluxDbError>(
+                    InfluxDbError::InvalidQueryError {
+                        error: format!("{}", err),
+                    },
+                ))
+            }
+        };
+        Box::new(
+            self.send_request(Method::POST, url, Some(line_protocol.to_string()))
+                .and_then(|(_, body)| response_to_string(&body)),
+        )
+    }
+
+    /// Sends `batch` to the InfluxDB Server in a single request, like
+    /// [`write_many`](crate::client::InfluxDbClient::write_many)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::write_batch::InfluxDbWriteBatch;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let batch = InfluxDbWriteBatch::from(vec![
+    ///     InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather").add_field("temperature", 82),
+    ///     InfluxDbQuery::write_query(Timestamp::HOURS(12), "weather").add_field("temperature", 84),
+    /// ]);
+    /// let _future = client.write_batch(batch);
+    /// ```
+    /// # Errors
+    ///
+    /// Fails like [`write_many`](crate::client::InfluxDbClient::write_many), e.g. if the batch is empty.
+    pub fn write_batch(
+        &self,
+        batch: InfluxDbWriteBatch,
+    ) -> Box<dyn Future<Item = String, Error = InfluxDbError>> {
+        self.write_many(batch)
+    }
+
+    /// Sends `batch` in requests of at most `chunk_size` write queries and returns the responses
+    /// to all requests
+    ///
+    /// Requests are sent one at a time, in the order of the batch. If a request fails, the
+    /// remaining chunks are not sent, while the chunks sent before remain written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::write_batch::InfluxDbWriteBatch;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let batch: InfluxDbWriteBatch = (0..10_000)
+    ///     .map(|i| InfluxDbQuery::write_query(Timestamp::SECONDS(i), "weather").add_field("temperature", 82))
+    ///     .collect();
+    /// let _future = client.write_batch_chunked(batch, 5_000);
+    /// ```
+    /// # Errors
+    ///
+    /// If `chunk_size` is 0, a [`InfluxDbError::InvalidQueryError`] will be returned. Otherwise,
+    /// this fails like [`write_batch`](crate::client::InfluxDbClient::write_batch) for the first
+    /// chunk which cannot be written.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn write_batch_chunked(
+        &self,
+        batch: InfluxDbWriteBatch,
+        chunk_size: usize,
+    ) -> Box<dyn Future<Item = Vec<String>, Error = InfluxDbError>> {
+        if chunk_size == 0 {
+            return Box::new(future::err(InfluxDbError::InvalidQueryError {
+                error: "chunk size must not be 0".to_string(),
+            }));
+        }
+        if batch.is_empty() {
+            return Box::new(self.write_batch(batch).map(|response| vec![response]));
+        }
+        let client = self.clone();
+        Box::new(
+            futures::stream::iter_ok(batch.chunks(chunk_size))
+                .and_then(move |chunk| client.write_batch(chunk))
+                .collect(),
+        )
+    }
+
+    /// Validates all `queries` and sends them in a single request, so that either all or none of
+    /// them are written
+    ///
+    /// InfluxDB does not support transactions. It rejects the whole request if a line cannot be
+    /// parsed, but may still write the other lines, e.g. if a field has a different type than in
+    /// previous writes. Like [`write_many`](crate::client::InfluxDbClient::write_many), all
+    /// queries must use the same timestamp precision and retention policy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.transaction_write(vec![
+    ///     InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather").add_field("temperature", 82),
+    ///     InfluxDbQuery::write_query(Timestamp::HOURS(11), "humidity").add_field("percent", 48),
+    /// ]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If a query is invalid according to [`validate`](crate::query::write_query::InfluxDbWriteQuery::validate),
+    /// a [`InfluxDbError::BatchError`] with its position in `queries` will be returned and nothing
+    /// is sent. If InfluxDB rejects the request with `HTTP 400 Bad Request` and reports the line
+    /// it failed on, a [`InfluxDbError::PartialWriteError`] with the position of that line in
+    /// `queries` will be returned. Otherwise, this fails like
+    /// [`write_many`](crate::client::InfluxDbClient::write_many).
+    ///
+    /// [`InfluxDbError::BatchError`]: crate::error::InfluxDbError::BatchError
+    /// [`InfluxDbError::PartialWriteError`]: crate::error::InfluxDbError::PartialWriteError
+    pub fn transaction_write(
+        &self,
+        queries: Vec<InfluxDbWriteQuery>,
+    ) -> Box<dyn Future<Item = (), Error = InfluxDbError>> {
+        for (index, query) in queries.iter().enumerate() {
+            if let Err(err) = query.validate() {
+                return Box::new(future::err(InfluxDbError::BatchError {
+                    index,
+                    inner: Box::new(err),
+                }));
+            }
+        }
+        let (url, body) = match self.write_many_request(queries) {
+            Ok(request) => request,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        let lines = body.lines().map(String::from).collect::<Vec<String>>();
+        Box::new(
+            self.send_request(Method::POST, url, Some(body))
+                .and_then(|(_, body)| response_to_string(&body))
+                .map(|_| ())
+                .map_err(move |err| match err {
+                    InfluxDbError::HttpError { status: 400, body } => {
+                        match failed_line(&lines, &body) {
+                            Some(failed_line) => InfluxDbError::PartialWriteError {
+                                failed_line,
+                                error: body,
+                            },
+                            None => InfluxDbError::HttpError { status: 400, body },
+                        }
+                    }
+                    err => err,
+                }),
+        )
+    }
+
+    /// Sends the write queries of `input` in batches and returns a stream of the responses to each batch
+    ///
+    /// A batch is sent once it contains `batch_size` queries, or `flush_interval` after its first
+    /// query was received, whichever comes first. The remaining queries are sent when `input` ends.
+    /// Batches are sent one at a time, and like [`write_many`](crate::client::InfluxDbClient::write_many),
+    /// all queries of a batch must use the same timestamp precision and retention policy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures::stream;
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::error::InfluxDbError;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    /// use std::time::Duration;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let queries = (0..100).map(|i| {
+    ///     InfluxDbQuery::write_query(Timestamp::SECONDS(i), "weather").add_field("temperature", 82)
+    /// });
+    /// let _responses = client.write_stream(
+    ///     10,
+    ///     Duration::from_secs(1),
+    ///     stream::iter_ok::<_, InfluxDbError>(queries),
+    /// );
+    /// ```
+    pub fn write_stream<S>(
+        &self,
+        batch_size: usize,
+        flush_interval: Duration,
+        input: S,
+    ) -> impl Stream<Item = String, Error = InfluxDbError>
+    where
+        S: Stream<Item = InfluxDbWriteQuery, Error = InfluxDbError>,
+    {
+        WriteStream::new(self.clone(), batch_size, flush_interval, input)
+    }
+
+    /// Creates the database `name`
+    ///
+    /// Succeeds if the database already exists.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.create_database("test");
+    /// ```
+    pub fn create_database<S>(&self, name: S) -> impl Future<Item = (), Error = InfluxDbError>
+    where
+        S: AsRef<str>,
+    {
+        // InfluxDB 1.x no longer supports `IF NOT EXISTS`, `CREATE DATABASE` does nothing
+        // if the database exists
+        let query = InfluxDbReadQuery::new(format!(
+            "CREATE DATABASE {}",
+            quote_identifier(name.as_ref())
+        ));
+        self.query(&query).map(|_| ())
+    }
+
+    /// Drops the database `name` including all of its data
+    ///
+    /// Succeeds if the database does not exist.
+    pub fn drop_database<S>(&self, name: S) -> impl Future<Item = (), Error = InfluxDbError>
+    where
+        S: AsRef<str>,
+    {
+        let query =
+            InfluxDbReadQuery::new(format!("DROP DATABASE {}", quote_identifier(name.as_ref())));
+        self.query(&query).map(|_| ())
+    }
+
+    /// Creates the retention policy `name` on the database of the client
+    ///
+    /// # Arguments
+    ///
+    ///  * `name`: The name of the retention policy.
+    ///  * `duration`: How long data is kept, as InfluxQL duration (ex. `30d`), or `INF` to keep it forever.
+    ///  * `replication`: The number of copies of the data stored in a cluster.
+    ///  * `is_default`: Whether writes without a retention policy are written to this one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.create_retention_policy("one_month", "30d", 1, false);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `duration` is not a valid duration, a [`InfluxDbError::InvalidQueryError`] will be
+    /// returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn create_retention_policy(
+        &self,
+        name: &str,
+        duration: &str,
+        replication: u32,
+        is_default: bool,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        if !duration.eq_ignore_ascii_case("INF") && !is_duration_literal(duration) {
+            return future::Either::B(future::err(InfluxDbError::InvalidQueryError {
+                error: format!("invalid duration \"{}\"", duration),
+            }));
+        }
+        let mut query = format!(
+            "CREATE RETENTION POLICY {} ON {} DURATION {} REPLICATION {}",
+            quote_identifier(name),
+            quote_identifier(&self.database),
+            duration,
+            replication
+        );
+        if is_default {
+            query.push_str(" DEFAULT");
+        }
+        future::Either::A(self.query(&InfluxDbReadQuery::new(query)).map(|_| ()))
+    }
+
+    /// Changes the retention policy `name` of the database of the client
+    ///
+    /// # Arguments
+    ///
+    ///  * `name`: The name of the retention policy.
+    ///  * `duration`: How long data is kept, as InfluxQL duration (ex. `30d`), or `INF` to keep it forever.
+    ///  * `shard_duration`: The time range covered by a shard group, as InfluxQL duration (ex. `1d`), if it should be changed.
+    ///  * `replication`: The number of copies of the data stored in a cluster, if it should be changed.
+    ///  * `is_default`: Whether to make this the default retention policy. `false` leaves the
+    ///    default retention policy unchanged, as InfluxDB requires a default retention policy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.alter_retention_policy("one_month", "60d", Some("7d"), None, false);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `duration` or `shard_duration` is not a valid duration, a
+    /// [`InfluxDbError::InvalidQueryError`] will be returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn alter_retention_policy(
+        &self,
+        name: &str,
+        duration: &str,
+        shard_duration: Option<&str>,
+        replication: Option<u32>,
+        is_default: bool,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        let invalid = |error: String| {
+            future::Either::B(future::err(InfluxDbError::InvalidQueryError { error }))
+        };
+        if !duration.eq_ignore_ascii_case("INF") && !is_duration_literal(duration) {
+            return invalid(format!("invalid duration \"{}\"", duration));
+        }
+        let mut query = format!(
+            "ALTER RETENTION POLICY {} ON {} DURATION {}",
+            quote_identifier(name),
+            quote_identifier(&self.database),
+            duration
+        );
+        if let Some(replication) = replication {
+            query.push_str(&format!(" REPLICATION {}", replication));
+        }
+        if let Some(shard_duration) = shard_duration {
+            if !is_duration_literal(shard_duration) {
+                return invalid(format!("invalid shard duration \"{}\"", shard_duration));
+            }
+            query.push_str(&format!(" SHARD DURATION {}", shard_duration));
+        }
+        if is_default {
+            query.push_str(" DEFAULT");
+        }
+        future::Either::A(self.query(&InfluxDbReadQuery::new(query)).map(|_| ()))
+    }
+
+    /// Drops the retention policy `name` of the database of the client, including all of its data
+    ///
+    /// Succeeds if the retention policy does not exist.
+    pub fn drop_retention_policy(
+        &self,
+        name: &str,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        let query = InfluxDbReadQuery::new(format!(
+            "DROP RETENTION POLICY {} ON {}",
+            quote_identifier(name),
+            quote_identifier(&self.database)
+        ));
+        self.query(&query).map(|_| ())
+    }
+
+    /// Drops the shard `id` including all of its data
+    ///
+    /// The IDs of the shards are returned by
+    /// [`show_shards`](crate::client::InfluxDbClient::show_shards).
+    pub fn drop_shard(&self, id: u64) -> impl Future<Item = (), Error = InfluxDbError> {
+        let query = InfluxDbReadQuery::new(format!("DROP SHARD {}", id));
+        self.query(&query).map(|_| ())
+    }
+
+    /// Creates the continuous query `name` on the database of the client
+    ///
+    /// `query` is run every `interval` and grouped by time intervals of the same length.
+    ///
+    /// # Arguments
+    ///
+    ///  * `name`: The name of the continuous query.
+    ///  * `interval`: How often the query is run, as InfluxQL duration (ex. `1h`).
+    ///  * `query`: A `SELECT ... INTO ... FROM ...` query without `GROUP BY time(...)` clause.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.create_continuous_query(
+    ///     "hourly_temperature",
+    ///     "1h",
+    ///     "SELECT mean(temperature) INTO weather_hourly FROM weather",
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `interval` is not a valid duration, a [`InfluxDbError::InvalidQueryError`] will be
+    /// returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn create_continuous_query(
+        &self,
+        name: &str,
+        interval: &str,
+        query: &str,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        if !is_duration_literal(interval) {
+            return future::Either::B(future::err(InfluxDbError::InvalidQueryError {
+                error: format!("invalid interval \"{}\"", interval),
+            }));
+        }
+        let query = InfluxDbReadQuery::new(format!(
+            "CREATE CONTINUOUS QUERY {} ON {} BEGIN {} GROUP BY time({}) END",
+            quote_identifier(name),
+            quote_identifier(&self.database),
+            query,
+            interval
+        ));
+        future::Either::A(self.query(&query).map(|_| ()))
+    }
+
+    /// Drops the continuous query `name` of the database of the client
+    pub fn drop_continuous_query(
+        &self,
+        name: &str,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        let query = InfluxDbReadQuery::new(format!(
+            "DROP CONTINUOUS QUERY {} ON {}",
+            quote_identifier(name),
+            quote_identifier(&self.database)
+        ));
+        self.query(&query).map(|_| ())
+    }
+
+    /// Creates the subscription `name` on the retention policy `retention_policy` of the database
+    /// of the client, which forwards all data written to it to `destinations`
+    ///
+    /// # Arguments
+    ///
+    ///  * `name`: The name of the subscription.
+    ///  * `retention_policy`: The retention policy whose writes are forwarded (ex. `autogen`).
+    ///  * `destinations`: URLs of the receivers, with `udp`, `http` or `https` scheme (ex. `udp://kapacitor:9090`).
+    ///  * `mode`: Whether each point is sent to any or to all destinations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::{InfluxDbClient, SubscriptionMode};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.create_subscription(
+    ///     "kapacitor",
+    ///     "autogen",
+    ///     &["udp://kapacitor:9090"],
+    ///     SubscriptionMode::Any,
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `destinations` is empty, a [`InfluxDbError::InvalidQueryError`] will be returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn create_subscription(
+        &self,
+        name: &str,
+        retention_policy: &str,
+        destinations: &[&str],
+        mode: SubscriptionMode,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        if destinations.is_empty() {
+            return future::Either::B(future::err(InfluxDbError::InvalidQueryError {
+                error: "at least one destination is required".to_string(),
+            }));
+        }
+        let destinations = destinations
+            .iter()
+            .map(|destination| InfluxDbValue::Str(destination.to_string()).to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        let query = InfluxDbReadQuery::new(format!(
+            "CREATE SUBSCRIPTION {} ON {}.{} DESTINATIONS {} {}",
+            quote_identifier(name),
+            quote_identifier(&self.database),
+            quote_identifier(retention_policy),
+            mode,
+            destinations
+        ));
+        future::Either::A(self.query(&query).map(|_| ()))
+    }
+
+    /// Drops the subscription `name` on the retention policy `retention_policy` of the database of
+    /// the client
+    pub fn drop_subscription(
+        &self,
+        name: &str,
+        retention_policy: &str,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        let query = InfluxDbReadQuery::new(format!(
+            "DROP SUBSCRIPTION {} ON {}.{}",
+            quote_identifier(name),
+            quote_identifier(&self.database),
+            quote_identifier(retention_policy)
+        ));
+        self.query(&query).map(|_| ())
+    }
+
+    /// Creates the user `username` with `password`
+    ///
+    /// The user has no privileges until they are granted with
+    /// [`grant_privilege`](crate::client::InfluxDbClient::grant_privilege).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::{InfluxDbClient, Privilege};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.create_user("grafana", "secret");
+    /// let _future = client.grant_privilege("grafana", Privilege::Read, Some("test"));
+    /// ```
+    pub fn create_user(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        let query = InfluxDbReadQuery::new(format!(
+            "CREATE USER {} WITH PASSWORD {}",
+            quote_identifier(username),
+            InfluxDbValue::Str(password.to_string())
+        ));
+        self.query(&query).map(|_| ())
+    }
+
+    /// Drops the user `username`
+    pub fn drop_user(&self, username: &str) -> impl Future<Item = (), Error = InfluxDbError> {
+        let query = InfluxDbReadQuery::new(format!("DROP USER {}", quote_identifier(username)));
+        self.query(&query).map(|_| ())
+    }
+
+    /// Grants `privilege` on `database` to the user `username`
+    ///
+    /// Without `database`, the user is made an admin, which requires [`Privilege::All`](crate::client::Privilege::All).
+    ///
+    /// # Errors
+    ///
+    /// If `database` is `None` and `privilege` is not [`Privilege::All`](crate::client::Privilege::All),
+    /// a [`InfluxDbError::InvalidQueryError`] will be returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn grant_privilege(
+        &self,
+        username: &str,
+        privilege: Privilege,
+        database: Option<&str>,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        self.privilege_query("GRANT", "TO", username, privilege, database)
+    }
+
+    /// Revokes `privilege` on `database` from the user `username`
+    ///
+    /// Without `database`, the admin privilege of the user is revoked, which requires
+    /// [`Privilege::All`](crate::client::Privilege::All).
+    ///
+    /// # Errors
+    ///
+    /// If `database` is `None` and `privilege` is not [`Privilege::All`](crate::client::Privilege::All),
+    /// a [`InfluxDbError::InvalidQueryError`] will be returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn revoke_privilege(
+        &self,
+        username: &str,
+        privilege: Privilege,
+        database: Option<&str>,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        self.privilege_query("REVOKE", "FROM", username, privilege, database)
+    }
+
+    /// Sends a `GRANT` or `REVOKE` query
+    fn privilege_query(
+        &self,
+        action: &str,
+        preposition: &str,
+        username: &str,
+        privilege: Privilege,
+        database: Option<&str>,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        let query = match (database, privilege) {
+            (Some(database), privilege) => format!(
+                "{} {} ON {} {} {}",
+                action,
+                privilege,
+                quote_identifier(database),
+                preposition,
+                quote_identifier(username)
+            ),
+            (None, Privilege::All) => format!(
+                "{} ALL PRIVILEGES {} {}",
+                action,
+                preposition,
+                quote_identifier(username)
+            ),
+            (None, privilege) => {
+                return future::Either::B(future::err(InfluxDbError::InvalidQueryError {
+                    error: format!("{} privilege requires a database", privilege),
+                }));
+            }
+        };
+        future::Either::A(self.query(&InfluxDbReadQuery::new(query)).map(|_| ()))
+    }
+
+    /// Deletes the points of `measurement` between `start` and `stop` from the bucket of the
+    /// client, using the InfluxDB 2.x endpoint `/api/v2/delete`
+    ///
+    /// Requires [`with_org`](crate::client::InfluxDbClient::with_org) and
+    /// [`with_bucket`](crate::client::InfluxDbClient::with_bucket).
+    ///
+    /// # Arguments
+    ///
+    ///  * `measurement`: The measurement to delete points from.
+    ///  * `start`: The start of the time range, as RFC3339 time (ex. `2020-01-01T00:00:00Z`).
+    ///  * `stop`: The end of the time range, as RFC3339 time.
+    ///  * `predicate`: An optional [delete predicate](https://docs.influxdata.com/influxdb/v2.0/reference/syntax/delete-predicate/)
+    ///    further restricting the deleted points (ex. `location="berlin"`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:9999", "test")
+    ///     .with_token("my-token")
+    ///     .with_org("openvelo")
+    ///     .with_bucket("weather");
+    /// let _future = client.delete(
+    ///     "weather",
+    ///     "2020-01-01T00:00:00Z",
+    ///     "2020-02-01T00:00:00Z",
+    ///     Some("location=\"berlin\""),
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the client has no organization or bucket, a
+    /// [`InfluxDbError::InvalidQueryError`](crate::error::InfluxDbError::InvalidQueryError) will
+    /// be returned.
+    pub fn delete(
+        &self,
+        measurement: &str,
+        start: &str,
+        stop: &str,
+        predicate: Option<&str>,
+    ) -> impl Future<Item = (), Error = InfluxDbError> {
+        let url = match self.delete_url() {
+            Ok(url) => url,
+            Err(err) => return future::Either::B(future::err(err)),
+        };
+        // The measurement is part of the predicate, like any other column
+        let mut measurement_predicate = format!(
+            "_measurement=\"{}\"",
+            measurement.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+        if let Some(predicate) = predicate {
+            measurement_predicate = format!("{} AND {}", measurement_predicate, predicate);
+        }
+        let body = format!(
+            "{{\"start\":{},\"stop\":{},\"predicate\":{}}}",
+            json_string(start),
+            json_string(stop),
+            json_string(&measurement_predicate)
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        future::Either::A(
+            self.send_request_with_headers(Method::POST, url, headers, Some(body))
+                .map(|_| ()),
+        )
+    }
+
+    /// Builds the URL delete requests are sent to, failing unless organization and bucket are set
+    fn delete_url(&self) -> Result<Url, InfluxDbError> {
+        let (org, bucket) = match (&self.org, &self.bucket) {
+            (Some(org), Some(bucket)) => (org, bucket),
+            _ => {
+                return Err(InfluxDbError::InvalidQueryError {
+                    error: String::from("deleting points requires an organization and a bucket"),
+                });
+            }
+        };
+        Url::parse_with_params(
+            self.endpoint_url("/api/v2/delete").as_str(),
+            &[("org", org), ("bucket", bucket)],
+        )
+        .map_err(|err| InfluxDbError::UrlConstructionError {
+            error: format!("{}", err),
+        })
+    }
+
+    /// Sends a request to an arbitrary endpoint of the InfluxDB installation and returns the body
+    /// of the response
+    ///
+    /// This is an escape hatch for endpoints this library does not cover yet. The URL is built
+    /// from the URL of the client, `path` and `query_params`, and the credentials of the client
+    /// are attached. Transient failures are retried like for any other request.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use reqwest::Method;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.execute_raw(
+    ///     Method::GET,
+    ///     "/query",
+    ///     vec![("q".to_string(), "SHOW DATABASES".to_string())],
+    ///     None,
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the response is not valid UTF-8, a [`InfluxDbError::DeserializationError`] will be
+    /// returned. Unlike [`query`](crate::client::InfluxDbClient::query), the body is not checked
+    /// for InfluxDB errors.
+    ///
+    /// [`InfluxDbError::DeserializationError`]: crate::error::InfluxDbError::DeserializationError
+    pub fn execute_raw(
+        &self,
+        method: Method,
+        path: &str,
+        query_params: Vec<(String, String)>,
+        body: Option<String>,
+    ) -> impl Future<Item = String, Error = InfluxDbError> {
+        let mut parameters: Vec<(String, String)> = self.into();
+        parameters.retain(|(key, _)| key != "db" && key != "org");
+        parameters.extend(query_params);
+        let url = match Url::parse(&self.endpoint_url(path)) {
+            Ok(mut url) => {
+                if !parameters.is_empty() {
+                    url.query_pairs_mut().extend_pairs(parameters);
+                }
+                url
+            }
+            Err(err) => {
+                return future::Either::B(future::err(InfluxDbError::UrlConstructionError {
+                    error: format!("{}", err),
+                }));
+            }
+        };
+        future::Either::A(
+            self.send_request(method, url, body)
+                .and_then(|(_, body)| utf8_body(&body)),
+        )
+    }
+
+    /// Sends a Flux query to InfluxDB 2.x and returns the response as annotated CSV
+    ///
+    /// The CSV can be parsed with [`FluxQueryResult::parse`](crate::client::FluxQueryResult::parse).
+    /// Requires [`with_token`](crate::client::InfluxDbClient::with_token) and
+    /// [`with_org`](crate::client::InfluxDbClient::with_org).
+    ///
+    /// # Arguments
+    ///
+    ///  * `script`: The Flux script to run.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:9999", "test")
+    ///     .with_token("my-token")
+    ///     .with_org("openvelo");
+    /// let _future = client.flux_query(r#"from(bucket: "weather") |> range(start: -1h)"#);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the client has no token or organization, a
+    /// [`InfluxDbError::InvalidQueryError`](crate::error::InfluxDbError::InvalidQueryError) will
+    /// be returned.
+    pub fn flux_query(&self, script: &str) -> impl Future<Item = String, Error = InfluxDbError> {
+        self.send_flux_query(script, "application/csv")
+    }
+
+    /// Sends a read query and returns the response as CSV instead of JSON
+    ///
+    /// InfluxDB responds with a `name`, `tags` and `time` column followed by the columns of the
+    /// query. With the `csv` feature, the rows can be deserialized with
+    /// [`query_into_csv_records`](crate::client::InfluxDbClient::query_into_csv_records).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future =
+    ///     client.query_into_csv(&InfluxDbQuery::raw_read_query("SELECT * FROM weather"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the query is invalid, a [`InfluxDbError::InvalidQueryError`] will be returned. If
+    /// InfluxDB reports an error for a statement, a [`InfluxDbError::DatabaseError`] will be
+    /// returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    /// [`InfluxDbError::DatabaseError`]: crate::error::InfluxDbError::DatabaseError
+    pub fn query_into_csv(
+        &self,
+        q: &InfluxDbReadQuery,
+    ) -> impl Future<Item = String, Error = InfluxDbError> {
+        let (method, url, _) = match self.query_request(q) {
+            Ok(request) => request,
+            Err(err) => return future::Either::B(future::err(err)),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("application/csv"));
+        future::Either::A(
+            self.send_request_with_headers(method, url, headers, None)
+                .and_then(|(_, body)| utf8_body(&body))
+                .and_then(|csv| match csv_error(&csv) {
+                    Some(error) => Err(InfluxDbError::DatabaseError { error }),
+                    None => Ok(csv),
+                }),
+        )
+    }
+
+    /// Sends a request to InfluxDB and returns the status and body of the response
+    ///
+    /// Transient failures are retried according to the [`RetryPolicy`](crate::client::RetryPolicy)
+    /// of this client.
+    pub(crate) fn send_request(
+        &self,
+        method: Method,
+        url: Url,
+        body: Option<String>,
+    ) -> Box<dyn Future<Item = (StatusCode, Chunk), Error = InfluxDbError>> {
+        self.send_request_with_headers(method, url, HeaderMap::new(), body)
+    }
+
+    /// Like [`send_request`](crate::client::InfluxDbClient::send_request), with `headers` added
+    /// to the request
+    pub(crate) fn send_request_with_headers(
+        &self,
+        method: Method,
+        url: Url,
+        headers: HeaderMap,
+        body: Option<String>,
+    ) -> Box<dyn Future<Item = (StatusCode, Chunk), Error = InfluxDbError>> {
+        #[cfg(feature = "tracing")]
+        let headers = {
+            let mut headers = headers;
+            if self.tracing_propagation {
+                trace::inject_current(&mut headers);
+            }
+            headers
+        };
+        let client = self.clone();
+        Box::new(future::loop_fn(0, move |attempt| {
+            let retry_policy = client.retry_policy.clone();
+            let rate_limit = match &client.rate_limiter {
+                Some(rate_limiter) => future::Either::A(rate_limiter.acquire()),
+                None => future::Either::B(future::ok(())),
+            };
+            let request_client = client.clone();
+            let (method, url, headers, body) =
+                (method.clone(), url.clone(), headers.clone(), body.clone());
+            rate_limit
+                .and_then(move |_| request_client.send_request_once(method, url, headers, body))
+                .then(
+                    move |result| -> Box<dyn Future<Item = Loop<_, u32>, Error = InfluxDbError>> {
+                        let policy = match &retry_policy {
+                            Some(policy) if attempt + 1 < policy.max_attempts => policy,
+                            _ => return Box::new(future::result(result.map(Loop::Break))),
+                        };
+                        let delay = match &result {
+                            // The delay is controlled by the server, so it is limited as well
+                            Err(InfluxDbError::RateLimitError {
+                                retry_after: Some(retry_after),
+                            }) => (*retry_after).min(policy.max_delay),
+                            Err(err) if err.is_retryable() => policy.delay(attempt),
+                            _ => return Box::new(future::result(result.map(Loop::Break))),
+                        };
+                        if let (Err(err), Some(on_retry)) = (&result, &policy.on_retry) {
+                            on_retry(attempt + 1, err);
+                        }
+                        let now = Instant::now();
+                        Box::new(
+                            Delay::new(now.checked_add(delay).unwrap_or(now))
+                                .map_err(|err| InfluxDbError::ProtocolError {
+                                    error: format!("{}", err),
+                                })
+                                .map(move |_| Loop::Continue(attempt + 1)),
+                        )
+                    },
+                )
+        }))
+    }
+
+    /// Sends a request to InfluxDB once and returns the status and body of a successful response
+    fn send_request_once(
+        &self,
+        method: Method,
+        url: Url,
+        headers: HeaderMap,
+        body: Option<String>,
+    ) -> impl Future<Item = (StatusCode, Chunk), Error = InfluxDbError> {
+        let timeouts = self.timeouts;
+        self.send_request_streaming(method, url, headers, body)
+            .and_then(move |res| read_response(res, timeouts))
+    }
+
+    /// Creates a request with `headers` and `body`, compressing the body if it is sent to the
+    /// write endpoint and write compression is enabled
+    pub(crate) fn prepare_request(
+        &self,
+        method: Method,
+        url: Url,
+        headers: HeaderMap,
+        body: Option<String>,
+    ) -> RequestBuilder {
+        #[cfg(feature = "gzip")]
+        let compress = self.write_compression && is_write_url(&url);
+        let mut request = self.request(method, url).headers(headers);
+        if let Some(body) = body {
+            #[cfg(feature = "gzip")]
+            {
+                if compress {
+                    request = request
+                        .header(CONTENT_ENCODING, "gzip")
+                        .body(gzip(body.as_bytes()));
+                } else {
+                    request = request.body(body);
+                }
+            }
+            #[cfg(not(feature = "gzip"))]
+            {
+                request = request.body(body);
+            }
+        }
+        request
+    }
+
+    /// Sends a request to InfluxDB once and returns a successful response without reading its body
+    ///
+    /// Unlike [`send_request`](crate::client::InfluxDbClient::send_request), failed requests are
+    /// not retried.
+    pub(crate) fn send_request_streaming(
+        &self,
+        method: Method,
+        url: Url,
+        headers: HeaderMap,
+        body: Option<String>,
+    ) -> impl Future<Item = Response, Error = InfluxDbError> {
+        let timeouts = self.timeouts;
+        self.prepare_request(method, url, headers, body)
+            .send()
+            .map_err(move |err| match timeouts.elapsed(&err) {
+                Some(elapsed) => InfluxDbError::TimeoutError { elapsed },
+                None => InfluxDbError::ConnectionError {
+                    error: Arc::new(err),
+                },
+            })
+            .and_then(
+                move |res| -> Box<dyn Future<Item = Response, Error = InfluxDbError>> {
+                    let status = res.status();
+                    match status {
+                        StatusCode::UNAUTHORIZED => {
+                            Box::new(future::err(InfluxDbError::AuthenticationFailure))
+                        }
+                        StatusCode::FORBIDDEN => {
+                            Box::new(future::err(InfluxDbError::AuthorizationFailure))
+                        }
+                        StatusCode::TOO_MANY_REQUESTS => {
+                            let retry_after = res
+                                .headers()
+                                .get(RETRY_AFTER)
+                                .and_then(|value| value.to_str().ok())
+                                .and_then(|value| parse_retry_after(value, SystemTime::now()));
+                            Box::new(future::err(InfluxDbError::RateLimitError { retry_after }))
+                        }
+                        _ if status.is_success() => Box::new(future::ok(res)),
+                        _ => Box::new(read_response(res, timeouts).and_then(|(status, body)| {
+                            Err(InfluxDbError::HttpError {
+                                status: status.as_u16(),
+                                body: String::from_utf8_lossy(&body).into_owned(),
+                            })
+                        })),
+                    }
+                },
+            )
+    }
+}
+
+/// Quotes `value` as JSON string
+fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Returns the position in `lines` of the line InfluxDB failed to write according to the error
+/// response `body`
+///
+/// InfluxDB 2.x reports the 1-based number of the line, InfluxDB 1.x quotes the line itself.
+fn failed_line(lines: &[String], body: &str) -> Option<usize> {
+    const LINE_PREFIX: &str = "line ";
+    let mut rest = body;
+    while let Some(start) = rest.find(LINE_PREFIX) {
+        rest = &rest[start + LINE_PREFIX.len()..];
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits > 0 && rest[digits..].starts_with(':') {
+            return match rest[..digits].parse::<usize>() {
+                Ok(number) if number >= 1 && number <= lines.len() => Some(number - 1),
+                _ => None,
+            };
+        }
+    }
+    // The error is a JSON string, so the quoted line is escaped
+    lines.iter().position(|line| {
+        let quoted = json_string(line);
+        body.contains(&format!(
+            "unable to parse '{}'",
+            &quoted[1..quoted.len() - 1]
+        ))
+    })
+}
+
+/// Appends `params` to `url` as JSON object in the `params` parameter, unless it is empty
+///
+/// Keys are sorted, so the URL does not depend on the order of the map. A leading `$` is removed
+/// like in [`InfluxDbReadQuery::bind`](crate::query::read_query::InfluxDbReadQuery::bind).
+pub(crate) fn with_params(
+    mut url: Url,
+    params: &HashMap<String, InfluxDbValue>,
+) -> Result<Url, InfluxDbError> {
+    if params.is_empty() {
+        return Ok(url);
+    }
+    let mut keys = params.keys().collect::<Vec<&String>>();
+    keys.sort();
+    let mut members = Vec::with_capacity(keys.len());
+    for key in keys {
+        let invalid = |error: String| Err(InfluxDbError::InvalidQueryError { error });
+        let value = match &params[key] {
+            InfluxDbValue::Str(text) => json_string(text),
+            InfluxDbValue::Int(x) => x.to_string(),
+            InfluxDbValue::Float(x) if x.is_finite() => x.to_string(),
+            InfluxDbValue::Float(x) => return invalid(format!("parameter {} is {}", key, x)),
+            InfluxDbValue::Bool(x) => x.to_string(),
+            InfluxDbValue::Timestamp(timestamp) => match timestamp.nanoseconds() {
+                Some(nanoseconds) => nanoseconds.to_string(),
+                None => return invalid(format!("parameter {} cannot be now()", key)),
+            },
+        };
+        members.push(format!(
+            "{}:{}",
+            json_string(key.trim_start_matches('$')),
+            value
+        ));
+    }
+    url.query_pairs_mut()
+        .append_pair("params", &format!("{{{}}}", members.join(",")));
+    Ok(url)
+}
+
+/// Collects `tags` into key-value pairs
+fn collect_tags<I, K, V>(tags: I) -> Vec<(String, String)>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    tags.into_iter()
+        .map(|(key, value)| (key.into(), value.into()))
+        .collect()
+}
+
+/// Converts the error of a certificate which cannot be parsed
+fn certificate_error(err: reqwest::Error) -> InfluxDbError {
+    InfluxDbError::ProtocolError {
+        error: format!("invalid certificate: {}", err),
+    }
+}
+
+/// Validates a header added with [`InfluxDbClient::with_header`](crate::client::InfluxDbClient::with_header)
+fn parse_header(name: String, value: String) -> Result<(HeaderName, HeaderValue), InfluxDbError> {
+    let header_name =
+        HeaderName::from_bytes(name.as_bytes()).map_err(|err| InfluxDbError::ProtocolError {
+            error: format!("invalid header name \"{}\": {}", name, err),
+        })?;
+    let header_value =
+        HeaderValue::from_str(&value).map_err(|err| InfluxDbError::ProtocolError {
+            error: format!("invalid value of header \"{}\": {}", name, err),
+        })?;
+    Ok((header_name, header_value))
+}
+
+/// Converts `user_agent` to the value of a `User-Agent` header
+fn user_agent_header(user_agent: &str) -> Result<HeaderValue, InfluxDbError> {
+    HeaderValue::from_str(user_agent).map_err(|err| InfluxDbError::ProtocolError {
+        error: format!(
+            "invalid User-Agent \"{}\": {}",
+            user_agent.escape_debug(),
+            err
+        ),
+    })
+}
+
+/// Converts the error of an invalid proxy URL
+fn proxy_error(err: reqwest::Error) -> InfluxDbError {
+    InfluxDbError::UrlConstructionError {
+        error: format!("invalid proxy URL: {}", err),
+    }
+}
+
+/// Returns the error InfluxDB reported in a CSV response, if any
+fn csv_error(csv: &str) -> Option<String> {
+    // Errors are written as a single `error` column, followed by a row with the message
+    let mut lines = csv.lines();
+    lines.find(|line| line.trim_end() == "error")?;
+    let message = lines.next().unwrap_or_default().trim_end();
+    let message = if message.len() >= 2 && message.starts_with('"') && message.ends_with('"') {
+        message[1..message.len() - 1].replace("\"\"", "\"")
+    } else {
+        message.to_string()
+    };
+    Some(message)
+}
+
+/// Converts the body of a response to a `String`
+fn utf8_body(body: &Chunk) -> Result<String, InfluxDbError> {
+    String::from_utf8(body.to_vec()).map_err(|_| InfluxDbError::DeserializationError {
+        error: "response could not be converted to UTF-8".to_string(),
+    })
+}
+
+/// Fails with a [`InfluxDbError::TimeoutError`](crate::error::InfluxDbError::TimeoutError) if
+/// `future` does not complete within `timeout`
+pub(crate) fn with_timeout<F>(
+    future: F,
+    timeout: Duration,
+) -> impl Future<Item = F::Item, Error = InfluxDbError>
+where
+    F: Future<Error = InfluxDbError>,
+{
+    Timeout::new(future, timeout).map_err(move |err| {
+        if err.is_elapsed() {
+            return InfluxDbError::TimeoutError { elapsed: timeout };
+        }
+        match err.into_inner() {
+            Some(err) => err,
+            None => InfluxDbError::ProtocolError {
+                error: "timer is unavailable".to_string(),
+            },
+        }
+    })
+}
+
+/// Reads the whole body of `res`
+fn read_response(
+    mut res: Response,
+    timeouts: InfluxDbTimeouts,
+) -> impl Future<Item = (StatusCode, Chunk), Error = InfluxDbError> {
+    let status = res.status();
+    let body = mem::replace(res.body_mut(), Decoder::empty());
+    body.concat2()
+        .map_err(move |err| timeouts.body_error(err))
+        .map(move |body| (status, body))
+}
+
+/// Returns `true` if `url` is the write endpoint of InfluxDB 1.x or 2.x
+#[cfg(feature = "gzip")]
+fn is_write_url(url: &Url) -> bool {
+    url.path().ends_with("/write")
+}
+
+/// Compresses `body` with gzip
+#[cfg(feature = "gzip")]
+fn gzip(body: &[u8]) -> Vec<u8> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    // Writing to a `Vec` cannot fail
+    encoder.write_all(body).expect("failed to compress body");
+    encoder.finish().expect("failed to compress body")
+}
+
+/// Parses the value of a `Retry-After` header, which is either a number of seconds or a HTTP date
+///
+/// Dates in the past result in a delay of zero.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    parse_http_date(value).map(|date| date.duration_since(now).unwrap_or_default())
+}
+
+/// Parses a HTTP date in the preferred format of RFC 7231, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if parts.len() != 6 || parts[5] != "GMT" {
+        return None;
+    }
+    let day = parts[1].parse::<i64>().ok()?;
+    let month = MONTHS.iter().position(|month| *month == parts[2])? as i64 + 1;
+    let year = parts[3].parse::<i64>().ok()?;
+    let time = parts[4]
+        .split(':')
+        .map(|part| part.parse::<i64>().ok())
+        .collect::<Option<Vec<i64>>>()?;
+    if time.len() != 3 {
+        return None;
+    }
+
+    // Days since the Unix epoch, see http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86_400 + time[0] * 3_600 + time[1] * 60 + time[2];
+    if seconds < 0 {
+        return None;
+    }
+    Some(UNIX_EPOCH + Duration::from_secs(seconds as u64))
+}
+
+/// Returns `path` with a leading and without a trailing slash, or an empty string for the root
+fn normalize_base_path(path: &str) -> String {
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", path)
+    }
+}
+
+/// Returns `true` if `read_query` returns data and is therefore sent as `GET` request
+///
+/// Only the start of the query is checked, as statements like `CREATE CONTINUOUS QUERY`
+/// contain a `SELECT` but must be sent as `POST` request. `SELECT ... INTO` queries write their
+/// results and must be sent as `POST` request as well.
+pub(crate) fn is_data_query(read_query: &str) -> bool {
+    let upper = read_query.trim_start().to_uppercase();
+    (upper.starts_with("SELECT") && !has_into_clause(read_query)) || upper.starts_with("SHOW")
+}
+
+/// Converts the body of a response to a `String`, failing if it contains an InfluxDB error
+fn response_to_string(body: &[u8]) -> future::FutureResult<String, InfluxDbError> {
+    if let Ok(utf8) = std::str::from_utf8(body) {
+        let s = utf8.to_owned();
+
+        // todo: improve error parsing without serde
+        if s.contains("\"error\"") {
+            return futures::future::err(InfluxDbError::DatabaseError {
+                error: format!("influxdb error: \"{}\"", s),
+            });
+        }
+
+        return futures::future::ok(s);
+    }
+
+    futures::future::err(InfluxDbError::DeserializationError {
+        error: "response could not be converted to UTF-8".to_string(),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::client::{
+        failed_line, is_data_query, json_string, parse_retry_after, with_params,
+        InfluxDbAuthentication, InfluxDbClient, InfluxDbClientBuilder, InfluxDbVersion,
+        PingResponse, Privilege, RetryPolicy, SubscriptionMode,
+    };
+    use crate::error::InfluxDbError;
+    use crate::query::read_query::InfluxDbValue;
+    use crate::query::write_batch::InfluxDbWriteBatch;
+    use crate::query::{InfluxDbQuery, Precision, QueryType, Timestamp, ValidQuery};
+    use futures::{stream, Stream};
+    use reqwest::header::AUTHORIZATION;
+    use reqwest::{Method, Url};
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::mpsc::{channel, Receiver};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant, UNIX_EPOCH};
+    use tokio::runtime::current_thread::Runtime;
+
+    /// Starts a HTTP server on a random local port, answering every request with
+    /// `response` after waiting for `delay`. Returns the URL of the server and
+    /// a receiver for the raw requests it received.
+    pub(crate) fn mock_server(
+        delay: Duration,
+        response: &'static str,
+    ) -> (String, Receiver<String>) {
+        mock_server_with(delay, move |_| response)
+    }
+
+    /// Like [`mock_server`], but answers every request with the response `respond` returns for it
+    pub(crate) fn mock_server_with<F, R>(delay: Duration, respond: F) -> (String, Receiver<String>)
+    where
+        F: Fn(&str) -> R + Send + 'static,
+        R: AsRef<[u8]>,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let request = String::from_utf8_lossy(&read_request(&mut stream)).into_owned();
+                let response = respond(&request);
+                let _ = sender.send(request);
+                thread::sleep(delay);
+                let _ = stream.write_all(response.as_ref());
+            }
+        });
+        (url, receiver)
+    }
+
+    /// Like [`mock_server`], but serves HTTPS with the self-signed certificate for `localhost`
+    /// in `tests/fixtures/localhost.pem`. Failed handshakes are ignored.
+    fn tls_mock_server(response: &'static str) -> (String, Receiver<String>) {
+        let identity = native_tls::Identity::from_pkcs12(
+            include_bytes!("../../tests/fixtures/localhost.p12"),
+            "influxdb",
+        )
+        .unwrap();
+        let acceptor = native_tls::TlsAcceptor::new(identity).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "https://localhost:{}",
+            listener.local_addr().unwrap().port()
+        );
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match acceptor.accept(stream.unwrap()) {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let request = String::from_utf8_lossy(&read_request(&mut stream)).into_owned();
+                let _ = sender.send(request);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        (url, receiver)
+    }
+
+    /// Reads a HTTP request including the body announced by its `Content-Length` header
+    fn read_request<S: Read>(stream: &mut S) -> Vec<u8> {
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            let read = stream.read(&mut buffer).unwrap_or(0);
+            if read == 0 {
+                return request;
+            }
+            request.extend_from_slice(&buffer[..read]);
+            let header_end = match request.windows(4).position(|window| window == b"\r\n\r\n") {
+                Some(position) => position + 4,
+                None => continue,
+            };
+            let headers = String::from_utf8_lossy(&request[..header_end]).to_lowercase();
+            let content_length = headers
+                .lines()
+                .find(|line| line.starts_with("content-length:"))
+                .and_then(|line| line["content-length:".len()..].trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if request.len() >= header_end + content_length {
+                return request;
+            }
+        }
+    }
+
+    #[test]
+    fn test_fn_database() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database");
+        assert_eq!("database", client.database_name());
+    }
+
+    #[test]
+    fn test_clone_with_database_and_url() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database")
+            .with_auth("username", "password");
+        let other = client.clone_with_database("other");
+        assert_eq!(other.database_name(), "other");
+        assert_eq!(other.database_url(), "http://localhost:8068");
+        assert_eq!(other.auth().unwrap().username(), "username");
+        assert_eq!(client.database_name(), "database");
+
+        let other = client.clone_with_url("http://localhost:9086");
+        assert_eq!(other.database_url(), "http://localhost:9086");
+        assert_eq!(other.database_name(), "database");
+    }
+
+    #[test]
+    fn test_clone_with_database_shares_connections() {
+        // Answers requests on a connection until it is closed, counting the connections
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicU32::new(0));
+        let counter = connections.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut stream = stream.unwrap();
+                thread::spawn(move || {
+                    while !read_request(&mut stream).is_empty() {
+                        let response =
+                            "HTTP/1.1 204 No Content\r\nX-Influxdb-Version: 1.7.6\r\n\r\n";
+                        if stream.write_all(response.as_bytes()).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        let client = InfluxDbClient::new(url, "database");
+        let other = client.clone_with_database("other");
+        let mut rt = Runtime::new().unwrap();
+        assert!(rt.block_on(client.ping()).is_ok());
+        assert!(rt.block_on(other.ping()).is_ok());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_with_auth() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database");
+        assert_eq!(client.url, "http://localhost:8068");
+        assert_eq!(client.database, "database");
+        assert!(client.auth.is_none());
+        let with_auth = client.with_auth("username", "password");
+        assert!(with_auth.auth.is_some());
+        let auth = with_auth.auth().unwrap();
+        assert_eq!(auth.username(), "username");
+        assert_eq!(auth.password(), "password");
+        assert_eq!(auth, &InfluxDbAuthentication::new("username", "password"));
+    }
+
+    #[test]
+    fn test_into_impl() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database");
+        assert!(client.auth.is_none());
+        let basic_parameters: Vec<(String, String)> = client.into();
+        assert_eq!(
+            vec![("db".to_string(), "database".to_string())],
+            basic_parameters
+        );
+
+        let with_auth = InfluxDbClient::new("http://localhost:8068", "database")
+            .with_auth("username", "password");
+        let basic_parameters_with_auth: Vec<(String, String)> = with_auth.into();
+        assert_eq!(
+            vec![
+                ("db".to_string(), "database".to_string()),
+                ("u".to_string(), "username".to_string()),
+                ("p".to_string(), "password".to_string())
+            ],
+            basic_parameters_with_auth
+        );
+
+        let client = InfluxDbClient::new("http://localhost:8068", "database");
+        assert!(client.auth.is_none());
+        let basic_parameters: Vec<(String, String)> = (&client).into();
+        assert_eq!(
+            vec![("db".to_string(), "database".to_string())],
+            basic_parameters
+        );
+
+        let with_auth = InfluxDbClient::new("http://localhost:8068", "database")
+            .with_auth("username", "password");
+        let basic_parameters_with_auth: Vec<(String, String)> = (&with_auth).into();
+        assert_eq!(
+            vec![
+                ("db".to_string(), "database".to_string()),
+                ("u".to_string(), "username".to_string()),
+                ("p".to_string(), "password".to_string())
+            ],
+            basic_parameters_with_auth
+        );
+    }
+
+    #[test]
+    fn test_with_token() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database");
+        assert!(client.token.is_none());
+        let with_token = client.with_token("token");
+        assert_eq!(with_token.token, Some("token".to_string()));
+    }
+
+    #[test]
+    fn test_into_impl_with_token() {
+        let with_token = InfluxDbClient::new("http://localhost:8068", "database")
+            .with_auth("username", "password")
+            .with_token("token");
+        let basic_parameters: Vec<(String, String)> = (&with_token).into();
+        assert_eq!(
+            vec![("db".to_string(), "database".to_string())],
+            basic_parameters
+        );
+
+        let basic_parameters: Vec<(String, String)> = with_token.into();
+        assert_eq!(
+            vec![("db".to_string(), "database".to_string())],
+            basic_parameters
+        );
+    }
+
+    #[test]
+    fn test_request_headers() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database")
+            .with_auth("username", "password");
+        let request = client
+            .request(Method::GET, "http://localhost:8068/ping")
+            .build()
+            .unwrap();
+        assert!(request.headers().get(AUTHORIZATION).is_none());
+
+        let with_token = client.with_token("token");
+        let request = with_token
+            .request(Method::GET, "http://localhost:8068/ping")
+            .build()
+            .unwrap();
+        assert_eq!(request.headers().get(AUTHORIZATION).unwrap(), "Token token");
+    }
+
+    #[test]
+    fn test_with_user_agent() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nX-Influxdb-Version: 1.7.6\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database")
+            .with_user_agent("my-app/1.0")
+            .unwrap()
+            .with_request_timeout(Duration::from_secs(5));
+        let result = Runtime::new().unwrap().block_on(client.ping());
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(request.contains("\r\nuser-agent: my-app/1.0\r\n"));
+    }
+
+    #[test]
+    fn test_with_user_agent_invalid() {
+        let client =
+            InfluxDbClient::new("http://localhost:8086", "database").with_user_agent("my-app\r\n");
+        match client {
+            Err(InfluxDbError::ProtocolError { error }) => {
+                assert!(
+                    error.starts_with("invalid User-Agent \"my-app\\r\\n\""),
+                    "{}",
+                    error
+                )
+            }
+            _ => panic!("Should cause a ProtocolError"),
+        }
+        assert!(
+            InfluxDbClientBuilder::new("http://localhost:8086", "database")
+                .with_user_agent("my-app\n")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_with_header() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nX-Influxdb-Version: 1.7.6\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url.clone(), "database")
+            .with_header("X-API-Key", "secret")
+            .unwrap()
+            .with_header("X-Tenant-ID", "42")
+            .unwrap();
+        let result = Runtime::new().unwrap().block_on(client.ping());
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let result = Runtime::new().unwrap().block_on(
+            client.query(&InfluxDbQuery::write_query(Timestamp::NOW, "weather").add_field("a", 1)),
+        );
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let requests = requests.try_iter().collect::<Vec<String>>();
+        assert_eq!(requests.len(), 2);
+        for request in requests {
+            let request = request.to_lowercase();
+            assert!(request.contains("\r\nx-api-key: secret\r\n"), "{}", request);
+            assert!(request.contains("\r\nx-tenant-id: 42\r\n"), "{}", request);
+        }
+
+        let client = InfluxDbClient::builder(url, "database")
+            .with_header("X-API-Key", "secret")
+            .unwrap()
+            .build();
+        assert_eq!(client.headers.len(), 1);
+    }
+
+    #[test]
+    fn test_with_header_invalid() {
+        let client = InfluxDbClient::new("http://localhost:8086", "database");
+        for (name, value) in &[
+            ("X API Key", "secret"),
+            ("", "secret"),
+            ("X-API-Key", "a\nb"),
+        ] {
+            match client.clone().with_header(*name, *value) {
+                Err(InfluxDbError::ProtocolError { .. }) => {}
+                result => panic!(
+                    "Should cause a ProtocolError for {:?}: {:?}",
+                    name,
+                    result.map(|_| ())
+                ),
+            }
+        }
+        assert!(InfluxDbClient::builder("http://localhost:8086", "database")
+            .with_header("X API Key", "secret")
+            .is_err());
+    }
+
+    #[test]
+    fn test_with_http_proxy() {
+        let (proxy_url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nX-Influxdb-Version: 1.7.6\r\n\r\n",
+        );
+        let client = InfluxDbClient::new("http://influxdb.invalid:8086", "database")
+            .with_http_proxy(&proxy_url)
+            .unwrap()
+            .with_request_timeout(Duration::from_secs(5));
+        let result = Runtime::new().unwrap().block_on(client.ping());
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        // Proxied requests carry the absolute URL of InfluxDB
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET http://influxdb.invalid:8086/ping HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_with_proxy_invalid_url() {
+        let client = InfluxDbClient::new("http://localhost:8086", "database");
+        match client.clone().with_http_proxy("not a url") {
+            Err(InfluxDbError::UrlConstructionError { error }) => {
+                assert!(error.starts_with("invalid proxy URL"))
+            }
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        }
+        assert!(client.clone().with_https_proxy("http://[::1").is_err());
+        assert!(InfluxDbClient::builder("http://localhost:8086", "database")
+            .with_https_proxy("not a url")
+            .is_err());
+    }
+
+    #[test]
+    fn test_use_tls_with_custom_ca() {
+        let (url, requests) = tls_mock_server(
+            "HTTP/1.1 204 No Content\r\nX-Influxdb-Version: 1.7.6\r\nConnection: close\r\n\r\n",
+        );
+        let mut rt = Runtime::new().unwrap();
+        let client = InfluxDbClient::new(url.as_str(), "database")
+            .with_request_timeout(Duration::from_secs(5));
+
+        // The self-signed certificate is rejected unless it is trusted
+        let result = rt.block_on(client.clone().ping());
+        assert!(result.is_err(), "Should be an error: {:?}", result);
+
+        let client = client
+            .use_tls_with_custom_ca(include_bytes!("../../tests/fixtures/localhost.pem"))
+            .unwrap();
+        let result = rt.block_on(client.ping());
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        assert!(requests.recv().unwrap().starts_with("GET /ping "));
+    }
+
+    #[test]
+    fn test_with_connection_pool_size() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nX-Influxdb-Version: 1.7.6\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url.clone(), "database").with_connection_pool_size(0);
+        let result = Runtime::new().unwrap().block_on(client.ping());
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        assert!(requests.recv().unwrap().starts_with("GET /ping "));
+
+        let client = InfluxDbClientBuilder::new(url, "database")
+            .with_connection_pool_size(32)
+            .build()
+            .with_request_timeout(Duration::from_secs(5));
+        assert_eq!(
+            InfluxDbClientBuilder::from(client).connection_pool_size,
+            Some(32)
+        );
+    }
+
+    #[test]
+    fn test_accept_invalid_certs() {
+        let (url, _) = tls_mock_server(
+            "HTTP/1.1 204 No Content\r\nX-Influxdb-Version: 1.7.6\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database")
+            .with_request_timeout(Duration::from_secs(5))
+            .accept_invalid_certs(true);
+        let result = Runtime::new().unwrap().block_on(client.ping());
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+    }
+
+    #[test]
+    fn test_use_tls_with_custom_ca_invalid() {
+        let result = InfluxDbClient::new("https://localhost:8086", "database")
+            .use_tls_with_custom_ca(b"not a certificate");
+        match result {
+            Err(InfluxDbError::ProtocolError { error }) => {
+                assert!(error.starts_with("invalid certificate"))
+            }
+            result => panic!("Should cause a ProtocolError: {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_default_user_agent() {
+        assert_eq!(
+            InfluxDbClient::default_user_agent(),
+            format!("influxdb-rust/{}", env!("CARGO_PKG_VERSION"))
+        );
+    }
+
+    #[test]
+    fn test_execute_raw_show_databases() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 14\r\n\r\n{\"results\":[]}",
+        );
+        let client = InfluxDbClient::new(url, "database").with_auth("user", "pass");
+        let result = Runtime::new().unwrap().block_on(client.execute_raw(
+            Method::GET,
+            "/query",
+            vec![("q".to_string(), "SHOW DATABASES".to_string())],
+            None,
+        ));
+        assert_eq!(result.unwrap(), "{\"results\":[]}");
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?u=user&p=pass&q=SHOW+DATABASES "));
+    }
+
+    #[test]
+    fn test_execute_raw_metrics() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 15\r\n\r\ngo_goroutines 7",
+        );
+        let client = InfluxDbClient::new(url, "database").with_token("token");
+        let result = Runtime::new().unwrap().block_on(client.execute_raw(
+            Method::GET,
+            "/metrics",
+            Vec::new(),
+            None,
+        ));
+        assert_eq!(result.unwrap(), "go_goroutines 7");
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /metrics "));
+        assert!(request
+            .to_lowercase()
+            .contains("\r\nauthorization: token token\r\n"));
+    }
+
+    #[test]
+    fn test_flux_query() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 25\r\n\r\n,result,table\r\n,_result,0",
+        );
+        let client = InfluxDbClient::new(url, "database")
+            .with_token("token")
+            .with_org("org");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.flux_query("buckets()"));
+        assert_eq!(result.unwrap(), ",result,table\r\n,_result,0");
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /api/v2/query?org=org "));
+        let lowercase = request.to_lowercase();
+        assert!(lowercase.contains("\r\ncontent-type: application/vnd.flux\r\n"));
+        assert!(lowercase.contains("\r\naccept: application/csv\r\n"));
+        assert!(request.ends_with("\r\n\r\nbuckets()"));
+    }
+
+    #[test]
+    fn test_query_into_csv() {
+        let csv = "name,tags,time,temperature\nweather,,3600000000000,82\n";
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            csv.len(),
+            csv
+        );
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            Box::leak(response.into_boxed_str()),
+        );
+        let client = InfluxDbClient::new(url, "database").with_precision(Precision::Nanoseconds);
+        let result = Runtime::new().unwrap().block_on(
+            client.query_into_csv(&InfluxDbQuery::raw_read_query("SELECT * FROM weather")),
+        );
+        assert_eq!(result.unwrap(), csv);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SELECT+*+FROM+weather&epoch=ns "));
+        assert!(request
+            .to_lowercase()
+            .contains("\r\naccept: application/csv\r\n"));
+    }
+
+    #[test]
+    fn test_query_into_csv_error() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 43\r\n\r\nerror\n\"error parsing query: found \"\"EOF\"\"\"\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.query_into_csv(&InfluxDbQuery::raw_read_query("SELECT")));
+        assert_eq!(
+            result,
+            Err(InfluxDbError::DatabaseError {
+                error: "error parsing query: found \"EOF\"".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_flux_query_requires_token_and_org() {
+        let clients = vec![
+            InfluxDbClient::new("http://localhost:9999", "database").with_token("token"),
+            InfluxDbClient::new("http://localhost:9999", "database").with_org("org"),
+        ];
+        for client in clients {
+            match Runtime::new()
+                .unwrap()
+                .block_on(client.flux_query("buckets()"))
+            {
+                Err(InfluxDbError::InvalidQueryError { .. }) => {}
+                result => panic!("Should cause an InvalidQueryError: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_delete() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database")
+            .with_token("token")
+            .with_org("org")
+            .with_bucket("bucket");
+        let mut rt = Runtime::new().unwrap();
+        let result = rt.block_on(client.delete(
+            "weather",
+            "2020-01-01T00:00:00Z",
+            "2020-02-01T00:00:00Z",
+            Some("location=\"berlin\""),
+        ));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let result = rt.block_on(client.delete(
+            "weather",
+            "2020-01-01T00:00:00Z",
+            "2020-02-01T00:00:00Z",
+            None,
+        ));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /api/v2/delete?org=org&bucket=bucket "));
+        assert!(request
+            .to_lowercase()
+            .contains("\r\ncontent-type: application/json\r\n"));
+        assert!(request.ends_with(
+            r#"{"start":"2020-01-01T00:00:00Z","stop":"2020-02-01T00:00:00Z","predicate":"_measurement=\"weather\" AND location=\"berlin\""}"#
+        ));
+        let request = requests.recv().unwrap();
+        assert!(request.ends_with(
+            r#"{"start":"2020-01-01T00:00:00Z","stop":"2020-02-01T00:00:00Z","predicate":"_measurement=\"weather\""}"#
+        ));
+    }
+
+    #[test]
+    fn test_delete_requires_org_and_bucket() {
+        let client = InfluxDbClient::new("http://localhost:9999", "database").with_bucket("bucket");
+        let result = Runtime::new().unwrap().block_on(client.delete(
+            "weather",
+            "2020-01-01T00:00:00Z",
+            "2020-02-01T00:00:00Z",
+            None,
+        ));
+        match result {
+            Err(InfluxDbError::InvalidQueryError { error }) => assert_eq!(
+                error,
+                "deleting points requires an organization and a bucket"
+            ),
+            _ => panic!("Should cause an InvalidQueryError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("weather"), "\"weather\"");
+        assert_eq!(
+            json_string("a \"b\"\\\n\u{1}"),
+            "\"a \\\"b\\\"\\\\\\n\\u0001\""
+        );
+    }
+
+    #[test]
+    fn test_with_org_and_bucket() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database");
+        assert!(client.org.is_none());
+        assert!(client.bucket.is_none());
+        assert_eq!(client.org(), None);
+        let client = client.with_org("org").with_bucket("bucket");
+        assert_eq!(client.org, Some("org".to_string()));
+        assert_eq!(client.org(), Some("org"));
+        assert_eq!(client.bucket, Some("bucket".to_string()));
+    }
+
+    #[test]
+    fn test_write_url_v1() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database")
+            .with_auth("username", "password");
+        assert_eq!(
+            client.write_url("s", None).unwrap().as_str(),
+            "http://localhost:8068/write?db=database&u=username&p=password&precision=s"
+        );
+
+        // Only setting the organization keeps using InfluxDB 1.x
+        let client = client.with_org("org");
+        assert_eq!(
+            client.write_url("s", None).unwrap().as_str(),
+            "http://localhost:8068/write?db=database&org=org&u=username&p=password&precision=s"
+        );
+        assert_eq!(
+            client.write_url("s", Some("one_week")).unwrap().as_str(),
+            "http://localhost:8068/write?db=database&org=org&u=username&p=password&precision=s&rp=one_week"
+        );
+    }
+
+    #[test]
+    fn test_org_parameter() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database")
+            .with_token("token")
+            .with_org("org");
+        let mut rt = Runtime::new().unwrap();
+        let _ = rt.block_on(client.query(&InfluxDbQuery::raw_read_query("SELECT * FROM weather")));
+        let _ = rt.block_on(client.write_many(vec![InfluxDbQuery::write_query(
+                Timestamp::SECONDS(1),
+                "weather",
+            )
+            .add_field("temperature", 82)]));
+        let _ = rt.block_on(client.clone().with_bucket("bucket").write_many(vec![
+                InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                    .add_field("temperature", 82),
+            ]));
+
+        let requests = requests.try_iter().collect::<Vec<String>>();
+        assert!(requests[0].starts_with("GET /query?db=database&org=org&q=SELECT+*+FROM+weather "));
+        assert!(requests[1].starts_with("POST /write?db=database&org=org&precision=s "));
+        assert!(requests[2].starts_with("POST /api/v2/write?org=org&bucket=bucket&precision=s "));
+    }
+
+    #[test]
+    fn test_write_url_v2() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database")
+            .with_org("org")
+            .with_bucket("bucket");
+        assert_eq!(
+            client.write_url("s", None).unwrap().as_str(),
+            "http://localhost:8068/api/v2/write?org=org&bucket=bucket&precision=s"
+        );
+        assert_eq!(
+            client.write_url("u", None).unwrap().as_str(),
+            "http://localhost:8068/api/v2/write?org=org&bucket=bucket&precision=us"
+        );
+        assert_eq!(
+            client.write_url("", None).unwrap().as_str(),
+            "http://localhost:8068/api/v2/write?org=org&bucket=bucket"
+        );
+        assert_eq!(
+            client.write_url("s", Some("one_week")).unwrap().as_str(),
+            "http://localhost:8068/api/v2/write?org=org&bucket=bucket&precision=s"
+        );
+    }
+
+    #[test]
+    fn test_read_url_with_precision() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database");
+        assert_eq!(
+            client
+                .read_url("SELECT * FROM weather", None)
+                .unwrap()
+                .as_str(),
+            "http://localhost:8068/query?db=database&q=SELECT+*+FROM+weather"
+        );
+
+        let client = client.with_precision(Precision::Milliseconds);
+        assert_eq!(
+            client
+                .read_url("SELECT * FROM weather", None)
+                .unwrap()
+                .as_str(),
+            "http://localhost:8068/query?db=database&q=SELECT+*+FROM+weather&epoch=ms"
+        );
+        let client = client.with_precision(Precision::Nanoseconds);
+        assert_eq!(
+            client.read_url("SHOW MEASUREMENTS", None).unwrap().as_str(),
+            "http://localhost:8068/query?db=database&q=SHOW+MEASUREMENTS&epoch=ns"
+        );
+
+        // Statements which do not return data ignore the precision
+        assert_eq!(
+            client
+                .read_url("CREATE DATABASE test", None)
+                .unwrap()
+                .as_str(),
+            "http://localhost:8068/query?db=database&q=CREATE+DATABASE+test"
+        );
+
+        let client = client.with_precision(Precision::Rfc3339);
+        assert_eq!(
+            client
+                .read_url("SELECT * FROM weather", None)
+                .unwrap()
+                .as_str(),
+            "http://localhost:8068/query?db=database&q=SELECT+*+FROM+weather"
+        );
+
+        // The epoch of a query overrides the precision of the client
+        assert_eq!(
+            client
+                .read_url("SELECT * FROM weather", Some(Precision::Seconds))
+                .unwrap()
+                .as_str(),
+            "http://localhost:8068/query?db=database&q=SELECT+*+FROM+weather&epoch=s"
+        );
+    }
+
+    #[test]
+    fn test_query_with_precision() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        );
+        let client = InfluxDbClient::new(url, "database").with_precision(Precision::Hours);
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let result = Runtime::new().unwrap().block_on(client.query(&read_query));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SELECT+*+FROM+weather&epoch=h "));
+    }
+
+    #[test]
+    fn test_with_timeouts() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database");
+        assert!(client.timeouts.request.is_none());
+        assert!(client.timeouts.connect.is_none());
+        let client = client
+            .with_request_timeout(Duration::from_secs(30))
+            .with_connect_timeout(Duration::from_secs(5));
+        assert_eq!(client.timeouts.request, Some(Duration::from_secs(30)));
+        assert_eq!(client.timeouts.connect, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_query_request_timeout() {
+        let (url, _) = mock_server(
+            Duration::from_secs(2),
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        );
+        let client =
+            InfluxDbClient::new(url, "database").with_request_timeout(Duration::from_millis(100));
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let result = Runtime::new().unwrap().block_on(client.query(&read_query));
+        match result {
+            Err(InfluxDbError::TimeoutError { elapsed }) => {
+                assert_eq!(elapsed, Duration::from_millis(100))
+            }
+            _ => panic!("Should cause a TimeoutError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_ping_request_timeout() {
+        let (url, _) = mock_server(Duration::from_secs(2), "HTTP/1.1 204 No Content\r\n\r\n");
+        let client =
+            InfluxDbClient::new(url, "database").with_request_timeout(Duration::from_millis(100));
+        let result = Runtime::new().unwrap().block_on(client.ping());
+        match result {
+            Err(InfluxDbError::TimeoutError { elapsed }) => {
+                assert_eq!(elapsed, Duration::from_millis(100))
+            }
+            _ => panic!("Should cause a TimeoutError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_query_with_timeout() {
+        let (url, _) = mock_server(
+            Duration::from_secs(2),
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.query_with_timeout(&read_query, Duration::from_millis(100)));
+        match result {
+            Err(InfluxDbError::TimeoutError { elapsed }) => {
+                assert_eq!(elapsed, Duration::from_millis(100))
+            }
+            _ => panic!("Should cause a TimeoutError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_query_with_timeout_in_time() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.query_with_timeout(&read_query, Duration::from_secs(5)));
+        assert_eq!(result.unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_retry_policy_delay() {
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_millis(100),
+            backoff_factor: 2.0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(1), Duration::from_millis(200));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+        assert_eq!(policy.delay(100), Duration::from_secs(30));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(30));
+
+        let policy = RetryPolicy {
+            backoff_factor: 0.0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay(1), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn test_retry_policy_invalid_backoff_factor() {
+        for backoff_factor in &[-1.0, std::f64::NAN, std::f64::INFINITY] {
+            let client = InfluxDbClient::new("http://localhost:8086", "database")
+                .with_retry_policy(RetryPolicy {
+                    backoff_factor: *backoff_factor,
+                    ..RetryPolicy::default()
+                });
+            assert!(client.is_err(), "{} should be invalid", backoff_factor);
+        }
+    }
+
+    #[test]
+    fn test_retry_on_server_error() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nContent-Length: 2\r\n\r\n{}",
+        );
+        let retries = Arc::new(AtomicU32::new(0));
+        let counter = retries.clone();
+        let client = InfluxDbClient::new(url, "database")
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 3,
+                initial_delay: Duration::from_millis(10),
+                backoff_factor: 2.0,
+                on_retry: Some(Box::new(move |attempt, _| {
+                    counter.store(attempt, Ordering::SeqCst);
+                })),
+                ..RetryPolicy::default()
+            })
+            .unwrap();
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let _ = Runtime::new().unwrap().block_on(client.query(&read_query));
+        assert_eq!(retries.load(Ordering::SeqCst), 2);
+        assert_eq!(requests.try_iter().count(), 3);
+    }
+
+    /// Query implemented outside of this crate
+    struct CustomQuery(QueryType);
+
+    impl InfluxDbQuery for CustomQuery {
+        fn build(&self) -> Result<ValidQuery, InfluxDbError> {
+            Ok(ValidQuery::from("SHOW DATABASES"))
+        }
+
+        fn get_type(&self) -> QueryType {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_query_dispatches_on_query_type() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 14\r\n\r\n{\"results\":[]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let mut rt = Runtime::new().unwrap();
+        let result = rt.block_on(client.query(&CustomQuery(QueryType::ReadQuery)));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SHOW+DATABASES "));
+
+        let result = rt.block_on(client.query(&CustomQuery(QueryType::WriteQuery)));
+        match result {
+            Err(InfluxDbError::InvalidQueryError { .. }) => {}
+            _ => panic!("Should cause an InvalidQueryError: {:?}", result),
+        }
+    }
+
+    /// Starts a HTTP server answering a single request with `204 No Content`, returning the URL
+    /// of the server and a receiver for the raw bytes of the request
+    #[cfg(feature = "gzip")]
+    fn raw_mock_server() -> (String, Receiver<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = sender.send(read_request(&mut stream));
+            let _ = stream.write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n");
+        });
+        (url, receiver)
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn test_write_compression() {
+        use flate2::read::GzDecoder;
+
+        let (url, requests) = raw_mock_server();
+        let client = InfluxDbClient::new(url, "database").with_write_compression(true);
+        let write_query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .add_tag("location", "us-midwest");
+        let line_protocol = write_query.build().unwrap().into_string();
+        let result = Runtime::new().unwrap().block_on(client.query(&write_query));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        let header_end = request.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        let headers = String::from_utf8_lossy(&request[..header_end]).to_lowercase();
+        assert!(headers.contains("\r\ncontent-encoding: gzip\r\n"));
+        let mut body = String::new();
+        GzDecoder::new(&request[header_end..])
+            .read_to_string(&mut body)
+            .unwrap();
+        assert_eq!(body, line_protocol);
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn test_write_compression_ignores_reads() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database").with_write_compression(true);
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.create_database("weather"));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(!request.contains("content-encoding"));
+    }
+
+    #[test]
+    fn test_response_compression() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        let body = "{\"results\":[{\"statement_id\":0,\"series\":[{\"name\":\"wetter\",\"columns\":[\"ort\"],\"values\":[[\"Köln\"],[\"東京\"]]}]}]}";
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+            compressed.len()
+        )
+        .into_bytes();
+        response.extend(compressed);
+
+        let (url, requests) = mock_server_with(Duration::from_millis(0), move |_| response.clone());
+        let client = InfluxDbClient::new(url, "database");
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM wetter");
+        let result = Runtime::new().unwrap().block_on(client.query(&read_query));
+        assert_eq!(result.unwrap(), body);
+
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(request.contains("\r\naccept-encoding: gzip\r\n"));
+    }
+
+    #[test]
+    fn test_without_response_compression() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 14\r\n\r\n{\"results\":[]}",
+        );
+        let client = InfluxDbClient::new(url, "database").with_response_compression(false);
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let result = Runtime::new().unwrap().block_on(client.query(&read_query));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(!request.contains("accept-encoding"));
+    }
+
+    #[test]
+    fn test_rate_limit_error() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 429 Too Many Requests\r\nConnection: close\r\nRetry-After: 30\r\nContent-Length: 0\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let result = Runtime::new().unwrap().block_on(client.query(&read_query));
+        match result {
+            Err(InfluxDbError::RateLimitError { retry_after }) => {
+                assert_eq!(retry_after, Some(Duration::from_secs(30)))
+            }
+            _ => panic!("Should cause a RateLimitError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_retry_honors_retry_after() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 429 Too Many Requests\r\nConnection: close\r\nRetry-After: 0\r\nContent-Length: 0\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database")
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 2,
+                initial_delay: Duration::from_secs(60),
+                ..RetryPolicy::default()
+            })
+            .unwrap();
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let start = Instant::now();
+        let _ = Runtime::new().unwrap().block_on(client.query(&read_query));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(requests.try_iter().count(), 2);
+    }
+
+    #[test]
+    fn test_retry_after_limited_by_max_delay() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 429 Too Many Requests\r\nConnection: close\r\nRetry-After: 18446744073709551615\r\nContent-Length: 0\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database")
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 2,
+                max_delay: Duration::from_millis(10),
+                ..RetryPolicy::default()
+            })
+            .unwrap();
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let start = Instant::now();
+        let result = Runtime::new().unwrap().block_on(client.query(&read_query));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(requests.try_iter().count(), 2);
+        match result {
+            Err(InfluxDbError::RateLimitError { retry_after }) => {
+                assert_eq!(retry_after, Some(Duration::from_secs(std::u64::MAX)))
+            }
+            _ => panic!("Should cause a RateLimitError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        let date = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        let now = date - Duration::from_secs(90);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", now),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_retry_after(
+                "Sun, 06 Nov 1994 08:49:37 GMT",
+                date + Duration::from_secs(1)
+            ),
+            Some(Duration::from_secs(0))
+        );
+        assert_eq!(
+            parse_retry_after("Sunday, 06-Nov-94 08:49:37 GMT", now),
+            None
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_no_retry_without_policy() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nContent-Length: 2\r\n\r\n{}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let _ = Runtime::new().unwrap().block_on(client.query(&read_query));
+        assert_eq!(requests.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_builder() {
+        let client = InfluxDbClientBuilder::new("http://localhost:8068", "database")
+            .with_auth("username", "password")
+            .with_token("token")
+            .with_org("org")
+            .with_bucket("bucket")
+            .with_request_timeout(Duration::from_secs(30))
+            .with_connect_timeout(Duration::from_secs(5))
+            .with_retry_policy(RetryPolicy::default())
+            .unwrap()
+            .with_precision(Precision::Seconds)
+            .with_http_client_builder(|builder| builder.max_idle_per_host(4))
+            .build();
+        assert_eq!(client.url, "http://localhost:8068");
+        assert_eq!(client.database, "database");
+        assert_eq!(client.auth.unwrap().username, "username");
+        assert_eq!(client.token, Some("token".to_string()));
+        assert_eq!(client.org, Some("org".to_string()));
+        assert_eq!(client.bucket, Some("bucket".to_string()));
+        assert_eq!(client.timeouts.request, Some(Duration::from_secs(30)));
+        assert_eq!(client.timeouts.connect, Some(Duration::from_secs(5)));
+        assert!(client.retry_policy.is_some());
+        assert_eq!(client.precision, Precision::Seconds);
+    }
+
+    #[test]
+    fn test_builder_from_client() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database")
+            .with_auth("username", "password")
+            .with_request_timeout(Duration::from_secs(30));
+        let rebuilt = InfluxDbClientBuilder::from(client).build();
+        assert_eq!(rebuilt.database, "database");
+        assert_eq!(rebuilt.auth.unwrap().password, "password");
+        assert_eq!(rebuilt.timeouts.request, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_with_http_client() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\nX-Influxdb-Build: OSS\r\nX-Influxdb-Version: 1.7.6\r\n\r\n",
+        );
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("X-Custom", "custom".parse().unwrap());
+        let http_client = reqwest::r#async::Client::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap();
+        let client = InfluxDbClient::new(url, "database")
+            .with_http_client(http_client)
+            .with_request_timeout(Duration::from_secs(5))
+            .with_connection_pool_size(2);
+        let result = Runtime::new().unwrap().block_on(client.ping());
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        assert!(requests.recv().unwrap().contains("x-custom: custom"));
+    }
+
+    #[test]
+    fn test_with_http_client_builder_kept_on_rebuild() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\nX-Influxdb-Build: OSS\r\nX-Influxdb-Version: 1.7.6\r\n\r\n",
+        );
+        let client = InfluxDbClient::builder(url, "database")
+            .with_http_client_builder(|builder| {
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert("X-Custom", "custom".parse().unwrap());
+                builder.default_headers(headers)
+            })
+            .build()
+            .with_user_agent("my-app/1.0")
+            .unwrap()
+            .with_request_timeout(Duration::from_secs(5));
+        let result = Runtime::new().unwrap().block_on(client.ping());
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(request.contains("x-custom: custom"), "{}", request);
+        assert!(request.contains("user-agent: my-app/1.0"), "{}", request);
+    }
+
+    #[test]
+    fn test_write_many_single_request() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let queries = (0..1000).map(|i| {
+            InfluxDbQuery::write_query(Timestamp::SECONDS(i), "weather")
+                .add_field("temperature", i as u64)
+        });
+        let result = Runtime::new().unwrap().block_on(client.write_many(queries));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let requests = requests.try_iter().collect::<Vec<String>>();
+        assert_eq!(requests.len(), 1);
+        let (head, body) = requests[0].split_at(requests[0].find("\r\n\r\n").unwrap() + 4);
+        assert!(head.starts_with("POST /write?db=database&precision=s "));
+        let lines = body.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 1000);
+        assert_eq!(lines[0], "weather temperature=0i 0");
+        assert_eq!(lines[999], "weather temperature=999i 999");
+    }
+
+    #[test]
+    fn test_with_base_path() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nX-Influxdb-Build: OSS\r\nX-Influxdb-Version: 1.7.7\r\nConnection: close\r\n\r\n",
+        );
+        let client =
+            InfluxDbClient::new(format!("{}/", url), "database").with_base_path("influxdb/");
+        assert_eq!(client.base_path(), "/influxdb");
+        let mut rt = Runtime::new().unwrap();
+        assert!(rt.block_on(client.ping()).is_ok());
+        assert!(rt
+            .block_on(
+                client.query(
+                    &InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                        .add_field("temperature", 82)
+                )
+            )
+            .is_ok());
+        assert!(rt
+            .block_on(client.query(&InfluxDbQuery::raw_read_query("SHOW DATABASES")))
+            .is_ok());
+
+        let requests = requests.try_iter().collect::<Vec<String>>();
+        assert!(
+            requests[0].starts_with("GET /influxdb/ping "),
+            "{}",
+            requests[0]
+        );
+        assert!(
+            requests[1].starts_with("POST /influxdb/write?db=database&precision=s "),
+            "{}",
+            requests[1]
+        );
+        assert!(
+            requests[2].starts_with("GET /influxdb/query?db=database&q=SHOW+DATABASES "),
+            "{}",
+            requests[2]
+        );
+
+        let client = InfluxDbClient::builder("http://localhost:8086", "database")
+            .with_base_path("/a/b")
+            .build();
+        assert_eq!(
+            client.endpoint_url("/query"),
+            "http://localhost:8086/a/b/query"
+        );
+        assert_eq!(
+            client.with_base_path("/").endpoint_url("/query"),
+            "http://localhost:8086/query"
+        );
+    }
+
+    #[test]
+    fn test_write_line_protocol() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::builder(url, "database")
+            .with_default_tags(vec![("service", "api")])
+            .build();
+        let line_protocol =
+            "weather,location=us\\ midwest temperature=82 1\nweather temperature=\"hot\" 2";
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.write_line_protocol(line_protocol, Precision::Seconds));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.write_line_protocol(line_protocol, Precision::Rfc3339));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let requests = requests.try_iter().collect::<Vec<String>>();
+        assert_eq!(requests.len(), 2);
+        let (head, body) = requests[0].split_at(requests[0].find("\r\n\r\n").unwrap() + 4);
+        assert!(
+            head.starts_with("POST /write?db=database&precision=s "),
+            "{}",
+            head
+        );
+        assert_eq!(body, line_protocol);
+        assert!(
+            requests[1].starts_with("POST /write?db=database&precision= "),
+            "{}",
+            requests[1]
+        );
+    }
+
+    #[test]
+    fn test_transaction_write() {
+        let queries = || {
+            vec![
+                InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                    .add_field("temperature", 82),
+                InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                    .add_field("condition", "\"sunny\""),
+                InfluxDbQuery::write_query(Timestamp::SECONDS(1), "humidity")
+                    .add_field("percent", 48),
+            ]
+        };
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{\"code\":\"invalid\",\"message\":\"failed to parse line protocol:\\nerrors encountered on line(s):\\nline 3: invalid field format\"}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.transaction_write(queries()));
+        match result {
+            Err(InfluxDbError::PartialWriteError { failed_line, .. }) => assert_eq!(failed_line, 2),
+            _ => panic!("Should cause a PartialWriteError: {:?}", result),
+        }
+        let request = requests.recv().unwrap();
+        let body = &request[request.find("\r\n\r\n").unwrap() + 4..];
+        assert_eq!(body.lines().count(), 3);
+
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{\"error\":\"partial write: unable to parse 'weather condition=\\\"\\\\\\\"sunny\\\\\\\"\\\" 1': invalid boolean dropped=0\"}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.transaction_write(queries()));
+        match result {
+            Err(InfluxDbError::PartialWriteError { failed_line, .. }) => assert_eq!(failed_line, 1),
+            _ => panic!("Should cause a PartialWriteError: {:?}", result),
+        }
+
+        let mut queries = queries();
+        queries
+            .push(InfluxDbQuery::write_query(Timestamp::SECONDS(1), "_internal").add_field("x", 1));
+        let result = Runtime::new().unwrap().block_on(
+            InfluxDbClient::new("http://localhost:8068", "database").transaction_write(queries),
+        );
+        match result {
+            Err(InfluxDbError::BatchError { index, .. }) => assert_eq!(index, 3),
+            _ => panic!("Should cause a BatchError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_query_with_params() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let mut params = HashMap::new();
+        params.insert("meas".to_string(), InfluxDbValue::from("cpu"));
+        params.insert("$host".to_string(), InfluxDbValue::from("server1"));
+        let result = Runtime::new().unwrap().block_on(client.query_with_params(
+            &InfluxDbQuery::raw_read_query("SELECT * FROM $meas WHERE host = $host"),
+            params,
+        ));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let request = requests.recv().unwrap();
+        assert!(
+            request.starts_with("GET /query?db=database&q=SELECT+*+FROM+%24meas+WHERE+host+%3D+%24host&params=%7B%22host%22%3A%22server1%22%2C%22meas%22%3A%22cpu%22%7D "),
+            "{}",
+            request
+        );
+
+        let mut params = HashMap::new();
+        params.insert("limit".to_string(), InfluxDbValue::from(10));
+        params.insert("ratio".to_string(), InfluxDbValue::from(0.5));
+        params.insert("up".to_string(), InfluxDbValue::from(true));
+        params.insert(
+            "since".to_string(),
+            InfluxDbValue::from(Timestamp::SECONDS(1)),
+        );
+        let url = with_params(Url::parse("http://localhost:8086/query").unwrap(), &params).unwrap();
+        assert_eq!(
+            url.query_pairs().next().unwrap().1,
+            "{\"limit\":10,\"ratio\":0.5,\"since\":1000000000,\"up\":true}"
+        );
+        let url = Url::parse("http://localhost:8086/query").unwrap();
+        assert_eq!(with_params(url.clone(), &HashMap::new()), Ok(url.clone()));
+        params.insert("now".to_string(), InfluxDbValue::from(Timestamp::NOW));
+        assert!(with_params(url, &params).is_err());
+    }
+
+    #[test]
+    fn test_failed_line() {
+        let lines = vec![
+            "weather temperature=82i 1".to_string(),
+            "weather x=y 1".to_string(),
+        ];
+        assert_eq!(
+            failed_line(&lines, "{\"message\":\"line 2: invalid\"}"),
+            Some(1)
+        );
+        assert_eq!(
+            failed_line(&lines, "{\"message\":\"line 3: invalid\"}"),
+            None
+        );
+        assert_eq!(
+            failed_line(
+                &lines,
+                "{\"error\":\"unable to parse 'weather x=y 1': invalid\"}"
+            ),
+            Some(1)
+        );
+        assert_eq!(
+            failed_line(&lines, "{\"error\":\"field type conflict\"}"),
+            None
+        );
+    }
 
-        let query = match q.build() {
-            Err(err) => {
-                let error = InfluxDbError::InvalidQueryError {
-                    error: format!("{}", err),
-                };
-                return Box::new(future::err::<String, InfluxDbError>(error));
-            }
-            Ok(query) => query,
-        };
+    #[test]
+    fn test_default_tags() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::builder(url, "database")
+            .with_default_tags(vec![("service", "api"), ("env", "production")])
+            .build();
+        let mut rt = Runtime::new().unwrap();
+        let result = rt.block_on(
+            client.query(
+                &InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                    .add_tag("env", "staging")
+                    .add_field("temperature", 82),
+            ),
+        );
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let result = rt.block_on(client.write_many(vec![
+            InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                .add_field("temperature", 82),
+            InfluxDbQuery::write_query(Timestamp::SECONDS(2), "weather")
+                .add_tag("service", "worker")
+                .add_field("temperature", 83),
+        ]));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
 
-        let any_value = q as &dyn Any;
-        let basic_parameters: Vec<(String, String)> = self.into();
+        let request = requests.recv().unwrap();
+        assert!(request.ends_with("\r\n\r\nweather,env=staging,service=api temperature=82i 1"));
+        let request = requests.recv().unwrap();
+        assert!(request.ends_with("\r\n\r\nweather,service=api,env=production temperature=82i 1\nweather,service=worker,env=production temperature=83i 2"));
+    }
 
-        let client = if let Some(_) = any_value.downcast_ref::<InfluxDbReadQuery>() {
-            let read_query = query.get();
+    #[test]
+    fn test_write_stream_batch_size() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let queries = (0..5).map(|i| {
+            InfluxDbQuery::write_query(Timestamp::SECONDS(i), "weather")
+                .add_field("temperature", 82)
+        });
+        let responses = Runtime::new().unwrap().block_on(
+            client
+                .write_stream(2, Duration::from_secs(60), stream::iter_ok(queries))
+                .collect(),
+        );
+        assert_eq!(responses.unwrap().len(), 3);
 
-            let mut url = match Url::parse_with_params(
-                format!("{url}/query", url = self.database_url()).as_str(),
-                basic_parameters,
-            ) {
-                Ok(url) => url,
-                Err(err) => {
-                    let error = InfluxDbError::UrlConstructionError {
-                        error: format!("{}", err),
-                    };
-                    return Box::new(future::err::<String, InfluxDbError>(error));
-                }
-            };
-            url.query_pairs_mut().append_pair("q", &read_query.clone());
+        let bodies = requests
+            .try_iter()
+            .map(|request| {
+                request
+                    .lines()
+                    .filter(|line| line.starts_with("weather"))
+                    .count()
+            })
+            .collect::<Vec<usize>>();
+        assert_eq!(bodies, vec![2, 2, 1]);
+    }
 
-            if read_query.contains("SELECT") || read_query.contains("SHOW") {
-                Client::new().get(url)
-            } else {
-                Client::new().post(url)
-            }
-        } else if let Some(write_query) = any_value.downcast_ref::<InfluxDbWriteQuery>() {
-            let mut url = match Url::parse_with_params(
-                format!("{url}/write", url = self.database_url()).as_str(),
-                basic_parameters,
-            ) {
-                Ok(url) => url,
-                Err(err) => {
-                    let error = InfluxDbError::InvalidQueryError {
-                        error: format!("{}", err),
-                    };
-                    return Box::new(future::err::<String, InfluxDbError>(error));
-                }
-            };
-            url.query_pairs_mut()
-                .append_pair("precision", &write_query.get_precision());
-            Client::new().post(url).body(query.get())
-        } else {
-            unreachable!()
-        };
-        Box::new(
-            client
-                .send()
-                .map_err(|err| InfluxDbError::ConnectionError { error: err })
-                .and_then(
-                    |res| -> future::FutureResult<reqwest::r#async::Response, InfluxDbError> {
-                        match res.status() {
-                            StatusCode::UNAUTHORIZED => {
-                                futures::future::err(InfluxDbError::AuthorizationError)
-                            }
-                            StatusCode::FORBIDDEN => {
-                                futures::future::err(InfluxDbError::AuthenticationError)
-                            }
-                            _ => futures::future::ok(res),
-                        }
-                    },
-                )
-                .and_then(|mut res| {
-                    let body = mem::replace(res.body_mut(), Decoder::empty());
-                    body.concat2().map_err(|err| InfluxDbError::ProtocolError {
-                        error: format!("{}", err),
-                    })
-                })
-                .and_then(|body| {
-                    if let Ok(utf8) = std::str::from_utf8(&body) {
-                        let s = utf8.to_owned();
-
-                        // todo: improve error parsing without serde
-                        if s.contains("\"error\"") {
-                            return futures::future::err(InfluxDbError::DatabaseError {
-                                error: format!("influxdb error: \"{}\"", s),
-                            });
-                        }
+    #[test]
+    fn test_write_stream_flush_interval() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let (sender, receiver) = futures::sync::mpsc::unbounded();
+        sender
+            .unbounded_send(
+                InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                    .add_field("temperature", 82),
+            )
+            .unwrap();
+        // The input stays open, so only the flush interval causes the batch to be sent
+        let input = receiver.map_err(|_| InfluxDbError::ProtocolError {
+            error: "input failed".to_string(),
+        });
+        let responses = client.write_stream(100, Duration::from_millis(50), input);
+        let (response, _) = Runtime::new()
+            .unwrap()
+            .block_on(responses.into_future())
+            .map_err(|(err, _)| err)
+            .unwrap();
+        assert!(response.is_some());
+        assert_eq!(requests.try_iter().count(), 1);
+        drop(sender);
+    }
 
-                        return futures::future::ok(s);
-                    }
+    #[test]
+    fn test_write_batch_chunked() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let batch: InfluxDbWriteBatch = (0..5)
+            .map(|i| {
+                InfluxDbQuery::write_query(Timestamp::SECONDS(i), "weather")
+                    .add_field("temperature", i as u64)
+            })
+            .collect();
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.write_batch_chunked(batch, 2));
+        assert_eq!(
+            result,
+            Ok(vec![String::new(), String::new(), String::new()])
+        );
 
-                    futures::future::err(InfluxDbError::DeserializationError {
-                        error: "response could not be converted to UTF-8".to_string(),
-                    })
-                }),
-        )
+        let bodies = requests
+            .try_iter()
+            .map(|request| request[request.find("\r\n\r\n").unwrap() + 4..].to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            bodies,
+            vec![
+                "weather temperature=0i 0\nweather temperature=1i 1",
+                "weather temperature=2i 2\nweather temperature=3i 3",
+                "weather temperature=4i 4",
+            ]
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::client::InfluxDbClient;
+    #[test]
+    fn test_write_batch_chunked_invalid() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database");
+        let mut rt = Runtime::new().unwrap();
+        let batch = InfluxDbWriteBatch::from(vec![InfluxDbQuery::write_query(
+            Timestamp::SECONDS(1),
+            "weather",
+        )
+        .add_field("temperature", 82)]);
+        match rt.block_on(client.write_batch_chunked(batch, 0)) {
+            Err(InfluxDbError::InvalidQueryError { .. }) => {}
+            result => panic!("Should cause an InvalidQueryError: {:?}", result),
+        }
+        match rt.block_on(client.write_batch_chunked(InfluxDbWriteBatch::new(), 10)) {
+            Err(InfluxDbError::InvalidQueryError { .. }) => {}
+            result => panic!("Should cause an InvalidQueryError: {:?}", result),
+        }
+    }
 
     #[test]
-    fn test_fn_database() {
+    fn test_write_many_mixed_precision() {
         let client = InfluxDbClient::new("http://localhost:8068", "database");
-        assert_eq!("database", client.database_name());
+        let result = Runtime::new().unwrap().block_on(client.write_many(vec![
+                InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                    .add_field("temperature", 82),
+                InfluxDbQuery::write_query(Timestamp::HOURS(1), "weather")
+                    .add_field("temperature", 84),
+            ]));
+        match result {
+            Err(InfluxDbError::InvalidQueryError { .. }) => {}
+            _ => panic!("Should cause an InvalidQueryError: {:?}", result),
+        }
     }
 
     #[test]
-    fn test_with_auth() {
+    fn test_write_many_empty() {
         let client = InfluxDbClient::new("http://localhost:8068", "database");
-        assert_eq!(client.url, "http://localhost:8068");
-        assert_eq!(client.database, "database");
-        assert!(client.auth.is_none());
-        let with_auth = client.with_auth("username", "password");
-        assert!(with_auth.auth.is_some());
-        let auth = with_auth.auth.unwrap();
-        assert_eq!(&auth.username, "username");
-        assert_eq!(&auth.password, "password");
+        let result = Runtime::new().unwrap().block_on(client.write_many(vec![]));
+        match result {
+            Err(InfluxDbError::InvalidQueryError { .. }) => {}
+            _ => panic!("Should cause an InvalidQueryError: {:?}", result),
+        }
     }
 
     #[test]
-    fn test_into_impl() {
+    fn test_query_with_retention_policy() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .with_retention_policy("one_week");
+        let result = Runtime::new().unwrap().block_on(client.query(&query));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /write?db=database&precision=h&rp=one_week "));
+    }
+
+    #[test]
+    fn test_write_many_with_retention_policy() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let queries = (0..2).map(|i| {
+            InfluxDbQuery::write_query(Timestamp::HOURS(i), "weather")
+                .add_field("temperature", 82)
+                .with_retention_policy("one_week")
+        });
+        let result = Runtime::new().unwrap().block_on(client.write_many(queries));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /write?db=database&precision=h&rp=one_week "));
+    }
+
+    #[test]
+    fn test_write_many_mixed_retention_policy() {
         let client = InfluxDbClient::new("http://localhost:8068", "database");
-        assert!(client.auth.is_none());
-        let basic_parameters: Vec<(String, String)> = client.into();
+        let result = Runtime::new().unwrap().block_on(client.write_many(vec![
+            InfluxDbQuery::write_query(Timestamp::HOURS(1), "weather")
+                .add_field("temperature", 82)
+                .with_retention_policy("one_week"),
+            InfluxDbQuery::write_query(Timestamp::HOURS(2), "weather").add_field("temperature", 84),
+        ]));
+        match result {
+            Err(InfluxDbError::InvalidQueryError { .. }) => {}
+            _ => panic!("Should cause an InvalidQueryError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_query_with_select_builder() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let query = InfluxDbQuery::select_query().all_fields().from("weather");
+        let result = Runtime::new().unwrap().block_on(client.query(&query));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SELECT+*+FROM+%22weather%22 "));
+    }
+
+    #[test]
+    fn test_http_error() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 404 Not Found\r\nConnection: close\r\nContent-Length: 18\r\n\r\ndatabase not found",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let result = Runtime::new().unwrap().block_on(client.query(&read_query));
+        match result {
+            Err(InfluxDbError::HttpError { status, body }) => {
+                assert_eq!(status, 404);
+                assert_eq!(body, "database not found");
+            }
+            _ => panic!("Should cause a HttpError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_authentication_and_authorization_failure() {
+        let (url, _) = mock_server_with(Duration::from_millis(0), |request| {
+            if request.contains("u=wrong_user") {
+                "HTTP/1.1 401 Unauthorized\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+            } else {
+                "HTTP/1.1 403 Forbidden\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+            }
+        });
+        let mut rt = Runtime::new().unwrap();
+        let read_query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+
+        let client = InfluxDbClient::new(url.as_str(), "database").with_auth("wrong_user", "pass");
+        let result = rt.block_on(client.query(&read_query));
+        assert_eq!(result, Err(InfluxDbError::AuthenticationFailure));
+
+        let client = InfluxDbClient::new(url.as_str(), "database").with_auth("nopriv_user", "pass");
+        let result = rt.block_on(client.query(&read_query));
+        assert_eq!(result, Err(InfluxDbError::AuthorizationFailure));
+    }
+
+    #[test]
+    fn test_create_database() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.create_database("weather"));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /query?db=database&q=CREATE+DATABASE+%22weather%22 "));
+    }
+
+    #[test]
+    fn test_drop_database() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.drop_database("weather"));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /query?db=database&q=DROP+DATABASE+%22weather%22 "));
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let mut rt = Runtime::new().unwrap();
+        let result = rt.block_on(client.create_subscription(
+            "kapacitor",
+            "autogen",
+            &["udp://h1:9090", "udp://h2:9090"],
+            SubscriptionMode::All,
+        ));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let result = rt.block_on(client.drop_subscription("kapacitor", "autogen"));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /query?db=database&q=CREATE+SUBSCRIPTION+%22kapacitor%22+ON+%22database%22.%22autogen%22+DESTINATIONS+ALL+%27udp%3A%2F%2Fh1%3A9090%27%2C+%27udp%3A%2F%2Fh2%3A9090%27 "), "{}", request);
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /query?db=database&q=DROP+SUBSCRIPTION+%22kapacitor%22+ON+%22database%22.%22autogen%22 "), "{}", request);
+
+        let result = rt.block_on(client.create_subscription(
+            "kapacitor",
+            "autogen",
+            &[],
+            SubscriptionMode::Any,
+        ));
         assert_eq!(
-            vec![("db".to_string(), "database".to_string())],
-            basic_parameters
+            result,
+            Err(InfluxDbError::InvalidQueryError {
+                error: "at least one destination is required".to_string()
+            })
         );
+    }
 
-        let with_auth = InfluxDbClient::new("http://localhost:8068", "database")
-            .with_auth("username", "password");
-        let basic_parameters_with_auth: Vec<(String, String)> = with_auth.into();
+    #[test]
+    fn test_user_management() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let mut rt = Runtime::new().unwrap();
+        let results = vec![
+            rt.block_on(client.create_user("grafana", "it's secret")),
+            rt.block_on(client.grant_privilege("grafana", Privilege::Read, Some("weather"))),
+            rt.block_on(client.grant_privilege("grafana", Privilege::All, None)),
+            rt.block_on(client.revoke_privilege("grafana", Privilege::Write, Some("weather"))),
+            rt.block_on(client.revoke_privilege("grafana", Privilege::All, None)),
+            rt.block_on(client.drop_user("grafana")),
+        ];
+        assert!(results.iter().all(Result::is_ok), "{:?}", results);
+
+        let expected = [
+            "CREATE+USER+%22grafana%22+WITH+PASSWORD+%27it%5C%27s+secret%27",
+            "GRANT+READ+ON+%22weather%22+TO+%22grafana%22",
+            "GRANT+ALL+PRIVILEGES+TO+%22grafana%22",
+            "REVOKE+WRITE+ON+%22weather%22+FROM+%22grafana%22",
+            "REVOKE+ALL+PRIVILEGES+FROM+%22grafana%22",
+            "DROP+USER+%22grafana%22",
+        ];
+        for query in expected.iter() {
+            let request = requests.recv().unwrap();
+            assert!(
+                request.starts_with(&format!("POST /query?db=database&q={} ", query)),
+                "{}",
+                request
+            );
+        }
+
+        let result = rt.block_on(client.grant_privilege("grafana", Privilege::Read, None));
+        assert_eq!(
+            result,
+            Err(InfluxDbError::InvalidQueryError {
+                error: "READ privilege requires a database".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_retention_policy() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let mut rt = Runtime::new().unwrap();
+        let result = rt.block_on(client.create_retention_policy("one_month", "30d", 1, false));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let result = rt.block_on(client.create_retention_policy("forever", "INF", 2, true));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /query?db=database&q=CREATE+RETENTION+POLICY+%22one_month%22+ON+%22database%22+DURATION+30d+REPLICATION+1 "));
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /query?db=database&q=CREATE+RETENTION+POLICY+%22forever%22+ON+%22database%22+DURATION+INF+REPLICATION+2+DEFAULT "));
+
+        for duration in &["30 days", "", "1d; DROP DATABASE x"] {
+            let result =
+                rt.block_on(client.create_retention_policy("one_month", duration, 1, false));
+            match result {
+                Err(InfluxDbError::InvalidQueryError { .. }) => {}
+                _ => panic!("Should cause an InvalidQueryError: {:?}", result),
+            }
+        }
+        assert_eq!(requests.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_alter_retention_policy() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let mut rt = Runtime::new().unwrap();
+        for shard_duration in &[None, Some("1d")] {
+            for replication in &[None, Some(2)] {
+                for is_default in &[false, true] {
+                    let result = rt.block_on(client.alter_retention_policy(
+                        "one_month",
+                        "60d",
+                        *shard_duration,
+                        *replication,
+                        *is_default,
+                    ));
+                    assert!(result.is_ok(), "Should be no error: {:?}", result);
+                }
+            }
+        }
+
+        let queries = requests
+            .try_iter()
+            .map(|request| {
+                assert!(request.starts_with("POST /query?"), "{}", request);
+                let target = request.split(' ').nth(1).unwrap();
+                let url = Url::parse(&format!("http://localhost{}", target)).unwrap();
+                let query = url.query_pairs().find(|(key, _)| key == "q").unwrap().1;
+                query.into_owned()
+            })
+            .collect::<Vec<String>>();
+        let prefix = "ALTER RETENTION POLICY \"one_month\" ON \"database\" DURATION 60d";
         assert_eq!(
+            queries,
             vec![
-                ("db".to_string(), "database".to_string()),
-                ("u".to_string(), "username".to_string()),
-                ("p".to_string(), "password".to_string())
-            ],
-            basic_parameters_with_auth
+                prefix.to_string(),
+                format!("{} DEFAULT", prefix),
+                format!("{} REPLICATION 2", prefix),
+                format!("{} REPLICATION 2 DEFAULT", prefix),
+                format!("{} SHARD DURATION 1d", prefix),
+                format!("{} SHARD DURATION 1d DEFAULT", prefix),
+                format!("{} REPLICATION 2 SHARD DURATION 1d", prefix),
+                format!("{} REPLICATION 2 SHARD DURATION 1d DEFAULT", prefix),
+            ]
         );
 
-        let client = InfluxDbClient::new("http://localhost:8068", "database");
-        assert!(client.auth.is_none());
-        let basic_parameters: Vec<(String, String)> = (&client).into();
+        let result =
+            rt.block_on(client.alter_retention_policy("forever", "INF", None, None, false));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        for (duration, shard_duration) in &[("30 days", None), ("", None), ("1h", Some("INF"))] {
+            let result = rt.block_on(client.alter_retention_policy(
+                "one_month",
+                duration,
+                *shard_duration,
+                None,
+                false,
+            ));
+            match result {
+                Err(InfluxDbError::InvalidQueryError { .. }) => {}
+                _ => panic!("Should cause an InvalidQueryError: {:?}", result),
+            }
+        }
+        assert_eq!(requests.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_drop_shard() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new().unwrap().block_on(client.drop_shard(3));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /query?db=database&q=DROP+SHARD+3 "));
+    }
+
+    #[test]
+    fn test_drop_retention_policy() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.drop_retention_policy("one_month"));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with(
+            "POST /query?db=database&q=DROP+RETENTION+POLICY+%22one_month%22+ON+%22database%22 "
+        ));
+    }
+
+    #[test]
+    fn test_create_continuous_query() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.create_continuous_query(
+                "hourly",
+                "1h",
+                "SELECT mean(temperature) INTO weather_hourly FROM weather",
+            ));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /query?db=database&q=CREATE+CONTINUOUS+QUERY+%22hourly%22+ON+%22database%22+BEGIN+SELECT+mean%28temperature%29+INTO+weather_hourly+FROM+weather+GROUP+BY+time%281h%29+END "));
+    }
+
+    #[test]
+    fn test_create_continuous_query_invalid_interval() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let mut rt = Runtime::new().unwrap();
+        for interval in &["1 hour", "", "INF", "1h) END; DROP DATABASE x"] {
+            let result = rt.block_on(client.create_continuous_query(
+                "hourly",
+                interval,
+                "SELECT mean(temperature) INTO weather_hourly FROM weather",
+            ));
+            match result {
+                Err(InfluxDbError::InvalidQueryError { .. }) => {}
+                _ => panic!("Should cause an InvalidQueryError: {:?}", result),
+            }
+        }
+        assert_eq!(requests.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_drop_continuous_query() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.drop_continuous_query("hourly"));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with(
+            "POST /query?db=database&q=DROP+CONTINUOUS+QUERY+%22hourly%22+ON+%22database%22 "
+        ));
+    }
+
+    #[test]
+    fn test_is_data_query() {
+        assert!(is_data_query("SELECT * FROM weather"));
+        assert!(is_data_query("  show databases"));
+        assert!(!is_data_query("CREATE DATABASE weather"));
+        assert!(!is_data_query("SELECT * INTO weather_copy FROM weather"));
+        assert!(!is_data_query("SELECT *\nINTO weather_copy\tFROM weather"));
+        assert!(is_data_query("SELECT * FROM weather WHERE note = ' INTO '"));
+        assert!(!is_data_query(
+            "CREATE CONTINUOUS QUERY cq ON db BEGIN SELECT mean(x) INTO y FROM z GROUP BY time(1h) END"
+        ));
+    }
+
+    #[test]
+    fn test_ping() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\nX-Influxdb-Build: OSS\r\nX-Influxdb-Version: 1.7.6\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new().unwrap().block_on(client.ping());
         assert_eq!(
-            vec![("db".to_string(), "database".to_string())],
-            basic_parameters
+            result.unwrap(),
+            PingResponse {
+                build: "OSS".to_string(),
+                version: "1.7.6".to_string(),
+            }
         );
+    }
 
-        let with_auth = InfluxDbClient::new("http://localhost:8068", "database")
-            .with_auth("username", "password");
-        let basic_parameters_with_auth: Vec<(String, String)> = (&with_auth).into();
+    #[test]
+    fn test_detect_version() {
+        let cases = vec![
+            (
+                "X-Influxdb-Build: OSS\r\nX-Influxdb-Version: 1.7.6\r\n",
+                InfluxDbVersion::V1 {
+                    version: "1.7.6".to_string(),
+                },
+            ),
+            (
+                "X-Influxdb-Build: OSS\r\nX-Influxdb-Version: v2.0.4\r\n",
+                InfluxDbVersion::V2 {
+                    version: "v2.0.4".to_string(),
+                },
+            ),
+            (
+                "X-Influxdb-Version: 2.0.0-beta.1\r\n",
+                InfluxDbVersion::V2 {
+                    version: "2.0.0-beta.1".to_string(),
+                },
+            ),
+            (
+                "X-Influxdb-Version: unknown\r\n",
+                InfluxDbVersion::V2 {
+                    version: "unknown".to_string(),
+                },
+            ),
+            (
+                "X-Influxdb-Build: ENT\r\nX-Influxdb-Version: unknown\r\n",
+                InfluxDbVersion::V1 {
+                    version: "unknown".to_string(),
+                },
+            ),
+        ];
+        for (headers, expected) in cases {
+            let response = format!(
+                "HTTP/1.1 204 No Content\r\nConnection: close\r\n{}\r\n",
+                headers
+            );
+            let (url, _) = mock_server(
+                Duration::from_millis(0),
+                Box::leak(response.into_boxed_str()),
+            );
+            let client = InfluxDbClient::new(url, "database");
+            let result = Runtime::new().unwrap().block_on(client.detect_version());
+            assert_eq!(result, Ok(expected));
+        }
+    }
+
+    #[test]
+    fn test_with_auto_detect() {
+        let (url, requests) = mock_server_with(Duration::from_millis(0), |request: &str| {
+            if request.starts_with("GET /ping ") {
+                "HTTP/1.1 204 No Content\r\nConnection: close\r\nX-Influxdb-Build: OSS\r\nX-Influxdb-Version: v2.0.4\r\n\r\n"
+            } else {
+                "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n"
+            }
+        });
+        let mut rt = Runtime::new().unwrap();
+        let client = rt
+            .block_on(
+                InfluxDbClient::new(url, "weather")
+                    .with_token("token")
+                    .with_org("org")
+                    .with_auto_detect(),
+            )
+            .unwrap();
         assert_eq!(
-            vec![
-                ("db".to_string(), "database".to_string()),
-                ("u".to_string(), "username".to_string()),
-                ("p".to_string(), "password".to_string())
-            ],
-            basic_parameters_with_auth
+            client.version(),
+            Some(&InfluxDbVersion::V2 {
+                version: "v2.0.4".to_string()
+            })
+        );
+        let write_query = InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+            .add_field("temperature", 82);
+        assert!(rt.block_on(client.query(&write_query)).is_ok());
+
+        let requests = requests.try_iter().collect::<Vec<String>>();
+        assert!(requests[0].starts_with("GET /ping "));
+        // Without a bucket, the database is used as bucket
+        assert!(requests[1].starts_with("POST /api/v2/write?org=org&bucket=weather&precision=s "));
+    }
+
+    #[test]
+    fn test_write_url_with_version() {
+        let client = InfluxDbClient::new("http://localhost:8068", "database")
+            .with_org("org")
+            .with_bucket("bucket")
+            .with_version(InfluxDbVersion::V1 {
+                version: "1.7.6".to_string(),
+            });
+        assert_eq!(
+            client.write_url("s", None).unwrap().as_str(),
+            "http://localhost:8068/write?db=database&org=org&precision=s"
+        );
+
+        let client = client.with_version(InfluxDbVersion::V2 {
+            version: "2.0.4".to_string(),
+        });
+        assert_eq!(
+            client.write_url("s", None).unwrap().as_str(),
+            "http://localhost:8068/api/v2/write?org=org&bucket=bucket&precision=s"
+        );
+
+        // InfluxDB 2.x without an organization is written to through the compatibility API
+        let client = InfluxDbClient::new("http://localhost:8068", "database").with_version(
+            InfluxDbVersion::V2 {
+                version: "2.0.4".to_string(),
+            },
+        );
+        assert_eq!(
+            client.write_url("s", None).unwrap().as_str(),
+            "http://localhost:8068/write?db=database&precision=s"
+        );
+    }
+
+    #[test]
+    fn test_ping_without_version() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new().unwrap().block_on(client.ping());
+        match result {
+            Err(InfluxDbError::ProtocolError { .. }) => {}
+            _ => panic!("Should cause a ProtocolError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_ping_unsuccessful_status() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nX-Influxdb-Version: 1.7.6\r\nContent-Length: 0\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new().unwrap().block_on(client.ping());
+        match result {
+            Err(InfluxDbError::HttpError { status, .. }) => assert_eq!(status, 503),
+            _ => panic!("Should cause a HttpError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_is_healthy() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\nX-Influxdb-Version: 1.7.6\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new().unwrap().block_on(client.is_healthy());
+        assert!(result.unwrap());
+    }
+
+    #[test]
+    fn test_is_healthy_unhealthy() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
         );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new().unwrap().block_on(client.is_healthy());
+        assert!(!result.unwrap());
+    }
+
+    #[test]
+    fn test_is_healthy_unreachable() {
+        // Nothing listens on the discard port
+        let client = InfluxDbClient::new("http://127.0.0.1:9", "database");
+        let result = Runtime::new().unwrap().block_on(client.is_healthy());
+        assert!(result.is_err(), "Should cause an error: {:?}", result);
+    }
+
+    #[test]
+    fn test_ping_response_is_enterprise() {
+        let response = PingResponse {
+            build: "Enterprise".to_string(),
+            version: "1.7.6".to_string(),
+        };
+        assert!(response.is_enterprise());
+        let response = PingResponse {
+            build: "OSS".to_string(),
+            ..response
+        };
+        assert!(!response.is_enterprise());
     }
 }
@@ -0,0 +1,117 @@
+//! Limits the rate of requests sent to InfluxDB
+//!
+//! Set with [`InfluxDbClient::with_rate_limiter`](crate::client::InfluxDbClient::with_rate_limiter).
+
+use futures::future::{self, Either};
+use futures::Future;
+use tokio::timer::Delay;
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::InfluxDbError;
+
+/// Longest interval between two requests, about 136 years
+const MAX_INTERVAL: Duration = Duration::from_secs(u32::MAX as u64);
+
+/// Leaky bucket spacing requests evenly, so that at most `requests_per_second` requests are sent
+/// per second
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    /// Earliest time the next request may be sent at
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Returns `None` if `requests_per_second` is not a positive number
+    ///
+    /// Rates so small that the interval between requests exceeds [`MAX_INTERVAL`] are clamped.
+    pub(crate) fn new(requests_per_second: f64) -> Option<Self> {
+        if requests_per_second <= 0.0 || !requests_per_second.is_finite() {
+            return None;
+        }
+        let interval = Duration::try_from_secs_f64(1.0 / requests_per_second)
+            .unwrap_or(MAX_INTERVAL)
+            .min(MAX_INTERVAL);
+        Some(RateLimiter {
+            interval,
+            next_slot: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Reserves the next free slot and returns a future which resolves once it has been reached
+    pub(crate) fn acquire(&self) -> impl Future<Item = (), Error = InfluxDbError> {
+        let now = Instant::now();
+        let slot = {
+            // The slot is only ever advanced, so a panic while locked cannot corrupt it
+            let mut next_slot = self
+                .next_slot
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let slot = (*next_slot).max(now);
+            // A slot out of the range of `Instant` is never reached, so it can be reused
+            *next_slot = slot.checked_add(self.interval).unwrap_or(slot);
+            slot
+        };
+        if slot <= now {
+            return Either::B(future::ok(()));
+        }
+        Either::A(
+            Delay::new(slot).map_err(|err| InfluxDbError::ProtocolError {
+                error: format!("{}", err),
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateLimiter, MAX_INTERVAL};
+    use crate::client::tests::mock_server;
+    use crate::client::InfluxDbClient;
+    use crate::query::{InfluxDbQuery, Timestamp};
+    use futures::future::join_all;
+    use std::time::{Duration, Instant};
+    use tokio::runtime::current_thread::Runtime;
+
+    #[test]
+    fn test_new() {
+        assert!(RateLimiter::new(10.0).is_some());
+        assert!(RateLimiter::new(0.0).is_none());
+        assert!(RateLimiter::new(-1.0).is_none());
+        assert!(RateLimiter::new(std::f64::NAN).is_none());
+        assert!(RateLimiter::new(std::f64::INFINITY).is_none());
+        for tiny in &[1e-300, std::f64::MIN_POSITIVE] {
+            let limiter = RateLimiter::new(*tiny).unwrap();
+            assert_eq!(limiter.interval, MAX_INTERVAL);
+        }
+    }
+
+    #[test]
+    fn test_with_rate_limiter() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database").with_rate_limiter(50.0);
+        // Slots are reserved when the requests are created
+        let start = Instant::now();
+        let writes = (0..20)
+            .map(|i| {
+                client.query(
+                    &InfluxDbQuery::write_query(Timestamp::SECONDS(i), "weather")
+                        .add_field("temperature", 82),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let results = Runtime::new().unwrap().block_on(join_all(writes));
+        let elapsed = start.elapsed();
+        assert_eq!(results.map(|results| results.len()), Ok(20));
+        assert_eq!(requests.try_iter().count(), 20);
+        // The first request is sent immediately, the others 20ms apart
+        assert!(elapsed >= Duration::from_millis(380), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+    }
+}
@@ -0,0 +1,460 @@
+//! Tracing of queries, enabled with the `tracing` feature
+//!
+//! Every query sent by [`InfluxDbClient::query`](crate::client::InfluxDbClient::query) or
+//! `json_query` is logged with a `tracing` event, with the literal of any `PASSWORD` redacted.
+//! With [`InfluxDbClient::with_tracing_propagation`](crate::client::InfluxDbClient::with_tracing_propagation),
+//! the OpenTelemetry trace context is additionally injected with the globally registered
+//! propagator, so an application has to register one, e.g. the W3C `TraceContextPropagator` of
+//! `opentelemetry_sdk`.
+
+use futures::Future;
+use opentelemetry::global;
+use opentelemetry::propagation::Injector;
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{Context, KeyValue};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::r#async::Chunk;
+use reqwest::StatusCode;
+
+use std::any::Any;
+use std::time::Instant;
+
+use crate::error::InfluxDbError;
+use crate::query::write_query::InfluxDbWriteQuery;
+use crate::query::{InfluxDbQuery, QueryType};
+
+/// Name of the span created for each query
+pub(crate) const QUERY_SPAN_NAME: &str = "influxdb.query";
+
+/// Description of a query recorded in its span and log events
+pub(crate) struct QueryInfo {
+    query_type: QueryType,
+    text: String,
+    measurement: Option<String>,
+    /// Number of fields and tags of a write query
+    counts: Option<(usize, usize)>,
+}
+
+impl QueryInfo {
+    pub(crate) fn new<Q>(q: &Q) -> Self
+    where
+        Q: Any + InfluxDbQuery,
+    {
+        let query_type = q.get_type();
+        let text = q
+            .build()
+            .map(|query| query.into_string())
+            .unwrap_or_default();
+        let write_query = (q as &dyn Any).downcast_ref::<InfluxDbWriteQuery>();
+        let measurement = match write_query {
+            Some(write_query) => Some(write_query.get_measurement().to_string()),
+            None if query_type == QueryType::ReadQuery => read_measurement(&text),
+            None => None,
+        };
+        QueryInfo {
+            query_type,
+            text: redact_passwords(&text),
+            measurement,
+            counts: write_query
+                .map(|write_query| (write_query.field_count(), write_query.tag_count())),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self.query_type {
+            QueryType::ReadQuery => "read",
+            QueryType::WriteQuery => "write",
+        }
+    }
+
+    /// Emits a `DEBUG` event for a successful query, or an `ERROR` event for a failed one
+    fn log<T>(&self, start: Instant, result: &Result<T, InfluxDbError>) {
+        let latency_ms = start.elapsed().as_millis();
+        let measurement = self.measurement.as_deref().unwrap_or_default();
+        match (result, self.counts) {
+            (Ok(_), None) => tracing::debug!(
+                query.text = %self.text,
+                query.type = %self.type_name(),
+                query.latency_ms = %latency_ms,
+                "query succeeded"
+            ),
+            (Ok(_), Some((field_count, tag_count))) => tracing::debug!(
+                query.text = %self.text,
+                query.type = %self.type_name(),
+                query.latency_ms = %latency_ms,
+                write.measurement = %measurement,
+                write.field_count = %field_count,
+                write.tag_count = %tag_count,
+                "query succeeded"
+            ),
+            (Err(err), None) => tracing::error!(
+                query.text = %self.text,
+                query.type = %self.type_name(),
+                query.latency_ms = %latency_ms,
+                query.error = %err,
+                "query failed"
+            ),
+            (Err(err), Some((field_count, tag_count))) => tracing::error!(
+                query.text = %self.text,
+                query.type = %self.type_name(),
+                query.latency_ms = %latency_ms,
+                query.error = %err,
+                write.measurement = %measurement,
+                write.field_count = %field_count,
+                write.tag_count = %tag_count,
+                "query failed"
+            ),
+        }
+    }
+}
+
+/// Sends a query with `send` and logs its result, recording it in a child span of the current
+/// span whose trace context is added to `headers` if `propagate` is set
+pub(crate) fn instrument<F>(
+    info: QueryInfo,
+    propagate: bool,
+    mut headers: HeaderMap,
+    send: F,
+) -> Box<dyn Future<Item = (StatusCode, Chunk), Error = InfluxDbError>>
+where
+    F: FnOnce(HeaderMap) -> Box<dyn Future<Item = (StatusCode, Chunk), Error = InfluxDbError>>,
+{
+    let span = if propagate {
+        Some(QuerySpan::start(&info, &mut headers))
+    } else {
+        None
+    };
+    let start = Instant::now();
+    Box::new(send(headers).then(move |result| {
+        info.log(start, &result);
+        if let Some(span) = span {
+            span.end(&result);
+        }
+        result
+    }))
+}
+
+/// Span of a query
+struct QuerySpan {
+    context: Context,
+}
+
+impl QuerySpan {
+    /// Starts a child span of the current span and adds its trace context to `headers`
+    fn start(info: &QueryInfo, headers: &mut HeaderMap) -> Self {
+        let tracer = global::tracer("influxdb");
+        let mut attributes = vec![KeyValue::new("influxdb.query_type", info.type_name())];
+        if let Some(measurement) = &info.measurement {
+            attributes.push(KeyValue::new("influxdb.measurement", measurement.clone()));
+        }
+        let parent = Context::current();
+        let span = tracer
+            .span_builder(QUERY_SPAN_NAME)
+            .with_kind(SpanKind::Client)
+            .with_attributes(attributes)
+            .start_with_context(&tracer, &parent);
+        let context = parent.with_span(span);
+        inject(&context, headers);
+        QuerySpan { context }
+    }
+
+    /// Ends the span, marking it as failed if the query failed
+    fn end<T>(self, result: &Result<T, InfluxDbError>) {
+        let span = self.context.span();
+        if let Err(err) = result {
+            span.set_status(Status::error(err.to_string()));
+        }
+        span.end();
+    }
+}
+
+/// Adds the trace context of the current span to `headers`, unless they already contain one
+pub(crate) fn inject_current(headers: &mut HeaderMap) {
+    if !headers.contains_key("traceparent") {
+        inject(&Context::current(), headers);
+    }
+}
+
+fn inject(context: &Context, headers: &mut HeaderMap) {
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(context, &mut HeaderInjector(headers))
+    });
+}
+
+/// Returns the first measurement a read query selects from, e.g. `weather` for
+/// `SELECT * FROM "weather" WHERE time > now() - 1h`
+fn read_measurement(read_query: &str) -> Option<String> {
+    let uppercase = read_query.to_ascii_uppercase();
+    let start = uppercase.find(" FROM ")? + " FROM ".len();
+    let source = read_query[start..].trim_start();
+    let measurement = match source.strip_prefix('"') {
+        Some(quoted) => &quoted[..quoted.find('"')?],
+        None => source
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ';' || c == ')')
+            .next()?,
+    };
+    if measurement.is_empty() || measurement.starts_with('(') {
+        return None;
+    }
+    Some(measurement.to_string())
+}
+
+/// Replaces the string literals following a `PASSWORD` keyword, e.g. the password of
+/// `CREATE USER "admin" WITH PASSWORD 'secret'`, so that passwords are never logged
+fn redact_passwords(query: &str) -> String {
+    let start = match query.to_ascii_uppercase().find("PASSWORD") {
+        Some(start) => start,
+        None => return query.to_string(),
+    };
+    let mut redacted = query[..start].to_string();
+    let mut chars = query[start..].chars();
+    let mut in_literal = false;
+    while let Some(c) = chars.next() {
+        if !in_literal {
+            redacted.push(c);
+            in_literal = c == '\'';
+        } else if c == '\\' {
+            chars.next();
+        } else if c == '\'' {
+            redacted.push_str("[REDACTED]'");
+            in_literal = false;
+        }
+    }
+    redacted
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl<'a> Injector for HeaderInjector<'a> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_measurement, redact_passwords, QUERY_SPAN_NAME};
+    use crate::client::tests::mock_server;
+    use crate::client::InfluxDbClient;
+    use crate::query::{InfluxDbQuery, Timestamp};
+    use opentelemetry::trace::{TraceContextExt, Tracer, TracerProvider as _};
+    use opentelemetry::{global, Context, Key, Value};
+    use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use std::future::{self, Future};
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::runtime::current_thread::Runtime;
+    use tracing_test::traced_test;
+
+    /// Exporter keeping finished spans in memory
+    #[derive(Debug, Clone, Default)]
+    struct RecordingExporter {
+        spans: Arc<Mutex<Vec<SpanData>>>,
+    }
+
+    impl SpanExporter for RecordingExporter {
+        fn export(
+            &mut self,
+            batch: Vec<SpanData>,
+        ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+            self.spans.lock().unwrap().extend(batch);
+            Box::pin(future::ready(Ok(())))
+        }
+    }
+
+    /// Registers a tracer provider recording all spans, shared by all tests as it is global
+    fn recorded_spans() -> Arc<Mutex<Vec<SpanData>>> {
+        static SPANS: Mutex<Option<Arc<Mutex<Vec<SpanData>>>>> = Mutex::new(None);
+        SPANS
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| {
+                let exporter = RecordingExporter::default();
+                let spans = exporter.spans.clone();
+                global::set_tracer_provider(
+                    TracerProvider::builder()
+                        .with_simple_exporter(exporter)
+                        .build(),
+                );
+                global::set_text_map_propagator(TraceContextPropagator::new());
+                spans
+            })
+            .clone()
+    }
+
+    fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+        request.lines().find_map(|line| {
+            let (key, value) = line.split_at(line.find(':')?);
+            if key.eq_ignore_ascii_case(name) {
+                Some(value[1..].trim())
+            } else {
+                None
+            }
+        })
+    }
+
+    #[test]
+    fn test_query_span_and_traceparent() {
+        let spans = recorded_spans();
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database").with_tracing_propagation(true);
+        let parent = global::tracer_provider().tracer("test").start("parent");
+        let parent_context = Context::current_with_span(parent);
+        let parent_span = parent_context.span().span_context().clone();
+
+        let result = {
+            let _guard = parent_context.clone().attach();
+            let query = InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                .add_field("temperature", 82);
+            Runtime::new().unwrap().block_on(client.query(&query))
+        };
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let child = spans
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|span| {
+                span.name == QUERY_SPAN_NAME
+                    && span.span_context.trace_id() == parent_span.trace_id()
+            })
+            .cloned()
+            .expect("query span should be recorded");
+        assert_eq!(child.parent_span_id, parent_span.span_id());
+        let attribute = |key: &'static str| {
+            child
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == Key::from_static_str(key))
+                .map(|attribute| attribute.value.clone())
+        };
+        assert_eq!(attribute("influxdb.query_type"), Some(Value::from("write")));
+        assert_eq!(
+            attribute("influxdb.measurement"),
+            Some(Value::from("weather"))
+        );
+
+        let request = requests.recv().unwrap();
+        assert_eq!(
+            header(&request, "traceparent"),
+            Some(
+                format!(
+                    "00-{}-{}-01",
+                    parent_span.trace_id(),
+                    child.span_context.span_id()
+                )
+                .as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn test_tracing_propagation_disabled() {
+        recorded_spans();
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let parent = global::tracer_provider().tracer("test").start("parent");
+        let _guard = Context::current_with_span(parent).attach();
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        let _ = Runtime::new().unwrap().block_on(client.query(&query));
+
+        assert_eq!(header(&requests.recv().unwrap(), "traceparent"), None);
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_query_events() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let mut rt = Runtime::new().unwrap();
+        let query = InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+            .add_tag("location", "us-midwest")
+            .add_field("temperature", 82)
+            .add_field("humidity", 60);
+        assert!(rt.block_on(client.query(&query)).is_ok());
+        assert!(logs_contain("DEBUG"));
+        assert!(logs_contain(
+            "query.text=weather,location=us-midwest temperature=82i,humidity=60i 1"
+        ));
+        assert!(logs_contain("query.type=write"));
+        assert!(logs_contain("query.latency_ms="));
+        assert!(logs_contain("write.measurement=weather"));
+        assert!(logs_contain("write.field_count=2"));
+        assert!(logs_contain("write.tag_count=1"));
+
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 400 Bad Request\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+        assert!(rt.block_on(client.query(&query)).is_err());
+        assert!(logs_contain("ERROR"));
+        assert!(logs_contain("query.text=SELECT * FROM weather"));
+        assert!(logs_contain("query.type=read"));
+        assert!(logs_contain("query.error="));
+
+        let query = InfluxDbQuery::raw_read_query("CREATE USER admin WITH PASSWORD 'secret'");
+        assert!(rt.block_on(client.query(&query)).is_err());
+        assert!(logs_contain(
+            "query.text=CREATE USER admin WITH PASSWORD '[REDACTED]'"
+        ));
+        assert!(!logs_contain("secret"));
+    }
+
+    #[test]
+    fn test_redact_passwords() {
+        assert_eq!(
+            redact_passwords(
+                "CREATE USER \"admin\" WITH PASSWORD 'it\\'s secret' WITH ALL PRIVILEGES"
+            ),
+            "CREATE USER \"admin\" WITH PASSWORD '[REDACTED]' WITH ALL PRIVILEGES"
+        );
+        assert_eq!(
+            redact_passwords("set password for admin = 'secret'"),
+            "set password for admin = '[REDACTED]'"
+        );
+        assert_eq!(
+            redact_passwords("SELECT * FROM weather WHERE location = 'us-midwest'"),
+            "SELECT * FROM weather WHERE location = 'us-midwest'"
+        );
+    }
+
+    #[test]
+    fn test_read_measurement() {
+        assert_eq!(
+            read_measurement("SELECT * FROM weather WHERE time > now() - 1h"),
+            Some("weather".to_string())
+        );
+        assert_eq!(
+            read_measurement("select mean(temperature) from \"my weather\" group by time(1m)"),
+            Some("my weather".to_string())
+        );
+        assert_eq!(
+            read_measurement("SELECT * FROM weather;SELECT * FROM traffic"),
+            Some("weather".to_string())
+        );
+        assert_eq!(
+            read_measurement("SELECT * FROM (SELECT * FROM weather)"),
+            None
+        );
+        assert_eq!(read_measurement("SHOW DATABASES"), None);
+    }
+}
@@ -0,0 +1,274 @@
+//! Buffer which collects write queries and sends them in batches
+//!
+//! Unlike [`InfluxDbClient::write_stream`](crate::client::InfluxDbClient::write_stream), the
+//! buffer can be shared between threads, and [`start_auto_flush`](crate::client::InfluxDbWriteBuffer::start_auto_flush)
+//! flushes it on a background thread without driving a stream.
+
+use futures::{future, Future};
+use tokio::runtime::current_thread::Runtime;
+
+use std::mem;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::client::InfluxDbClient;
+use crate::error::InfluxDbError;
+use crate::query::write_batch::InfluxDbWriteBatch;
+use crate::query::write_query::InfluxDbWriteQuery;
+
+/// Queues write queries and sends them to InfluxDB in a single request once `max_size` queries
+/// have been queued, or when flushed
+///
+/// All queued queries must use the same timestamp precision and retention policy, as they are
+/// sent like [`InfluxDbClient::write_many`](crate::client::InfluxDbClient::write_many). Queries
+/// are removed from the buffer when a flush starts, so they are lost if the request fails.
+///
+/// # Examples
+///
+/// ```rust
+/// use influxdb::client::{InfluxDbClient, InfluxDbWriteBuffer};
+/// use influxdb::query::{InfluxDbQuery, Timestamp};
+/// use std::time::Duration;
+///
+/// let client = InfluxDbClient::new("http://localhost:8086", "test");
+/// let buffer = InfluxDbWriteBuffer::new(client, 1_000, Duration::from_secs(1));
+/// let _future = buffer.push(
+///     InfluxDbQuery::write_query(Timestamp::now(), "weather").add_field("temperature", 82),
+/// );
+///
+/// assert_eq!(buffer.len(), 1);
+/// ```
+pub struct InfluxDbWriteBuffer {
+    client: InfluxDbClient,
+    max_size: usize,
+    flush_interval: Duration,
+    queries: Mutex<Vec<InfluxDbWriteQuery>>,
+}
+
+impl InfluxDbWriteBuffer {
+    /// Creates an empty buffer which sends its queries with `client`
+    ///
+    /// `flush_interval` is only used by [`start_auto_flush`](crate::client::InfluxDbWriteBuffer::start_auto_flush).
+    /// A `max_size` of 0 is treated as 1.
+    pub fn new(client: InfluxDbClient, max_size: usize, flush_interval: Duration) -> Self {
+        InfluxDbWriteBuffer {
+            client,
+            max_size: max_size.max(1),
+            flush_interval,
+            queries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the number of queued write queries
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if no write queries are queued
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Queues `query`, and flushes the buffer if it contains `max_size` queries
+    ///
+    /// The query is queued immediately, while the returned future sends the flush request, if any.
+    pub fn push(
+        &self,
+        query: InfluxDbWriteQuery,
+    ) -> Box<dyn Future<Item = (), Error = InfluxDbError>> {
+        let full = {
+            let mut queries = self.lock();
+            queries.push(query);
+            queries.len() >= self.max_size
+        };
+        if full {
+            Box::new(self.flush().map(|_| ()))
+        } else {
+            Box::new(future::ok(()))
+        }
+    }
+
+    /// Sends all queued queries in a single request and returns the response of InfluxDB
+    ///
+    /// Resolves to an empty string without sending a request if the buffer is empty.
+    pub fn flush(&self) -> Box<dyn Future<Item = String, Error = InfluxDbError>> {
+        let queries = mem::take(&mut *self.lock());
+        if queries.is_empty() {
+            return Box::new(future::ok(String::new()));
+        }
+        self.client.write_batch(InfluxDbWriteBatch::from(queries))
+    }
+
+    /// Moves the buffer to a background thread, which queues the queries sent through the
+    /// returned `Sender`
+    ///
+    /// The buffer is flushed once it contains `max_size` queries, and `flush_interval` after the
+    /// first query of a batch was received. When all senders are dropped, the remaining queries
+    /// are flushed and the thread ends. As there is no caller to return them to, errors of
+    /// flushes are discarded; use [`InfluxDbClient::write_stream`](crate::client::InfluxDbClient::write_stream)
+    /// to receive them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use influxdb::client::{InfluxDbClient, InfluxDbWriteBuffer};
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    /// use std::time::Duration;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let (sender, handle) =
+    ///     InfluxDbWriteBuffer::new(client, 1_000, Duration::from_secs(1)).start_auto_flush();
+    /// sender
+    ///     .send(InfluxDbQuery::write_query(Timestamp::now(), "weather").add_field("temperature", 82))
+    ///     .unwrap();
+    /// drop(sender);
+    /// handle.join().unwrap();
+    /// ```
+    pub fn start_auto_flush(self) -> (Sender<InfluxDbWriteQuery>, JoinHandle<()>) {
+        let (sender, receiver) = channel();
+        let handle = thread::spawn(move || {
+            let mut runtime = Runtime::new().expect("failed to start runtime");
+            let mut flush_deadline: Option<Instant> = None;
+            loop {
+                let received = match flush_deadline {
+                    Some(deadline) => {
+                        receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    }
+                    None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                let flush = match received {
+                    Ok(query) => {
+                        flush_deadline.get_or_insert_with(|| Instant::now() + self.flush_interval);
+                        let mut queries = self.lock();
+                        queries.push(query);
+                        queries.len() >= self.max_size
+                    }
+                    Err(RecvTimeoutError::Timeout) => true,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        let _ = runtime.block_on(self.flush());
+                        return;
+                    }
+                };
+                if flush {
+                    let _ = runtime.block_on(self.flush());
+                    flush_deadline = None;
+                }
+            }
+        });
+        (sender, handle)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<InfluxDbWriteQuery>> {
+        // Queries are only pushed and taken, so a panic while locked cannot corrupt them
+        self.queries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InfluxDbWriteBuffer;
+    use crate::client::tests::mock_server;
+    use crate::client::InfluxDbClient;
+    use crate::query::{InfluxDbQuery, Timestamp};
+    use std::time::Duration;
+    use tokio::runtime::current_thread::Runtime;
+
+    const NO_CONTENT: &str = "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n";
+
+    fn body(request: &str) -> &str {
+        &request[request.find("\r\n\r\n").unwrap() + 4..]
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<InfluxDbWriteBuffer>();
+    }
+
+    #[test]
+    fn test_push_flushes_at_max_size() {
+        let (url, requests) = mock_server(Duration::from_millis(0), NO_CONTENT);
+        let buffer = InfluxDbWriteBuffer::new(
+            InfluxDbClient::new(url, "database"),
+            100,
+            Duration::from_secs(60),
+        );
+        let mut rt = Runtime::new().unwrap();
+        for i in 0..100 {
+            let query = InfluxDbQuery::write_query(Timestamp::SECONDS(i), "weather")
+                .add_field("temperature", i as u64);
+            let result = rt.block_on(buffer.push(query));
+            assert!(result.is_ok(), "Should be no error: {:?}", result);
+        }
+        assert!(buffer.is_empty());
+
+        let requests = requests.try_iter().collect::<Vec<String>>();
+        assert_eq!(requests.len(), 1);
+        let lines = body(&requests[0]).lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 100);
+        assert_eq!(lines[0], "weather temperature=0i 0");
+        assert_eq!(lines[99], "weather temperature=99i 99");
+    }
+
+    #[test]
+    fn test_flush() {
+        let (url, requests) = mock_server(Duration::from_millis(0), NO_CONTENT);
+        let buffer = InfluxDbWriteBuffer::new(
+            InfluxDbClient::new(url, "database"),
+            100,
+            Duration::from_secs(60),
+        );
+        let mut rt = Runtime::new().unwrap();
+        assert_eq!(rt.block_on(buffer.flush()), Ok(String::new()));
+
+        let _ = buffer.push(
+            InfluxDbQuery::write_query(Timestamp::SECONDS(1), "weather")
+                .add_field("temperature", 82),
+        );
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(rt.block_on(buffer.flush()), Ok(String::new()));
+        assert!(buffer.is_empty());
+
+        let requests = requests.try_iter().collect::<Vec<String>>();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(body(&requests[0]), "weather temperature=82i 1");
+    }
+
+    #[test]
+    fn test_start_auto_flush() {
+        let (url, requests) = mock_server(Duration::from_millis(0), NO_CONTENT);
+        let buffer = InfluxDbWriteBuffer::new(
+            InfluxDbClient::new(url, "database"),
+            100,
+            Duration::from_millis(50),
+        );
+        let (sender, handle) = buffer.start_auto_flush();
+        for i in 0..3 {
+            sender
+                .send(
+                    InfluxDbQuery::write_query(Timestamp::SECONDS(i), "weather")
+                        .add_field("temperature", 82),
+                )
+                .unwrap();
+        }
+        // Flushed after the flush interval
+        let request = requests.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(body(&request).lines().count(), 3);
+
+        // Flushed when the sender is dropped
+        sender
+            .send(
+                InfluxDbQuery::write_query(Timestamp::SECONDS(3), "weather")
+                    .add_field("temperature", 82),
+            )
+            .unwrap();
+        drop(sender);
+        handle.join().unwrap();
+        let request = requests.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(body(&request), "weather temperature=82i 3");
+    }
+}
@@ -0,0 +1,112 @@
+//! Batching of write queries for [`InfluxDbClient::write_stream`](crate::client::InfluxDbClient::write_stream)
+
+use futures::stream::Fuse;
+use futures::{Async, Future, Poll, Stream};
+use tokio::timer::Delay;
+
+use std::mem;
+use std::time::{Duration, Instant};
+
+use crate::client::InfluxDbClient;
+use crate::error::InfluxDbError;
+use crate::query::write_query::InfluxDbWriteQuery;
+
+/// Stream which sends the write queries of `input` in batches and yields the response to each batch
+pub(crate) struct WriteStream<S> {
+    client: InfluxDbClient,
+    input: Fuse<S>,
+    batch_size: usize,
+    flush_interval: Duration,
+    batch: Vec<InfluxDbWriteQuery>,
+    /// Elapses when the current batch has to be sent, started when its first query is received
+    flush_deadline: Option<Delay>,
+    /// Request of the batch which is currently being sent
+    request: Option<Box<dyn Future<Item = String, Error = InfluxDbError>>>,
+}
+
+impl<S> WriteStream<S>
+where
+    S: Stream<Item = InfluxDbWriteQuery, Error = InfluxDbError>,
+{
+    pub(crate) fn new(
+        client: InfluxDbClient,
+        batch_size: usize,
+        flush_interval: Duration,
+        input: S,
+    ) -> Self {
+        WriteStream {
+            client,
+            input: input.fuse(),
+            batch_size: batch_size.max(1),
+            flush_interval,
+            batch: Vec::new(),
+            flush_deadline: None,
+            request: None,
+        }
+    }
+
+    /// Returns `true` if the current batch has to be sent
+    fn should_flush(&mut self) -> Result<bool, InfluxDbError> {
+        if self.batch.is_empty() {
+            return Ok(false);
+        }
+        if self.batch.len() >= self.batch_size || self.input.is_done() {
+            return Ok(true);
+        }
+        match &mut self.flush_deadline {
+            Some(deadline) => deadline
+                .poll()
+                .map(|ready| ready.is_ready())
+                .map_err(|err| InfluxDbError::ProtocolError {
+                    error: format!("{}", err),
+                }),
+            None => Ok(false),
+        }
+    }
+}
+
+impl<S> Stream for WriteStream<S>
+where
+    S: Stream<Item = InfluxDbWriteQuery, Error = InfluxDbError>,
+{
+    type Item = String;
+    type Error = InfluxDbError;
+
+    fn poll(&mut self) -> Poll<Option<String>, InfluxDbError> {
+        loop {
+            // Only one batch is sent at a time, so the input is not read while a request is pending
+            if let Some(request) = &mut self.request {
+                let result = request.poll();
+                if let Ok(Async::NotReady) = result {
+                    return Ok(Async::NotReady);
+                }
+                self.request = None;
+                return result.map(|ready| ready.map(Some));
+            }
+
+            while self.batch.len() < self.batch_size {
+                match self.input.poll()? {
+                    Async::Ready(Some(query)) => {
+                        if self.batch.is_empty() {
+                            self.flush_deadline =
+                                Some(Delay::new(Instant::now() + self.flush_interval));
+                        }
+                        self.batch.push(query);
+                    }
+                    Async::Ready(None) | Async::NotReady => break,
+                }
+            }
+
+            if self.should_flush()? {
+                let batch = mem::take(&mut self.batch);
+                self.flush_deadline = None;
+                self.request = Some(self.client.write_many(batch));
+                continue;
+            }
+            if self.input.is_done() {
+                return Ok(Async::Ready(None));
+            }
+            return Ok(Async::NotReady);
+        }
+    }
+}
@@ -1,40 +1,595 @@
 //! Errors that might happen in the crate
+//!
+//! [`InfluxDbError`](crate::error::InfluxDbError) implements [`std::error::Error`], and therefore
+//! also `failure::Fail`.
+//!
+//! # Migrating from `AuthenticationError` and `AuthorizationError`
+//!
+//! Earlier versions returned `AuthorizationError` for `HTTP 401 Unauthorized` and
+//! `AuthenticationError` for `HTTP 403 Forbidden`, the opposite of what the names suggest. Both
+//! variants are deprecated and no longer returned. Instead,
+//!
+//!  * [`AuthenticationFailure`](crate::error::InfluxDbError::AuthenticationFailure) is returned
+//!    for `HTTP 401 Unauthorized`, so replace matches on `AuthorizationError` with it
+//!  * [`AuthorizationFailure`](crate::error::InfluxDbError::AuthorizationFailure) is returned
+//!    for `HTTP 403 Forbidden`, so replace matches on `AuthenticationError` with it
+//!
+//! [`is_authentication_failure`](crate::error::InfluxDbError::is_authentication_failure) is
+//! `true` for both, so code only checking for rejected credentials does not need to change.
 use reqwest;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(Debug, Fail)]
+/// Errors implement `Clone` and `PartialEq`, so they can be stored and compared, e.g. to check
+/// whether a retried request failed the same way.
+#[derive(Clone, Debug)]
 pub enum InfluxDbError {
-    #[fail(display = "query is invalid: {}", error)]
     /// Error happens when a query is invalid
     InvalidQueryError { error: String },
 
-    #[fail(display = "Failed to build URL: {}", error)]
     /// Error happens when a query is invalid
     UrlConstructionError { error: String },
 
-    #[fail(display = "http protocol error: {}", error)]
     /// Error happens when a query is invalid
     ProtocolError { error: String },
 
-    #[fail(display = "http protocol error: {}", error)]
     /// Error happens when Serde cannot deserialize the response
     DeserializationError { error: String },
 
-    #[fail(display = "InfluxDB encountered the following error: {}", error)]
     /// Error which has happened inside InfluxDB
     DatabaseError { error: String },
 
-    #[fail(display = "authentication error. No or incorrect credentials")]
     /// Error happens when no or incorrect credentials are used. `HTTP 401 Unauthorized`
-    AuthenticationError,
+    AuthenticationFailure,
 
-    #[fail(display = "authorization error. User not authorized")]
     /// Error happens when the supplied user is not authorized. `HTTP 403 Forbidden`
+    AuthorizationFailure,
+
+    /// Formerly returned for `HTTP 403 Forbidden`, which is now an
+    /// [`AuthorizationFailure`](crate::error::InfluxDbError::AuthorizationFailure)
+    #[deprecated(note = "HTTP 403 Forbidden is returned as `AuthorizationFailure` instead")]
+    AuthenticationError,
+
+    /// Formerly returned for `HTTP 401 Unauthorized`, which is now an
+    /// [`AuthenticationFailure`](crate::error::InfluxDbError::AuthenticationFailure)
+    #[deprecated(note = "HTTP 401 Unauthorized is returned as `AuthenticationFailure` instead")]
     AuthorizationError,
 
-    #[fail(display = "connection error: {}", error)]
+    /// Error happens when InfluxDB responds with an unsuccessful status code other than
+    /// `HTTP 401 Unauthorized` and `HTTP 403 Forbidden`
+    HttpError { status: u16, body: String },
+
+    /// Error happens when InfluxDB rejects a request due to a rate limit. `HTTP 429 Too Many Requests`
+    ///
+    /// `retry_after` is the time to wait before sending another request, if InfluxDB sent a
+    /// `Retry-After` header.
+    RateLimitError { retry_after: Option<Duration> },
+
+    /// Error happens when a request does not complete within the configured timeout
+    TimeoutError { elapsed: Duration },
+
     /// Error happens when reqwest fails
-    ConnectionError {
-        #[fail(cause)]
-        error: reqwest::Error,
+    ///
+    /// The `reqwest::Error` is shared between clones of the error, as it cannot be cloned itself.
+    ConnectionError { error: Arc<reqwest::Error> },
+
+    /// Error happens when one query of a batch fails, with the position of the query in the batch
+    BatchError {
+        index: usize,
+        inner: Box<InfluxDbError>,
+    },
+
+    /// Error happens when InfluxDB rejects a line of a write, with the position of the line in the
+    /// request and the response of InfluxDB. See
+    /// [`InfluxDbClient::transaction_write`](crate::client::InfluxDbClient::transaction_write).
+    PartialWriteError { failed_line: usize, error: String },
+
+    /// Error with a description of the operation which failed, added with
+    /// [`with_context`](crate::error::InfluxDbError::with_context)
+    ContextError {
+        context: String,
+        inner: Box<InfluxDbError>,
     },
 }
+
+#[allow(deprecated)]
+impl fmt::Display for InfluxDbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use InfluxDbError::*;
+        match self {
+            InvalidQueryError { error } => write!(f, "query is invalid: {}", error),
+            UrlConstructionError { error } => write!(f, "Failed to build URL: {}", error),
+            ProtocolError { error } => write!(f, "http protocol error: {}", error),
+            DeserializationError { error } => write!(f, "http protocol error: {}", error),
+            DatabaseError { error } => {
+                write!(f, "InfluxDB encountered the following error: {}", error)
+            }
+            AuthenticationFailure | AuthorizationError => {
+                write!(f, "authentication error. No or incorrect credentials")
+            }
+            AuthorizationFailure | AuthenticationError => {
+                write!(f, "authorization error. User not authorized")
+            }
+            HttpError { status, body } => write!(f, "HTTP error {}: {}", status, body),
+            RateLimitError {
+                retry_after: Some(retry_after),
+            } => write!(f, "rate limit exceeded, retry after {:?}", retry_after),
+            RateLimitError { retry_after: None } => write!(f, "rate limit exceeded"),
+            TimeoutError { elapsed } => write!(f, "request timed out after {:?}", elapsed),
+            ConnectionError { error } => write!(f, "connection error: {}", error),
+            BatchError { index, inner } => write!(f, "query {} failed: {}", index, inner),
+            PartialWriteError { failed_line, error } => {
+                write!(f, "line {} of write failed: {}", failed_line, error)
+            }
+            ContextError { context, inner } => write!(f, "{}: {}", context, inner),
+        }
+    }
+}
+
+impl Error for InfluxDbError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InfluxDbError::ConnectionError { error } => Some(error.as_ref()),
+            InfluxDbError::BatchError { inner, .. } | InfluxDbError::ContextError { inner, .. } => {
+                Some(inner.as_ref())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Errors are equal if they are of the same variant with equal values. As `reqwest::Error` does
+/// not implement `PartialEq`, [`ConnectionError`](crate::error::InfluxDbError::ConnectionError)s
+/// are equal if their messages are.
+#[allow(deprecated)]
+impl PartialEq for InfluxDbError {
+    fn eq(&self, other: &Self) -> bool {
+        use InfluxDbError::*;
+        match (self, other) {
+            (InvalidQueryError { error: a }, InvalidQueryError { error: b })
+            | (UrlConstructionError { error: a }, UrlConstructionError { error: b })
+            | (ProtocolError { error: a }, ProtocolError { error: b })
+            | (DeserializationError { error: a }, DeserializationError { error: b })
+            | (DatabaseError { error: a }, DatabaseError { error: b }) => a == b,
+            (AuthenticationFailure, AuthenticationFailure)
+            | (AuthorizationFailure, AuthorizationFailure)
+            | (AuthenticationError, AuthenticationError)
+            | (AuthorizationError, AuthorizationError) => true,
+            (
+                HttpError {
+                    status: status_a,
+                    body: body_a,
+                },
+                HttpError {
+                    status: status_b,
+                    body: body_b,
+                },
+            ) => status_a == status_b && body_a == body_b,
+            (RateLimitError { retry_after: a }, RateLimitError { retry_after: b }) => a == b,
+            (TimeoutError { elapsed: a }, TimeoutError { elapsed: b }) => a == b,
+            (ConnectionError { error: a }, ConnectionError { error: b }) => {
+                Arc::ptr_eq(a, b) || a.to_string() == b.to_string()
+            }
+            (
+                BatchError {
+                    index: index_a,
+                    inner: inner_a,
+                },
+                BatchError {
+                    index: index_b,
+                    inner: inner_b,
+                },
+            ) => index_a == index_b && inner_a == inner_b,
+            (
+                PartialWriteError {
+                    failed_line: line_a,
+                    error: error_a,
+                },
+                PartialWriteError {
+                    failed_line: line_b,
+                    error: error_b,
+                },
+            ) => line_a == line_b && error_a == error_b,
+            (
+                ContextError {
+                    context: context_a,
+                    inner: inner_a,
+                },
+                ContextError {
+                    context: context_b,
+                    inner: inner_b,
+                },
+            ) => context_a == context_b && inner_a == inner_b,
+            _ => false,
+        }
+    }
+}
+
+impl InfluxDbError {
+    /// Returns the underlying `reqwest::Error` of a
+    /// [`ConnectionError`](crate::error::InfluxDbError::ConnectionError)
+    pub fn into_inner(self) -> Option<Arc<reqwest::Error>> {
+        match self {
+            InfluxDbError::ConnectionError { error } => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Wraps the error in a [`ContextError`](crate::error::InfluxDbError::ContextError), whose
+    /// message starts with `context`, e.g. to tell which of several operations failed
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::error::InfluxDbError;
+    ///
+    /// let error = InfluxDbError::AuthenticationFailure.with_context("writing weather data");
+    ///
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "writing weather data: authentication error. No or incorrect credentials"
+    /// );
+    /// assert!(error.is_authentication_failure());
+    /// ```
+    pub fn with_context<S>(self, context: S) -> InfluxDbError
+    where
+        S: Into<String>,
+    {
+        InfluxDbError::ContextError {
+            context: context.into(),
+            inner: Box::new(self),
+        }
+    }
+
+    /// Adds the context returned by `context` to the error of `result`, like
+    /// [`with_context`](crate::error::InfluxDbError::with_context)
+    ///
+    /// `context` is only called if `result` is an error.
+    pub fn context_for<T, E, F>(result: Result<T, E>, context: F) -> Result<T, InfluxDbError>
+    where
+        E: Into<InfluxDbError>,
+        F: FnOnce() -> String,
+    {
+        result.map_err(|err| err.into().with_context(context()))
+    }
+
+    /// Returns a function adding the context returned by `context` to an error, for use with
+    /// `map_err`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures::Future;
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::error::InfluxDbError;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client
+    ///     .query(&InfluxDbQuery::write_query(Timestamp::NOW, "weather").add_field("temperature", 82))
+    ///     .map_err(InfluxDbError::map_err_context(|| "writing weather data".to_string()));
+    /// ```
+    pub fn map_err_context<F>(context: F) -> impl FnOnce(InfluxDbError) -> InfluxDbError
+    where
+        F: FnOnce() -> String,
+    {
+        move |err| err.with_context(context())
+    }
+
+    /// Returns `true` if the error is likely transient, so sending the same request again may succeed
+    ///
+    /// Retryable are
+    ///
+    ///  * [`ConnectionError`](crate::error::InfluxDbError::ConnectionError) and
+    ///    [`ProtocolError`](crate::error::InfluxDbError::ProtocolError), as the network or server
+    ///    may recover
+    ///  * [`TimeoutError`](crate::error::InfluxDbError::TimeoutError), as the server may be under
+    ///    temporary load
+    ///  * [`HttpError`](crate::error::InfluxDbError::HttpError) with a `5xx` status code, as the
+    ///    server failed to handle an otherwise valid request
+    ///  * [`RateLimitError`](crate::error::InfluxDbError::RateLimitError), as the rate limit
+    ///    will be lifted after some time
+    ///
+    /// All other errors are caused by the request itself, such as invalid queries or credentials,
+    /// and will fail again. A [`BatchError`](crate::error::InfluxDbError::BatchError) or
+    /// [`ContextError`](crate::error::InfluxDbError::ContextError) is retryable if the error it
+    /// wraps is.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            InfluxDbError::BatchError { inner, .. } | InfluxDbError::ContextError { inner, .. } => {
+                inner.is_retryable()
+            }
+            InfluxDbError::ConnectionError { .. }
+            | InfluxDbError::ProtocolError { .. }
+            | InfluxDbError::TimeoutError { .. }
+            | InfluxDbError::RateLimitError { .. } => true,
+            InfluxDbError::HttpError { .. } => self.is_server_error(),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` for [`AuthenticationFailure`](crate::error::InfluxDbError::AuthenticationFailure)
+    /// and [`AuthorizationFailure`](crate::error::InfluxDbError::AuthorizationFailure)
+    #[allow(deprecated)]
+    pub fn is_authentication_failure(&self) -> bool {
+        match self {
+            InfluxDbError::BatchError { inner, .. } | InfluxDbError::ContextError { inner, .. } => {
+                inner.is_authentication_failure()
+            }
+            _ => matches!(
+                self,
+                InfluxDbError::AuthenticationFailure
+                    | InfluxDbError::AuthorizationFailure
+                    | InfluxDbError::AuthenticationError
+                    | InfluxDbError::AuthorizationError
+            ),
+        }
+    }
+
+    /// Returns `true` if InfluxDB rejected the request with a `4xx` status code, including
+    /// [`AuthenticationFailure`](crate::error::InfluxDbError::AuthenticationFailure),
+    /// [`AuthorizationFailure`](crate::error::InfluxDbError::AuthorizationFailure),
+    /// [`RateLimitError`](crate::error::InfluxDbError::RateLimitError) and
+    /// [`PartialWriteError`](crate::error::InfluxDbError::PartialWriteError)
+    pub fn is_client_error(&self) -> bool {
+        match self {
+            InfluxDbError::HttpError { status, .. } => (400..500).contains(status),
+            InfluxDbError::PartialWriteError { .. } => true,
+            InfluxDbError::BatchError { inner, .. } | InfluxDbError::ContextError { inner, .. } => {
+                inner.is_client_error()
+            }
+            InfluxDbError::RateLimitError { .. } => true,
+            _ => self.is_authentication_failure(),
+        }
+    }
+
+    /// Returns `true` if InfluxDB failed to handle the request with a `5xx` status code
+    pub fn is_server_error(&self) -> bool {
+        match self {
+            InfluxDbError::HttpError { status, .. } => (500..600).contains(status),
+            InfluxDbError::BatchError { inner, .. } | InfluxDbError::ContextError { inner, .. } => {
+                inner.is_server_error()
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::InfluxDbError;
+    use futures::Future;
+    use std::error::Error;
+    use tokio::runtime::current_thread::Runtime;
+
+    /// Fails to connect, as nothing listens on the discard port
+    fn connection_error() -> InfluxDbError {
+        let request = reqwest::r#async::Client::new()
+            .get("http://127.0.0.1:9")
+            .send();
+        let error = Runtime::new().unwrap().block_on(request).unwrap_err();
+        InfluxDbError::ConnectionError {
+            error: std::sync::Arc::new(error),
+        }
+    }
+
+    fn http_error(status: u16) -> InfluxDbError {
+        InfluxDbError::HttpError {
+            status,
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_is_client_error() {
+        assert!(http_error(400).is_client_error());
+        assert!(http_error(404).is_client_error());
+        assert!(InfluxDbError::PartialWriteError {
+            failed_line: 1,
+            error: String::new(),
+        }
+        .is_client_error());
+        assert!(InfluxDbError::AuthenticationFailure.is_client_error());
+        assert!(!http_error(500).is_client_error());
+        assert!(!InfluxDbError::InvalidQueryError {
+            error: String::new()
+        }
+        .is_client_error());
+    }
+
+    #[test]
+    fn test_is_server_error() {
+        assert!(http_error(500).is_server_error());
+        assert!(http_error(503).is_server_error());
+        assert!(!http_error(404).is_server_error());
+        assert!(!InfluxDbError::AuthorizationFailure.is_server_error());
+    }
+
+    #[test]
+    fn test_source() {
+        let error = connection_error();
+        let source = error
+            .source()
+            .expect("ConnectionError should have a source");
+        assert_eq!(
+            format!("{}", error),
+            format!("connection error: {}", source)
+        );
+
+        assert!(InfluxDbError::AuthenticationFailure.source().is_none());
+        assert!(http_error(404).source().is_none());
+    }
+
+    #[test]
+    fn test_into_inner() {
+        assert!(connection_error().into_inner().is_some());
+        assert!(InfluxDbError::AuthorizationFailure.into_inner().is_none());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            format!("{}", http_error(404)),
+            "HTTP error 404: ".to_string()
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                InfluxDbError::InvalidQueryError {
+                    error: "fields cannot be empty".to_string()
+                }
+            ),
+            "query is invalid: fields cannot be empty"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                InfluxDbError::RateLimitError {
+                    retry_after: Some(std::time::Duration::from_secs(30))
+                }
+            ),
+            "rate limit exceeded, retry after 30s"
+        );
+    }
+
+    #[test]
+    fn test_batch_error() {
+        let error = InfluxDbError::BatchError {
+            index: 2,
+            inner: Box::new(http_error(503)),
+        };
+        assert_eq!(format!("{}", error), "query 2 failed: HTTP error 503: ");
+        assert_eq!(
+            format!("{}", error.source().unwrap()),
+            "HTTP error 503: ".to_string()
+        );
+        assert!(error.is_retryable());
+        assert!(error.is_server_error());
+        assert!(!error.is_client_error());
+        assert!(!error.is_authentication_failure());
+    }
+
+    #[test]
+    fn test_clone() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Attempt {
+            number: u32,
+            error: InfluxDbError,
+        }
+
+        let attempt = Attempt {
+            number: 1,
+            error: connection_error(),
+        };
+        let retry = Attempt {
+            number: 1,
+            error: connection_error(),
+        };
+        assert_eq!(attempt.clone(), attempt);
+        assert_eq!(attempt, retry);
+
+        let error = InfluxDbError::BatchError {
+            index: 0,
+            inner: Box::new(http_error(503)),
+        };
+        assert_eq!(error.clone(), error);
+        assert_ne!(http_error(503), http_error(502));
+        assert_ne!(connection_error(), InfluxDbError::AuthenticationFailure);
+    }
+
+    #[test]
+    fn test_implements_fail() {
+        fn assert_fail<F: failure::Fail>(_: &F) {}
+        assert_fail(&InfluxDbError::AuthenticationFailure);
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(connection_error().is_retryable());
+        assert!(InfluxDbError::ProtocolError {
+            error: String::new()
+        }
+        .is_retryable());
+        assert!(InfluxDbError::TimeoutError {
+            elapsed: std::time::Duration::from_secs(1)
+        }
+        .is_retryable());
+        assert!(http_error(503).is_retryable());
+        assert!(InfluxDbError::RateLimitError { retry_after: None }.is_retryable());
+
+        assert!(!http_error(404).is_retryable());
+        assert!(!InfluxDbError::AuthenticationFailure.is_retryable());
+        assert!(!InfluxDbError::AuthorizationFailure.is_retryable());
+        assert!(!InfluxDbError::InvalidQueryError {
+            error: String::new()
+        }
+        .is_retryable());
+        assert!(!InfluxDbError::DatabaseError {
+            error: String::new()
+        }
+        .is_retryable());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_is_authentication_failure() {
+        assert!(InfluxDbError::AuthenticationFailure.is_authentication_failure());
+        assert!(InfluxDbError::AuthorizationFailure.is_authentication_failure());
+        assert!(InfluxDbError::AuthenticationError.is_authentication_failure());
+        assert!(InfluxDbError::AuthorizationError.is_client_error());
+        assert!(!http_error(401).is_authentication_failure());
+        assert!(!connection_error().is_authentication_failure());
+    }
+
+    #[test]
+    fn test_with_context() {
+        let (url, _) = crate::client::tests::mock_server(
+            std::time::Duration::from_millis(0),
+            "HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+        );
+        let client = crate::client::InfluxDbClient::new(url, "database");
+        let query =
+            crate::query::InfluxDbQuery::write_query(crate::query::Timestamp::NOW, "weather")
+                .add_field("temperature", 82);
+        let error = Runtime::new()
+            .unwrap()
+            .block_on(
+                client
+                    .query(&query)
+                    .map_err(|e| e.with_context("writing weather data")),
+            )
+            .unwrap_err();
+        assert!(
+            error.to_string().starts_with("writing weather data: "),
+            "{}",
+            error
+        );
+        assert_eq!(
+            error.source().map(ToString::to_string),
+            Some(http_error(503).to_string())
+        );
+        assert!(error.is_retryable());
+        assert!(error.is_server_error());
+
+        let result: Result<(), InfluxDbError> = Err(http_error(404));
+        let error =
+            InfluxDbError::context_for(result, || "reading weather data".to_string()).unwrap_err();
+        assert_eq!(
+            error,
+            InfluxDbError::ContextError {
+                context: "reading weather data".to_string(),
+                inner: Box::new(http_error(404)),
+            }
+        );
+        assert!(error.is_client_error());
+        assert_eq!(
+            InfluxDbError::context_for(Ok::<_, InfluxDbError>(1), || unreachable!()),
+            Ok(1)
+        );
+
+        let error = InfluxDbError::map_err_context(|| "deleting".to_string())(http_error(500));
+        assert_eq!(error.to_string(), "deleting: HTTP error 500: ");
+    }
+}
@@ -0,0 +1,125 @@
+//! Chrono Integration for InfluxDB. Provides conversions between [`Timestamp`](crate::query::Timestamp)
+//! and `chrono` date types. Enabled with the `chrono` feature.
+//!
+//! ```rust
+//! use chrono::{DateTime, Utc};
+//! use influxdb::query::InfluxDbQuery;
+//!
+//! let time: DateTime<Utc> = "2019-08-16T12:00:00Z".parse().unwrap();
+//! let _write_query = InfluxDbQuery::write_query(time.into(), "weather")
+//!     .add_field("temperature", 82);
+//! ```
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use std::convert::TryFrom;
+
+use crate::query::Timestamp;
+
+impl From<DateTime<Utc>> for Timestamp {
+    /// Converts to [`Timestamp::NANOSECONDS`](crate::query::Timestamp::NANOSECONDS). Times outside
+    /// of the range of `i64` nanoseconds, the years 1677 to 2262, saturate.
+    fn from(date_time: DateTime<Utc>) -> Self {
+        Timestamp::NANOSECONDS(
+            date_time
+                .timestamp()
+                .saturating_mul(1_000_000_000)
+                .saturating_add(i64::from(date_time.timestamp_subsec_nanos())),
+        )
+    }
+}
+
+impl From<NaiveDateTime> for Timestamp {
+    /// Converts assuming the time is in UTC
+    fn from(date_time: NaiveDateTime) -> Self {
+        Utc.from_utc_datetime(&date_time).into()
+    }
+}
+
+impl From<Timestamp> for DateTime<Utc> {
+    /// Converts to a UTC time. [`Timestamp::NOW`](crate::query::Timestamp::NOW) is converted to the current time.
+    ///
+    /// Times outside of the range of `DateTime`, about 262,000 years around the epoch, saturate.
+    fn from(timestamp: Timestamp) -> Self {
+        let nanos = match timestamp.nanoseconds() {
+            Some(nanos) => nanos,
+            None => return Utc::now(),
+        };
+        i64::try_from(nanos.div_euclid(1_000_000_000))
+            .ok()
+            .and_then(|secs| {
+                Utc.timestamp_opt(secs, nanos.rem_euclid(1_000_000_000) as u32)
+                    .single()
+            })
+            .unwrap_or(if nanos < 0 {
+                DateTime::<Utc>::MIN_UTC
+            } else {
+                DateTime::<Utc>::MAX_UTC
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::Timestamp;
+    use chrono::{DateTime, NaiveDateTime, Utc};
+
+    fn utc(date_time: &str) -> DateTime<Utc> {
+        date_time.parse().unwrap()
+    }
+
+    #[test]
+    fn test_from_date_time() {
+        let timestamp: Timestamp = utc("2019-08-16T12:00:00.123456789Z").into();
+        assert!(timestamp == Timestamp::NANOSECONDS(1_565_956_800_123_456_789));
+    }
+
+    #[test]
+    fn test_from_naive_date_time() {
+        let date_time: NaiveDateTime = "2019-08-16T12:00:00.123456789".parse().unwrap();
+        let timestamp: Timestamp = date_time.into();
+        assert!(timestamp == Timestamp::NANOSECONDS(1_565_956_800_123_456_789));
+    }
+
+    #[test]
+    fn test_from_date_time_before_epoch() {
+        let timestamp: Timestamp = utc("1969-12-31T23:59:59.5Z").into();
+        assert!(timestamp == Timestamp::NANOSECONDS(-500_000_000));
+
+        let date_time: DateTime<Utc> = Timestamp::SECONDS(-86_400).into();
+        assert_eq!(date_time, utc("1969-12-31T00:00:00Z"));
+        assert_eq!(
+            Timestamp::from(date_time),
+            Timestamp::NANOSECONDS(-86_400_000_000_000)
+        );
+    }
+
+    #[test]
+    fn test_into_date_time() {
+        let date_time: DateTime<Utc> = Timestamp::HOURS(11).into();
+        assert_eq!(date_time, utc("1970-01-01T11:00:00Z"));
+
+        let date_time: DateTime<Utc> = Timestamp::MILLISECONDS(1_565_956_800_123).into();
+        assert_eq!(date_time, utc("2019-08-16T12:00:00.123Z"));
+    }
+
+    #[test]
+    fn test_into_date_time_out_of_range() {
+        let date_time: DateTime<Utc> = Timestamp::HOURS(i64::MAX).into();
+        assert_eq!(date_time, DateTime::<Utc>::MAX_UTC);
+        let date_time: DateTime<Utc> = Timestamp::SECONDS(i64::MIN).into();
+        assert_eq!(date_time, DateTime::<Utc>::MIN_UTC);
+        let date_time: DateTime<Utc> = Timestamp::NANOSECONDS(i64::MAX).into();
+        assert_eq!(date_time, utc("2262-04-11T23:47:16.854775807Z"));
+    }
+
+    #[test]
+    fn test_round_trip_preserves_nanoseconds() {
+        let date_time = utc("2019-08-16T12:00:00.123456789Z");
+        let timestamp: Timestamp = date_time.into();
+        let round_trip: DateTime<Utc> = timestamp.into();
+        assert_eq!(round_trip, date_time);
+
+        let date_time: DateTime<Utc> = Timestamp::NANOSECONDS(1_565_956_800_123_456_789).into();
+        assert!(Timestamp::from(date_time) == Timestamp::NANOSECONDS(1_565_956_800_123_456_789));
+    }
+}
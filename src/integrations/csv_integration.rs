@@ -0,0 +1,134 @@
+//! CSV Integration for InfluxDB. Provides deserialization of CSV query responses with the `csv`
+//! crate. Enabled with the `csv` feature.
+//!
+//! ```rust,no_run
+//! use influxdb::client::InfluxDbClient;
+//! use influxdb::query::{InfluxDbQuery, Precision};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Weather {
+//!     time: i64,
+//!     temperature: i32,
+//! }
+//!
+//! let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+//! let client =
+//!     InfluxDbClient::new("http://localhost:8086", "test").with_precision(Precision::Seconds);
+//! let query = InfluxDbQuery::raw_read_query("SELECT temperature FROM weather");
+//! let _weather: Vec<Weather> = rt
+//!     .block_on(client.query_into_csv_records(&query))
+//!     .unwrap();
+//! ```
+
+use futures::Future;
+use serde::de::DeserializeOwned;
+
+use crate::client::InfluxDbClient;
+use crate::error::InfluxDbError;
+use crate::query::read_query::InfluxDbReadQuery;
+
+impl InfluxDbClient {
+    /// Sends a read query like [`query_into_csv`](crate::client::InfluxDbClient::query_into_csv)
+    /// and deserializes each row of the response into `T` by column name
+    ///
+    /// Besides the columns of the query, rows have a `name` column containing the measurement and
+    /// a `tags` column containing the tags of `GROUP BY <tag_key>` queries, e.g.
+    /// `location=us-midwest`. Columns without a field in `T` are ignored. Only the response to a
+    /// single statement can be deserialized.
+    ///
+    /// # Errors
+    ///
+    /// If a row can not be deserialized into `T`, a [`InfluxDbError::DeserializationError`] will
+    /// be returned.
+    ///
+    /// [`InfluxDbError::DeserializationError`]: crate::error::InfluxDbError::DeserializationError
+    pub fn query_into_csv_records<T>(
+        &self,
+        q: &InfluxDbReadQuery,
+    ) -> impl Future<Item = Vec<T>, Error = InfluxDbError>
+    where
+        T: DeserializeOwned,
+    {
+        self.query_into_csv(q).and_then(|csv| parse_records(&csv))
+    }
+}
+
+fn parse_records<T>(csv: &str) -> Result<Vec<T>, InfluxDbError>
+where
+    T: DeserializeOwned,
+{
+    csv::Reader::from_reader(csv.as_bytes())
+        .deserialize()
+        .collect::<Result<Vec<T>, csv::Error>>()
+        .map_err(|err| InfluxDbError::DeserializationError {
+            error: format!("could not deserialize: {}", err),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_records;
+    use crate::client::tests::mock_server;
+    use crate::client::InfluxDbClient;
+    use crate::query::InfluxDbQuery;
+    use serde::Deserialize;
+    use std::time::Duration;
+    use tokio::runtime::current_thread::Runtime;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Weather {
+        tags: String,
+        time: i64,
+        temperature: i32,
+    }
+
+    #[test]
+    fn test_query_into_csv_records() {
+        let csv = "name,tags,time,temperature\nweather,location=us-midwest,1,82\nweather,location=us-east,2,83\n";
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            csv.len(),
+            csv
+        );
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            Box::leak(response.into_boxed_str()),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let query =
+            InfluxDbQuery::raw_read_query("SELECT temperature FROM weather GROUP BY location");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.query_into_csv_records::<Weather>(&query));
+        assert_eq!(
+            result.unwrap(),
+            vec![
+                Weather {
+                    tags: "location=us-midwest".to_string(),
+                    time: 1,
+                    temperature: 82
+                },
+                Weather {
+                    tags: "location=us-east".to_string(),
+                    time: 2,
+                    temperature: 83
+                },
+            ]
+        );
+        assert!(requests
+            .recv()
+            .unwrap()
+            .to_lowercase()
+            .contains("\r\naccept: application/csv\r\n"));
+    }
+
+    #[test]
+    fn test_parse_records_invalid() {
+        assert!(parse_records::<Weather>("name,tags,time,temperature\nweather,,1,warm\n").is_err());
+        assert_eq!(
+            parse_records::<Weather>("name,tags,time,temperature\n").unwrap(),
+            Vec::new()
+        );
+    }
+}
@@ -29,7 +29,7 @@
 //!     "SELECT temperature FROM /weather_[a-z]*$/ WHERE time > now() - 1m ORDER BY DESC",
 //! );
 //! let _result = rt
-//!     .block_on(client.json_query(query))
+//!     .block_on(client.json_query(&query))
 //!     .map(|mut db_result| db_result.deserialize_next::<WeatherWithoutCityName>())
 //!     .map(|it| {
 //!         it.map(|series_vec| {
@@ -49,25 +49,32 @@
 //!     });
 //! ```
 
-use crate::client::InfluxDbClient;
+use crate::client::{is_data_query, with_params, with_timeout, InfluxDbClient};
 
 use serde::de::DeserializeOwned;
 
-use futures::{Future, Stream};
-use reqwest::r#async::{Client, Decoder};
-use reqwest::{StatusCode, Url};
-use std::mem;
+use futures::{stream, Future, Stream};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::{Method, Url};
 
-use serde::Deserialize;
-use serde_json;
+use serde::{Deserialize, Serialize};
+use serde_json::{self, Number, Value};
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::Duration;
 
 use crate::error::InfluxDbError;
 
-use crate::query::read_query::InfluxDbReadQuery;
-use crate::query::InfluxDbQuery;
+use crate::query::read_query::{InfluxDbReadQuery, InfluxDbValue};
+use crate::query::write_query::{FieldValue, InfluxDbWriteQuery};
+use crate::query::{quote_identifier, InfluxDbQuery, Timestamp};
 
 use futures::future::Either;
 
+#[cfg(feature = "derive")]
+pub use influxdb_derive::InfluxDbQueryable;
+
 #[derive(Deserialize)]
 #[doc(hidden)]
 struct _DatabaseError {
@@ -78,27 +85,210 @@ struct _DatabaseError {
 #[doc(hidden)]
 pub struct DatabaseQueryResult {
     pub results: Vec<serde_json::Value>,
+    /// Messages of the response and of its statements
+    #[serde(default)]
+    pub messages: Vec<InfluxDbMessage>,
+    /// Performance metrics of the query, if InfluxDB returned them
+    #[serde(default)]
+    pub statistics: Option<QueryStats>,
+}
+
+/// Performance metrics InfluxDB returns with a query result, all durations in nanoseconds
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct QueryStats {
+    #[serde(rename = "executionTime", default)]
+    pub execution_time_ns: u64,
+    #[serde(rename = "plannerTime", default)]
+    pub planner_time_ns: u64,
+    /// Time the query waited for a free slot, see `max-concurrent-queries`
+    #[serde(rename = "queueDuration", default)]
+    pub queue_duration_ns: u64,
+    #[serde(rename = "executeDuration", default)]
+    pub execute_duration_ns: u64,
+    /// Number of queries running at the same time
+    #[serde(default)]
+    pub concurrency: u32,
+}
+
+/// Informational message or warning InfluxDB returns with a query result, e.g. about a
+/// deprecated statement
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct InfluxDbMessage {
+    /// Severity of the message, e.g. `info` or `warning`
+    pub level: String,
+    pub text: String,
 }
 
 impl DatabaseQueryResult {
+    /// Returns the messages InfluxDB sent with the response, including those of each statement
+    pub fn messages(&self) -> &[InfluxDbMessage] {
+        &self.messages
+    }
+
+    /// Returns the performance metrics InfluxDB returned with the first statement, if any
+    pub fn statistics(&self) -> Option<&QueryStats> {
+        self.statistics.as_ref()
+    }
+
+    /// Returns `true` if any message has the level `warning`
+    pub fn has_warnings(&self) -> bool {
+        self.messages
+            .iter()
+            .any(|message| message.level.eq_ignore_ascii_case("warning"))
+    }
+
+    /// Deserializes the result of the next statement
+    ///
+    /// A statement which matched no data yields an empty `series`.
+    ///
+    /// # Errors
+    ///
+    /// If all results have already been deserialized or the result can not be deserialized
+    /// into `T`, a [`InfluxDbError::DeserializationError`] will be returned. If InfluxDB reported
+    /// an error for the statement, a [`InfluxDbError::DatabaseError`] will be returned.
+    ///
+    /// [`InfluxDbError::DeserializationError`]: crate::error::InfluxDbError::DeserializationError
+    /// [`InfluxDbError::DatabaseError`]: crate::error::InfluxDbError::DatabaseError
     pub fn deserialize_next<T: 'static>(
         &mut self,
     ) -> impl Future<Item = InfluxDbReturn<T>, Error = InfluxDbError>
     where
         T: DeserializeOwned,
     {
-        match serde_json::from_value::<InfluxDbReturn<T>>(self.results.remove(0)) {
-            Ok(item) => futures::future::result(Ok(item)),
-            Err(err) => futures::future::err(InfluxDbError::DeserializationError {
-                error: format!("could not deserialize: {}", err),
+        if self.results.is_empty() {
+            return futures::future::err(InfluxDbError::DeserializationError {
+                error: "no results left to deserialize".to_string(),
+            });
+        }
+        futures::future::result(deserialize_result(self.results.remove(0)))
+    }
+
+    /// Deserializes the result of the statement with the `statement_id` `id`, regardless of its
+    /// position in the response
+    ///
+    /// Statements are numbered from 0 in the order they were sent. The result is removed, so the
+    /// remaining results can still be deserialized with [`deserialize_next`](crate::integrations::serde_integration::DatabaseQueryResult::deserialize_next).
+    ///
+    /// # Errors
+    ///
+    /// If no remaining result has the statement ID `id` or the result can not be deserialized
+    /// into `T`, a [`InfluxDbError::DeserializationError`] will be returned. If InfluxDB reported
+    /// an error for the statement, a [`InfluxDbError::DatabaseError`] will be returned.
+    ///
+    /// [`InfluxDbError::DeserializationError`]: crate::error::InfluxDbError::DeserializationError
+    /// [`InfluxDbError::DatabaseError`]: crate::error::InfluxDbError::DatabaseError
+    pub fn deserialize_for_statement<T>(
+        &mut self,
+        id: u32,
+    ) -> impl Future<Item = InfluxDbReturn<T>, Error = InfluxDbError>
+    where
+        T: DeserializeOwned + 'static,
+    {
+        let position = self
+            .results
+            .iter()
+            .position(|result| statement_id(result) == Some(id));
+        match position {
+            Some(position) => {
+                futures::future::result(deserialize_result(self.results.remove(position)))
+            }
+            None => futures::future::err(InfluxDbError::DeserializationError {
+                error: format!("no result for statement {}", id),
             }),
         }
     }
+
+    /// Returns the `statement_id` and raw JSON of each statement result which has not been
+    /// deserialized yet
+    ///
+    /// Results without a valid `statement_id` are skipped.
+    pub fn results_with_ids(&self) -> Vec<(u32, Value)> {
+        self.results
+            .iter()
+            .filter_map(|result| statement_id(result).map(|id| (id, result.clone())))
+            .collect()
+    }
+
+    /// Deserializes the result of the next statement, including the tags of each series
+    ///
+    /// Queries using `GROUP BY <tag_key>` return one series per tag value, whose tags are
+    /// available in [`InfluxDbSeries::tags`](crate::integrations::serde_integration::InfluxDbSeries::tags).
+    /// The tags are populated by [`deserialize_next`](crate::integrations::serde_integration::DatabaseQueryResult::deserialize_next)
+    /// as well, this method makes the intent explicit.
+    pub fn deserialize_next_tagged<T>(
+        &mut self,
+    ) -> impl Future<Item = InfluxDbReturn<T>, Error = InfluxDbError>
+    where
+        T: DeserializeOwned + 'static,
+    {
+        self.deserialize_next()
+    }
+
+    /// Deserializes the results of all remaining statements
+    ///
+    /// # Errors
+    ///
+    /// If any result can not be deserialized into `T`, a [`InfluxDbError::DeserializationError`]
+    /// will be returned. If InfluxDB reported an error for a statement, a
+    /// [`InfluxDbError::DatabaseError`] will be returned.
+    ///
+    /// [`InfluxDbError::DeserializationError`]: crate::error::InfluxDbError::DeserializationError
+    /// [`InfluxDbError::DatabaseError`]: crate::error::InfluxDbError::DatabaseError
+    pub fn deserialize_all<T>(
+        &mut self,
+    ) -> impl Future<Item = Vec<InfluxDbReturn<T>>, Error = InfluxDbError>
+    where
+        T: DeserializeOwned + 'static,
+    {
+        let results = self
+            .results
+            .drain(..)
+            .map(deserialize_result)
+            .collect::<Result<Vec<InfluxDbReturn<T>>, InfluxDbError>>();
+        futures::future::result(results)
+    }
+
+    /// Returns the number of statement results which have not been deserialized yet
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns `true` if all statement results have been deserialized
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+/// Deserializes the result of a statement, failing with the error InfluxDB reported for it
+fn deserialize_result<T>(result: Value) -> Result<InfluxDbReturn<T>, InfluxDbError>
+where
+    T: DeserializeOwned,
+{
+    if let Some(error) = result.get("error").and_then(Value::as_str) {
+        return Err(InfluxDbError::DatabaseError {
+            error: error.to_string(),
+        });
+    }
+    serde_json::from_value::<InfluxDbReturn<T>>(result).map_err(|err| {
+        InfluxDbError::DeserializationError {
+            error: format!("could not deserialize: {}", err),
+        }
+    })
+}
+
+/// Returns the `statement_id` of a statement result
+fn statement_id(result: &Value) -> Option<u32> {
+    result
+        .get("statement_id")
+        .and_then(Value::as_u64)
+        .and_then(|id| u32::try_from(id).ok())
 }
 
 #[derive(Deserialize, Debug)]
 #[doc(hidden)]
 pub struct InfluxDbReturn<T> {
+    /// Empty if the statement matched no data
+    #[serde(default = "Vec::new")]
     pub series: Vec<InfluxDbSeries<T>>,
 }
 
@@ -106,92 +296,3245 @@ pub struct InfluxDbReturn<T> {
 /// Represents a returned series from InfluxDB
 pub struct InfluxDbSeries<T> {
     pub name: String,
+    /// Tags the series was grouped by, empty if the query does not use `GROUP BY <tag_key>`
+    #[serde(default = "HashMap::new")]
+    pub tags: HashMap<String, String>,
+    /// Names of the columns, in the order of the values in each row
+    pub columns: Vec<String>,
+    /// Rows of the series, empty if InfluxDB omits them for a series without data
+    #[serde(default = "Vec::new")]
     pub values: Vec<T>,
 }
 
+impl<T> InfluxDbSeries<T> {
+    /// Returns the position of the column called `name`, if the series contains it
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == name)
+    }
+}
+
+impl InfluxDbSeries<Value> {
+    /// Deserializes every row into `T`, matching values to struct fields by column name
+    ///
+    /// Rows deserialized by [`deserialize_next`](crate::integrations::serde_integration::DatabaseQueryResult::deserialize_next)
+    /// are matched to struct fields by position instead. The tags of the series are available
+    /// alongside the columns of each row.
+    ///
+    /// # Errors
+    ///
+    /// If a row can not be deserialized into `T`, a [`InfluxDbError::DeserializationError`]
+    /// will be returned.
+    ///
+    /// [`InfluxDbError::DeserializationError`]: crate::error::InfluxDbError::DeserializationError
+    pub fn deserialize_rows<T>(self) -> Result<Vec<T>, InfluxDbError>
+    where
+        T: DeserializeOwned,
+    {
+        let InfluxDbSeries {
+            tags,
+            columns,
+            values,
+            ..
+        } = self;
+        values
+            .into_iter()
+            .map(|row| {
+                let mut object = tags
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+                    .collect::<serde_json::Map<String, Value>>();
+                match row {
+                    Value::Array(row) => object.extend(columns.iter().cloned().zip(row)),
+                    _ => {
+                        return Err(InfluxDbError::DeserializationError {
+                            error: "row is not an array".to_string(),
+                        })
+                    }
+                }
+                serde_json::from_value(Value::Object(object)).map_err(|err| {
+                    InfluxDbError::DeserializationError {
+                        error: format!("could not deserialize: {}", err),
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Pairs the epoch timestamp of every row with the value `field_extractor` returns for the row
+    ///
+    /// Rows are passed to `field_extractor` as arrays in the order of
+    /// [`columns`](crate::integrations::serde_integration::InfluxDbSeries::columns). The `time`
+    /// column must contain epoch timestamps, which InfluxDB returns if a precision is set with
+    /// [`InfluxDbClient::with_precision`](crate::client::InfluxDbClient::with_precision) or
+    /// [`InfluxDbReadQuery::with_epoch`](crate::query::read_query::InfluxDbReadQuery::with_epoch).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::integrations::serde_integration::InfluxDbSeries;
+    /// use serde_json::json;
+    ///
+    /// let series: InfluxDbSeries<serde_json::Value> = serde_json::from_value(json!({
+    ///     "name": "weather",
+    ///     "columns": ["time", "temperature"],
+    ///     "values": [[1, 82], [2, 84]]
+    /// }))
+    /// .unwrap();
+    /// let pairs = series.into_time_value_pairs(|row| row[1].as_i64()).unwrap();
+    ///
+    /// assert_eq!(pairs, vec![(1, Some(82)), (2, Some(84))]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the series has no `time` column, or a row is not an array or has no epoch timestamp,
+    /// a [`InfluxDbError::DeserializationError`] will be returned.
+    ///
+    /// [`InfluxDbError::DeserializationError`]: crate::error::InfluxDbError::DeserializationError
+    pub fn into_time_value_pairs<V, F>(
+        self,
+        field_extractor: F,
+    ) -> Result<Vec<(i64, V)>, InfluxDbError>
+    where
+        F: Fn(Value) -> V,
+    {
+        let time_index = self.time_index()?;
+        self.values
+            .into_iter()
+            .map(|row| {
+                let time = row_time(&row, time_index)?;
+                Ok((time, field_extractor(row)))
+            })
+            .collect()
+    }
+
+    /// Returns the earliest and latest epoch timestamp of the series, or `None` if it has no rows
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`into_time_value_pairs`](crate::integrations::serde_integration::InfluxDbSeries::into_time_value_pairs)
+    /// if a timestamp cannot be read.
+    pub fn time_range(&self) -> Result<Option<(i64, i64)>, InfluxDbError> {
+        let time_index = self.time_index()?;
+        let mut range: Option<(i64, i64)> = None;
+        for row in &self.values {
+            let time = row_time(row, time_index)?;
+            range = Some(match range {
+                Some((min, max)) => (min.min(time), max.max(time)),
+                None => (time, time),
+            });
+        }
+        Ok(range)
+    }
+
+    fn time_index(&self) -> Result<usize, InfluxDbError> {
+        self.column_index("time")
+            .ok_or_else(|| InfluxDbError::DeserializationError {
+                error: "series has no time column".to_string(),
+            })
+    }
+}
+
+/// Reads the epoch timestamp at `time_index` of `row`
+fn row_time(row: &Value, time_index: usize) -> Result<i64, InfluxDbError> {
+    match row {
+        Value::Array(values) => values
+            .get(time_index)
+            .and_then(Value::as_i64)
+            .ok_or_else(|| InfluxDbError::DeserializationError {
+                error: "time is not an epoch timestamp, set a precision to receive one".to_string(),
+            }),
+        _ => Err(InfluxDbError::DeserializationError {
+            error: "row is not an array".to_string(),
+        }),
+    }
+}
+
 impl InfluxDbClient {
     pub fn json_query(
         &self,
-        q: InfluxDbReadQuery,
+        q: &InfluxDbReadQuery,
+    ) -> impl Future<Item = DatabaseQueryResult, Error = InfluxDbError> {
+        self.json_query_with_params(q, HashMap::new())
+    }
+
+    /// Sends `q` like `json_query`, letting InfluxDB replace the placeholders `$key` of the query
+    /// with the values of `params`
+    ///
+    /// See [`query_with_params`](crate::client::InfluxDbClient::query_with_params) for how the
+    /// values are sent.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::InfluxDbQuery;
+    /// use std::collections::HashMap;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let mut params = HashMap::new();
+    /// params.insert("location".to_string(), "berlin".into());
+    /// let _future = client.json_query_with_params(
+    ///     &InfluxDbQuery::raw_read_query("SELECT * FROM weather WHERE location = $location"),
+    ///     params,
+    /// );
+    /// ```
+    pub fn json_query_with_params(
+        &self,
+        q: &InfluxDbReadQuery,
+        params: HashMap<String, InfluxDbValue>,
+    ) -> impl Future<Item = DatabaseQueryResult, Error = InfluxDbError> {
+        use futures::future;
+
+        let (read_query, url) = match self
+            .json_query_url(q)
+            .and_then(|(read_query, url)| Ok((read_query, with_params(url, &params)?)))
+        {
+            Ok(url) => url,
+            Err(error) => {
+                return Either::B(future::err::<DatabaseQueryResult, InfluxDbError>(error));
+            }
+        };
+        let chunked = self.is_chunked(&read_query);
+
+        Either::A(
+            self.send_query_request(q, Method::GET, url, HeaderMap::new(), None)
+                .and_then(move |(_, body)| {
+                    if !chunked {
+                        return parse_query_result(&body);
+                    }
+                    body.split(|byte| *byte == b'\n')
+                        .filter(|line| !line.is_empty())
+                        .map(parse_query_result)
+                        .collect::<Result<Vec<DatabaseQueryResult>, InfluxDbError>>()
+                        .map(merge_chunks)
+                }),
+        )
+    }
+
+    /// Sends all `queries` concurrently like `json_query` and returns their results in the order
+    /// of `queries`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.query_many(&[
+    ///     InfluxDbQuery::raw_read_query("SELECT * FROM weather"),
+    ///     InfluxDbQuery::raw_read_query("SELECT * FROM traffic"),
+    /// ]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If any query fails, a [`InfluxDbError::BatchError`] with the index of the query and its
+    /// error will be returned. Queries which have not completed yet are dropped.
+    ///
+    /// [`InfluxDbError::BatchError`]: crate::error::InfluxDbError::BatchError
+    pub fn query_many(
+        &self,
+        queries: &[InfluxDbReadQuery],
+    ) -> impl Future<Item = Vec<DatabaseQueryResult>, Error = InfluxDbError> {
+        futures::future::join_all(self.batch_queries(queries))
+    }
+
+    /// Sends `queries` like [`query_many`](crate::client::InfluxDbClient::query_many), but with
+    /// at most `concurrency` requests at a time
+    ///
+    /// A `concurrency` of `0` is treated as `1`.
+    pub fn query_many_with_limit(
+        &self,
+        queries: &[InfluxDbReadQuery],
+        concurrency: usize,
+    ) -> impl Future<Item = Vec<DatabaseQueryResult>, Error = InfluxDbError> {
+        // `buffered` starts at most `concurrency` queries at a time and keeps their order
+        stream::iter_ok(self.batch_queries(queries))
+            .buffered(concurrency.max(1))
+            .collect()
+    }
+
+    /// Creates a `json_query` future for each query, failing with a
+    /// [`InfluxDbError::BatchError`](crate::error::InfluxDbError::BatchError) for its index
+    fn batch_queries(
+        &self,
+        queries: &[InfluxDbReadQuery],
+    ) -> Vec<impl Future<Item = DatabaseQueryResult, Error = InfluxDbError>> {
+        queries
+            .iter()
+            .enumerate()
+            .map(|(index, query)| {
+                self.json_query(query)
+                    .map_err(move |err| InfluxDbError::BatchError {
+                        index,
+                        inner: Box::new(err),
+                    })
+            })
+            .collect()
+    }
+
+    /// Sends a query like `json_query`, but fails with a
+    /// [`InfluxDbError::TimeoutError`](crate::error::InfluxDbError::TimeoutError) if it does not
+    /// complete within `timeout`
+    pub fn json_query_with_timeout(
+        &self,
+        q: &InfluxDbReadQuery,
+        timeout: Duration,
     ) -> impl Future<Item = DatabaseQueryResult, Error = InfluxDbError> {
+        with_timeout(self.json_query(q), timeout)
+    }
+
+    /// Sends a `SELECT` query and returns a stream of the chunks InfluxDB responds with
+    ///
+    /// Each chunk contains at most as many points as set with
+    /// [`with_chunk_size`](crate::client::InfluxDbClient::with_chunk_size), or InfluxDB's default
+    /// of 10000 points. Chunks are deserialized while the response is received, so the whole
+    /// result never has to be held in memory. The request is not retried if it fails.
+    ///
+    /// Errors which occur before the response is received, like an invalid query or a failed
+    /// authentication, are yielded by the stream. Use
+    /// [`json_query_stream`](crate::client::InfluxDbClient::json_query_stream) to handle them
+    /// separately.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use futures::Stream;
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test").with_chunk_size(1000);
+    /// let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+    /// let _chunks = rt.block_on(client.query_chunked(&query).collect());
+    /// ```
+    pub fn query_chunked(
+        &self,
+        q: &InfluxDbReadQuery,
+    ) -> impl Stream<Item = DatabaseQueryResult, Error = InfluxDbError> {
+        self.json_query_stream(q).flatten_stream()
+    }
+
+    /// Sends a `SELECT` query and returns a stream of the chunks InfluxDB responds with, once
+    /// the response has been received
+    ///
+    /// Like [`query_chunked`](crate::client::InfluxDbClient::query_chunked), but the returned
+    /// future fails if the query could not be sent or InfluxDB rejects it, while the stream only
+    /// yields errors which occur while reading the chunks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use futures::{Future, Stream};
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+    /// let _chunk_count = rt.block_on(
+    ///     client
+    ///         .json_query_stream(&query)
+    ///         .and_then(|chunks| chunks.fold(0, |count, _| Ok(count + 1))),
+    /// );
+    /// ```
+    pub fn json_query_stream(
+        &self,
+        q: &InfluxDbReadQuery,
+    ) -> impl Future<
+        Item = impl Stream<Item = DatabaseQueryResult, Error = InfluxDbError>,
+        Error = InfluxDbError,
+    > {
         use futures::future;
 
-        let query = q.build().unwrap();
-        let basic_parameters: Vec<(String, String)> = self.into();
-        let client = {
-            let read_query = query.get();
-
-            let mut url = match Url::parse_with_params(
-                format!("{url}/query", url = self.database_url()).as_str(),
-                basic_parameters,
-            ) {
-                Ok(url) => url,
-                Err(err) => {
-                    let error = InfluxDbError::UrlConstructionError {
-                        error: format!("{}", err),
-                    };
-                    return Either::B(future::err::<DatabaseQueryResult, InfluxDbError>(error));
+        let (read_query, mut url) = match self.json_query_url(q) {
+            Ok(url) => url,
+            Err(error) => return Either::B(future::err(error)),
+        };
+        if !self.is_chunked(&read_query) {
+            url.query_pairs_mut().append_pair("chunked", "true");
+        }
+
+        let timeouts = self.timeouts;
+        Either::A(
+            self.send_request_streaming(Method::GET, url, HeaderMap::new(), None)
+                .map(move |res| {
+                    parse_chunks(
+                        res.into_body()
+                            .map_err(move |err| timeouts.body_error(err))
+                            .map(|chunk| chunk.to_vec()),
+                    )
+                }),
+        )
+    }
+
+    /// Builds the URL a query deserialized from JSON is sent to, alongside the built query
+    pub(crate) fn json_query_url(
+        &self,
+        q: &InfluxDbReadQuery,
+    ) -> Result<(String, Url), InfluxDbError> {
+        let read_query = q
+            .build()
+            .map_err(|err| InfluxDbError::InvalidQueryError {
+                error: format!("{}", err),
+            })?
+            .into_string();
+        if !is_data_query(&read_query) {
+            return Err(InfluxDbError::InvalidQueryError {
+                error: String::from(
+                    "Only SELECT and SHOW queries supported with JSON deserialization",
+                ),
+            });
+        }
+        let url = self.read_url(&read_query, q.get_epoch()).map_err(|err| {
+            InfluxDbError::UrlConstructionError {
+                error: format!("{}", err),
+            }
+        })?;
+        Ok((read_query, url))
+    }
+}
+
+/// Parses the response of the `/health` endpoint, failing if the health check failed
+fn parse_health_status(body: &[u8]) -> Result<HealthStatus, InfluxDbError> {
+    let health = serde_json::from_slice::<HealthStatus>(body).map_err(|err| {
+        InfluxDbError::DeserializationError {
+            error: format!("serde error: {}", err),
+        }
+    })?;
+    match health.status {
+        HealthStatusValue::Pass => Ok(health),
+        HealthStatusValue::Fail => Err(InfluxDbError::DatabaseError {
+            error: health.message,
+        }),
+    }
+}
+
+/// Parses a JSON response of InfluxDB, failing if it contains an error
+fn parse_query_result(body: &[u8]) -> Result<DatabaseQueryResult, InfluxDbError> {
+    // Try parsing InfluxDBs { "error": "error message here" }
+    if let Ok(error) = serde_json::from_slice::<_DatabaseError>(body) {
+        return Err(InfluxDbError::DatabaseError {
+            error: error.error.to_string(),
+        });
+    }
+    // Json has another structure, let's try actually parsing it to the type we're deserializing
+    let mut result = serde_json::from_slice::<DatabaseQueryResult>(body).map_err(|err| {
+        InfluxDbError::DeserializationError {
+            error: format!("serde error: {}", err),
+        }
+    })?;
+    // InfluxDB 1.x returns the messages and statistics of a statement with its result
+    let serde_error = |err: serde_json::Error| InfluxDbError::DeserializationError {
+        error: format!("serde error: {}", err),
+    };
+    for statement in &result.results {
+        if let Some(messages) = statement.get("messages") {
+            let messages = Vec::<InfluxDbMessage>::deserialize(messages).map_err(serde_error)?;
+            result.messages.extend(messages);
+        }
+    }
+    if result.statistics.is_none() {
+        if let Some(statistics) = result
+            .results
+            .first()
+            .and_then(|statement| statement.get("statistics"))
+        {
+            result.statistics = Some(QueryStats::deserialize(statistics).map_err(serde_error)?);
+        }
+    }
+    Ok(result)
+}
+
+/// Parses a chunked response, consisting of one JSON object per line
+///
+/// The lines are reassembled from `body` regardless of where its parts are split.
+fn parse_chunks<S>(body: S) -> impl Stream<Item = DatabaseQueryResult, Error = InfluxDbError>
+where
+    S: Stream<Item = Vec<u8>, Error = InfluxDbError>,
+{
+    let mut buffer = Vec::new();
+    body
+        // Terminates the last line, in case the response does not end with a newline
+        .chain(stream::once(Ok(vec![b'\n'])))
+        .map(move |bytes| {
+            buffer.extend(bytes);
+            stream::iter_ok(split_lines(&mut buffer))
+        })
+        .flatten()
+        .and_then(|line| parse_query_result(&line))
+}
+
+/// Removes all complete, non-empty lines from `buffer`, leaving an incomplete last line in it
+fn split_lines(buffer: &mut Vec<u8>) -> Vec<Vec<u8>> {
+    let end = match buffer.iter().rposition(|byte| *byte == b'\n') {
+        Some(position) => position + 1,
+        None => return Vec::new(),
+    };
+    buffer
+        .drain(..end)
+        .collect::<Vec<u8>>()
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_vec())
+        .collect()
+}
+
+/// Merges the chunks of a chunked response into a single result
+///
+/// InfluxDB splits the series of a statement across chunks, so consecutive chunks of the same
+/// statement and series are joined.
+fn merge_chunks(chunks: Vec<DatabaseQueryResult>) -> DatabaseQueryResult {
+    let mut results: Vec<Value> = Vec::new();
+    let mut messages = Vec::new();
+    let mut statistics = None;
+    let chunk_results = chunks
+        .into_iter()
+        .flat_map(|chunk| {
+            messages.extend(chunk.messages);
+            statistics = statistics.or(chunk.statistics);
+            chunk.results
+        })
+        .collect::<Vec<Value>>();
+    for mut result in chunk_results {
+        if let Some(result) = result.as_object_mut() {
+            result.remove("partial");
+        }
+        let previous = match results.last_mut() {
+            Some(previous) if previous.get("statement_id") == result.get("statement_id") => {
+                previous
+            }
+            _ => {
+                results.push(result);
+                continue;
+            }
+        };
+        let series = match result.get_mut("series").map(Value::take) {
+            Some(Value::Array(series)) => series,
+            _ => continue,
+        };
+        let previous_series = match previous.as_object_mut().map(|previous| {
+            previous
+                .entry("series")
+                .or_insert_with(|| Value::Array(Vec::new()))
+        }) {
+            Some(Value::Array(previous_series)) => previous_series,
+            _ => continue,
+        };
+        for mut series in series {
+            if let Some(series) = series.as_object_mut() {
+                series.remove("partial");
+            }
+            match previous_series.last_mut() {
+                Some(previous)
+                    if previous.get("name") == series.get("name")
+                        && previous.get("tags") == series.get("tags") =>
+                {
+                    if let (Some(Value::Array(values)), Some(Value::Array(more))) = (
+                        previous.get_mut("values"),
+                        series.get_mut("values").map(Value::take),
+                    ) {
+                        values.extend(more);
+                    }
                 }
-            };
-            url.query_pairs_mut().append_pair("q", &read_query.clone());
+                _ => previous_series.push(series),
+            }
+        }
+    }
+    DatabaseQueryResult {
+        results,
+        messages,
+        statistics,
+    }
+}
 
-            if read_query.contains("SELECT") || read_query.contains("SHOW") {
-                Client::new().get(url.as_str())
-            } else {
-                let error = InfluxDbError::InvalidQueryError {
-                    error: String::from(
-                        "Only SELECT and SHOW queries supported with JSON deserialization",
-                    ),
-                };
-                return Either::B(future::err::<DatabaseQueryResult, InfluxDbError>(error));
+/// Field key and type of a measurement, as returned by
+/// [`InfluxDbClient::show_field_keys`](crate::client::InfluxDbClient::show_field_keys)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldKeyInfo {
+    pub name: String,
+    /// Type of the field's values, e.g. `float`, `integer`, `string` or `boolean`
+    pub field_type: String,
+}
+
+/// Subscription forwarding writes to other hosts, as returned by
+/// [`InfluxDbClient::show_subscriptions`](crate::client::InfluxDbClient::show_subscriptions)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriptionInfo {
+    pub name: String,
+    pub database: String,
+    pub retention_policy: String,
+    /// `ANY` or `ALL`, see [`SubscriptionMode`](crate::client::SubscriptionMode)
+    pub mode: String,
+    /// URLs the writes are forwarded to, e.g. `udp://kapacitor:9090`
+    pub destinations: Vec<String>,
+}
+
+/// Shard group, covering the time from `start_time` to `end_time` of a retention policy, as
+/// returned by [`InfluxDbClient::show_shard_groups`](crate::client::InfluxDbClient::show_shard_groups)
+///
+/// Times are RFC3339 formatted, e.g. `2019-08-16T00:00:00Z`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardGroupInfo {
+    pub id: u64,
+    pub database: String,
+    pub retention_policy: String,
+    pub start_time: String,
+    pub end_time: String,
+    /// Time after which the shard group is dropped by the retention policy
+    pub expiry_time: String,
+}
+
+/// Shard of a shard group, as returned by
+/// [`InfluxDbClient::show_shards`](crate::client::InfluxDbClient::show_shards)
+///
+/// Times are RFC3339 formatted, e.g. `2019-08-16T00:00:00Z`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardInfo {
+    pub id: u64,
+    pub database: String,
+    pub retention_policy: String,
+    pub shard_group: u64,
+    pub start_time: String,
+    pub end_time: String,
+    /// Time after which the shard is dropped by the retention policy
+    pub expiry_time: String,
+    /// IDs of the data nodes storing the shard, empty for a single InfluxDB server
+    pub owners: Vec<u64>,
+}
+
+/// User of InfluxDB, as returned by
+/// [`InfluxDbClient::show_users`](crate::client::InfluxDbClient::show_users)
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserInfo {
+    pub username: String,
+    /// Whether the user has all privileges on all databases
+    pub admin: bool,
+}
+
+/// Continuous query, as returned by
+/// [`InfluxDbClient::show_continuous_queries`](crate::client::InfluxDbClient::show_continuous_queries)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContinuousQuery {
+    pub name: String,
+    /// Database the continuous query runs on
+    pub database: String,
+    /// The full `CREATE CONTINUOUS QUERY` statement
+    pub query: String,
+}
+
+/// Retention policy of a database, as returned by
+/// [`InfluxDbClient::show_retention_policies`](crate::client::InfluxDbClient::show_retention_policies)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetentionPolicy {
+    pub name: String,
+    /// How long data is kept, e.g. `720h0m0s`, or `0s` if it is kept forever
+    pub duration: String,
+    /// Time range covered by each shard group, e.g. `24h0m0s`
+    pub shard_group_duration: String,
+    /// Number of copies of the data stored in a cluster
+    pub replication: u32,
+    /// Whether writes without a retention policy are written to this one
+    pub is_default: bool,
+}
+
+/// Health of an InfluxDB server, as returned by
+/// [`InfluxDbClient::health`](crate::client::InfluxDbClient::health)
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct HealthStatus {
+    pub name: String,
+    /// Description of the status, e.g. `ready for queries and writes`
+    pub message: String,
+    pub status: HealthStatusValue,
+    /// Version number, e.g. `2.0.0`
+    #[serde(default)]
+    pub version: String,
+}
+
+/// Result of the health check of an InfluxDB server
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatusValue {
+    Pass,
+    Fail,
+}
+
+/// User, organization, bucket and token created by
+/// [`InfluxDbClient::setup`](crate::client::InfluxDbClient::setup)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetupResponse {
+    pub user_id: String,
+    pub org_id: String,
+    pub bucket_id: String,
+    /// Token with all permissions, to be used with [`InfluxDbClient::with_token`](crate::client::InfluxDbClient::with_token)
+    pub auth_token: String,
+}
+
+#[derive(Deserialize)]
+struct _SetupResource {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct _SetupAuthorization {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct _SetupResponse {
+    user: _SetupResource,
+    org: _SetupResource,
+    bucket: _SetupResource,
+    auth: _SetupAuthorization,
+}
+
+#[derive(Deserialize)]
+struct _SetupAllowed {
+    allowed: bool,
+}
+
+/// InfluxDB 2.x bucket, as returned by [`InfluxDbClient::list_buckets`](crate::client::InfluxDbClient::list_buckets)
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct BucketInfo {
+    pub id: String,
+    pub name: String,
+    /// ID of the organization owning the bucket
+    #[serde(rename = "orgID")]
+    pub org_id: String,
+    /// Empty if data is kept forever
+    #[serde(rename = "retentionRules", default)]
+    pub retention_rules: Vec<RetentionRule>,
+}
+
+/// Rule deciding how long data is kept in an InfluxDB 2.x bucket
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RetentionRule {
+    /// Kind of the rule, `expire` is the only one supported by InfluxDB
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Seconds after which data expires, 0 to keep it forever
+    #[serde(rename = "everySeconds")]
+    pub every_seconds: u64,
+}
+
+impl RetentionRule {
+    /// Creates a rule which deletes data older than `every_seconds`
+    pub fn expire(every_seconds: u64) -> Self {
+        RetentionRule {
+            type_: "expire".to_string(),
+            every_seconds,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct _Buckets {
+    buckets: Vec<BucketInfo>,
+}
+
+/// Maximum number of buckets InfluxDB returns in a single response
+const BUCKETS_PAGE_SIZE: usize = 100;
+
+impl InfluxDbClient {
+    /// Checks the health of the InfluxDB server using the `/health` endpoint of InfluxDB 2.x
+    ///
+    /// Servers without a `/health` endpoint, like InfluxDB 1.x, are checked with
+    /// [`ping`](crate::client::InfluxDbClient::ping) instead. In that case the status is `Pass`
+    /// if the server responds.
+    ///
+    /// # Errors
+    ///
+    /// If the server reports a failed health check, a [`InfluxDbError::DatabaseError`] with its
+    /// message will be returned.
+    ///
+    /// [`InfluxDbError::DatabaseError`]: crate::error::InfluxDbError::DatabaseError
+    pub fn health(&self) -> impl Future<Item = HealthStatus, Error = InfluxDbError> {
+        use futures::future;
+
+        let url = match Url::parse(&self.endpoint_url("/health")) {
+            Ok(url) => url,
+            Err(err) => {
+                return Either::B(future::err(InfluxDbError::UrlConstructionError {
+                    error: format!("{}", err),
+                }));
             }
         };
+        let client = self.clone();
+        Either::A(self.send_request(Method::GET, url, None).then(
+            move |result| -> Box<dyn Future<Item = HealthStatus, Error = InfluxDbError>> {
+                let body = match result {
+                    Ok((_, body)) => body.to_vec(),
+                    // InfluxDB responds to failed health checks with `503 Service Unavailable`
+                    Err(InfluxDbError::HttpError { status: 503, body }) => body.into_bytes(),
+                    Err(InfluxDbError::HttpError { status: 404, .. }) => {
+                        return Box::new(client.ping().map(|ping| HealthStatus {
+                            name: "influxdb".to_string(),
+                            message: "ready for queries and writes".to_string(),
+                            status: HealthStatusValue::Pass,
+                            version: ping.version,
+                        }));
+                    }
+                    Err(err) => return Box::new(future::err(err)),
+                };
+                Box::new(future::result(parse_health_status(&body)))
+            },
+        ))
+    }
+
+    /// Initializes a new InfluxDB 2.x instance with its first user, organization and bucket
+    ///
+    /// # Arguments
+    ///
+    ///  * `retention_seconds`: How long data is kept in the bucket, forever if `None`.
+    ///  * `token`: Token to create for the user, generated by InfluxDB if `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    /// let client = InfluxDbClient::new("http://localhost:9999", "weather");
+    /// let setup = rt
+    ///     .block_on(client.setup("admin", "password", "openvelo", "weather", None, None))
+    ///     .unwrap();
+    /// let _client = client
+    ///     .with_token(setup.auth_token)
+    ///     .with_org("openvelo")
+    ///     .with_bucket("weather");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the instance has already been set up, InfluxDB responds with `422 Unprocessable Entity`
+    /// and a [`InfluxDbError::HttpError`] will be returned.
+    ///
+    /// [`InfluxDbError::HttpError`]: crate::error::InfluxDbError::HttpError
+    pub fn setup(
+        &self,
+        username: &str,
+        password: &str,
+        org: &str,
+        bucket: &str,
+        retention_seconds: Option<u64>,
+        token: Option<&str>,
+    ) -> impl Future<Item = SetupResponse, Error = InfluxDbError> {
+        use futures::future;
+
+        let url = match self.setup_url() {
+            Ok(url) => url,
+            Err(err) => return Either::B(future::err(err)),
+        };
+        let mut body = serde_json::json!({
+            "username": username,
+            "password": password,
+            "org": org,
+            "bucket": bucket,
+        });
+        if let Some(retention_seconds) = retention_seconds {
+            body["retentionPeriodSeconds"] = retention_seconds.into();
+        }
+        if let Some(token) = token {
+            body["token"] = token.into();
+        }
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        Either::A(
+            self.send_request_with_headers(Method::POST, url, headers, Some(body.to_string()))
+                .and_then(|(_, body)| {
+                    serde_json::from_slice::<_SetupResponse>(&body)
+                        .map(|setup| SetupResponse {
+                            user_id: setup.user.id,
+                            org_id: setup.org.id,
+                            bucket_id: setup.bucket.id,
+                            auth_token: setup.auth.token,
+                        })
+                        .map_err(|err| InfluxDbError::DeserializationError {
+                            error: format!("serde error: {}", err),
+                        })
+                }),
+        )
+    }
+
+    /// Returns whether the InfluxDB 2.x instance has not been set up yet, so
+    /// [`setup`](crate::client::InfluxDbClient::setup) can be called
+    pub fn is_setup_required(&self) -> impl Future<Item = bool, Error = InfluxDbError> {
+        use futures::future;
 
+        let url = match self.setup_url() {
+            Ok(url) => url,
+            Err(err) => return Either::B(future::err(err)),
+        };
         Either::A(
-            client
-                .send()
-                .map_err(|err| InfluxDbError::ConnectionError { error: err })
-                .and_then(
-                    |res| -> future::FutureResult<reqwest::r#async::Response, InfluxDbError> {
-                        match res.status() {
-                            StatusCode::UNAUTHORIZED => {
-                                futures::future::err(InfluxDbError::AuthorizationError)
-                            }
-                            StatusCode::FORBIDDEN => {
-                                futures::future::err(InfluxDbError::AuthenticationError)
-                            }
-                            _ => futures::future::ok(res),
+            self.send_request(Method::GET, url, None)
+                .and_then(|(_, body)| {
+                    serde_json::from_slice::<_SetupAllowed>(&body)
+                        .map(|setup| setup.allowed)
+                        .map_err(|err| InfluxDbError::DeserializationError {
+                            error: format!("serde error: {}", err),
+                        })
+                }),
+        )
+    }
+
+    /// Returns all buckets the token of the client can read
+    ///
+    /// Requires [`with_token`](crate::client::InfluxDbClient::with_token).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let mut rt = tokio::runtime::current_thread::Runtime::new().unwrap();
+    /// let client = InfluxDbClient::new("http://localhost:9999", "weather").with_token("my-token");
+    /// for bucket in rt.block_on(client.list_buckets()).unwrap() {
+    ///     println!("{} ({})", bucket.name, bucket.id);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the client has no token, a
+    /// [`InfluxDbError::InvalidQueryError`](crate::error::InfluxDbError::InvalidQueryError) will
+    /// be returned.
+    pub fn list_buckets(&self) -> impl Future<Item = Vec<BucketInfo>, Error = InfluxDbError> {
+        use futures::future::{self, Loop};
+
+        let client = self.clone();
+        future::loop_fn(
+            (0, Vec::new()),
+            move |(offset, mut buckets): (usize, Vec<BucketInfo>)| {
+                let url = client.buckets_url(
+                    None,
+                    &[
+                        ("limit", BUCKETS_PAGE_SIZE.to_string()),
+                        ("offset", offset.to_string()),
+                    ],
+                );
+                let client = client.clone();
+                future::result(url)
+                    .and_then(move |url| client.get_buckets(url))
+                    .map(move |page| {
+                        let more = page.len() == BUCKETS_PAGE_SIZE;
+                        buckets.extend(page);
+                        if more {
+                            Loop::Continue((offset + BUCKETS_PAGE_SIZE, buckets))
+                        } else {
+                            Loop::Break(buckets)
                         }
-                    },
-                )
-                .and_then(|mut res| {
-                    let body = mem::replace(res.body_mut(), Decoder::empty());
-                    body.concat2().map_err(|err| InfluxDbError::ProtocolError {
-                        error: format!("{}", err),
                     })
-                })
-                .and_then(|body| {
-                    // Try parsing InfluxDBs { "error": "error message here" }
-                    if let Ok(error) = serde_json::from_slice::<_DatabaseError>(&body) {
-                        return futures::future::err(InfluxDbError::DatabaseError {
-                            error: error.error.to_string(),
-                        });
-                    } else {
-                        // Json has another structure, let's try actually parsing it to the type we're deserializing
-                        let from_slice = serde_json::from_slice::<DatabaseQueryResult>(&body);
-
-                        let deserialized = match from_slice {
-                            Ok(deserialized) => deserialized,
-                            Err(err) => {
-                                return futures::future::err(InfluxDbError::DeserializationError {
-                                    error: format!("serde error: {}", err),
-                                })
-                            }
-                        };
+            },
+        )
+    }
 
-                        return futures::future::result(Ok(deserialized));
-                    }
+    /// Returns the bucket called `name`, or `None` if the token of the client can not read a
+    /// bucket of that name
+    ///
+    /// Requires [`with_token`](crate::client::InfluxDbClient::with_token).
+    pub fn find_bucket_by_name(
+        &self,
+        name: &str,
+    ) -> impl Future<Item = Option<BucketInfo>, Error = InfluxDbError> {
+        use futures::future;
+
+        let url = match self.buckets_url(None, &[("name", name.to_string())]) {
+            Ok(url) => url,
+            Err(err) => return Either::B(future::err(err)),
+        };
+        Either::A(self.get_buckets(url).then(|result| match result {
+            Ok(buckets) => Ok(buckets.into_iter().next()),
+            // Some versions of InfluxDB respond with `404 Not Found` instead of an empty list
+            Err(InfluxDbError::HttpError { status: 404, .. }) => Ok(None),
+            Err(err) => Err(err),
+        }))
+    }
+
+    /// Creates the bucket `name` in the organization with the ID `org_id`
+    ///
+    /// Without `retention_rules`, data is kept forever. Requires
+    /// [`with_token`](crate::client::InfluxDbClient::with_token).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use influxdb::client::InfluxDbClient;
+    /// use influxdb::integrations::serde_integration::RetentionRule;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:9999", "weather").with_token("my-token");
+    /// let _future = client.create_bucket("weather", "0123456789abcdef", vec![RetentionRule::expire(3600)]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If a bucket of that name already exists, InfluxDB responds with `422 Unprocessable Entity`
+    /// and a [`InfluxDbError::HttpError`](crate::error::InfluxDbError::HttpError) will be returned.
+    pub fn create_bucket(
+        &self,
+        name: &str,
+        org_id: &str,
+        retention_rules: Vec<RetentionRule>,
+    ) -> impl Future<Item = BucketInfo, Error = InfluxDbError> {
+        use futures::future;
+
+        let url = match self.buckets_url(None, &[]) {
+            Ok(url) => url,
+            Err(err) => return Either::B(future::err(err)),
+        };
+        let body = serde_json::json!({
+            "name": name,
+            "orgID": org_id,
+            "retentionRules": retention_rules,
+        });
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        Either::A(
+            self.send_request_with_headers(Method::POST, url, headers, Some(body.to_string()))
+                .and_then(|(_, body)| {
+                    serde_json::from_slice::<BucketInfo>(&body).map_err(|err| {
+                        InfluxDbError::DeserializationError {
+                            error: format!("serde error: {}", err),
+                        }
+                    })
                 }),
         )
     }
+
+    /// Deletes the bucket with the ID `bucket_id`, including all of its data
+    ///
+    /// Requires [`with_token`](crate::client::InfluxDbClient::with_token).
+    pub fn delete_bucket(&self, bucket_id: &str) -> impl Future<Item = (), Error = InfluxDbError> {
+        use futures::future;
+
+        let url = match self.buckets_url(Some(bucket_id), &[]) {
+            Ok(url) => url,
+            Err(err) => return Either::B(future::err(err)),
+        };
+        Either::A(self.send_request(Method::DELETE, url, None).map(|_| ()))
+    }
+
+    fn get_buckets(&self, url: Url) -> impl Future<Item = Vec<BucketInfo>, Error = InfluxDbError> {
+        self.send_request(Method::GET, url, None)
+            .and_then(|(_, body)| {
+                serde_json::from_slice::<_Buckets>(&body)
+                    .map(|buckets| buckets.buckets)
+                    .map_err(|err| InfluxDbError::DeserializationError {
+                        error: format!("serde error: {}", err),
+                    })
+            })
+    }
+
+    fn setup_url(&self) -> Result<Url, InfluxDbError> {
+        Url::parse(&self.endpoint_url("/api/v2/setup")).map_err(|err| {
+            InfluxDbError::UrlConstructionError {
+                error: format!("{}", err),
+            }
+        })
+    }
+
+    /// Returns the names of all databases
+    pub fn show_databases(&self) -> impl Future<Item = Vec<String>, Error = InfluxDbError> {
+        self.show_values::<(String,)>("SHOW DATABASES".to_string())
+            .map(|values| values.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Returns whether the database `name` exists
+    pub fn database_exists<S>(&self, name: S) -> impl Future<Item = bool, Error = InfluxDbError>
+    where
+        S: AsRef<str>,
+    {
+        let name = name.as_ref().to_string();
+        self.show_databases()
+            .map(move |databases| databases.contains(&name))
+    }
+
+    /// Returns the names of all measurements in the database
+    pub fn show_measurements(&self) -> impl Future<Item = Vec<String>, Error = InfluxDbError> {
+        self.show_values::<(String,)>("SHOW MEASUREMENTS".to_string())
+            .map(|values| values.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Copies the points of the measurement `src` into the measurement `dst`, optionally only
+    /// those between `start` and `stop` of `time_range`
+    ///
+    /// Runs `SELECT * INTO <dst> FROM <src> GROUP BY *`, which keeps tags as tags. Points already
+    /// in `dst` with the same series and time are overwritten. Copying a large measurement in one
+    /// query can exceed the request timeout, so it can be split into several time ranges.
+    ///
+    /// # Arguments
+    ///
+    ///  * `src`: The measurement to copy points from.
+    ///  * `dst`: The measurement to copy points to, which is created if it does not exist.
+    ///  * `time_range`: The start and end of the copied points, both inclusive, as RFC3339 time
+    ///    (ex. `2020-01-01T00:00:00Z`), or `None` to copy all points.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:8086", "test");
+    /// let _future = client.copy_measurement(
+    ///     "weather",
+    ///     "weather_2020",
+    ///     Some(("2020-01-01T00:00:00Z", "2020-12-31T23:59:59Z")),
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `src` does not exist, a [`InfluxDbError::DatabaseError`] will be returned.
+    ///
+    /// [`InfluxDbError::DatabaseError`]: crate::error::InfluxDbError::DatabaseError
+    pub fn copy_measurement<S1, S2>(
+        &self,
+        src: S1,
+        dst: S2,
+        time_range: Option<(&str, &str)>,
+    ) -> impl Future<Item = (), Error = InfluxDbError>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        let src = src.as_ref();
+        let mut query = format!(
+            "SELECT * INTO {} FROM {}",
+            quote_identifier(dst.as_ref()),
+            quote_identifier(src)
+        );
+        if let Some((start, stop)) = time_range {
+            query.push_str(&format!(
+                " WHERE time >= {} AND time <= {}",
+                InfluxDbValue::from(start),
+                InfluxDbValue::from(stop)
+            ));
+        }
+        query.push_str(" GROUP BY *");
+
+        let client = self.clone();
+        let missing = format!("measurement \"{}\" does not exist", src);
+        self.show_values::<(String,)>(format!(
+            "SHOW MEASUREMENTS WITH MEASUREMENT = {}",
+            quote_identifier(src)
+        ))
+        .and_then(move |measurements| {
+            if measurements.is_empty() {
+                return Either::B(futures::future::err(InfluxDbError::DatabaseError {
+                    error: missing,
+                }));
+            }
+            Either::A(client.query(&InfluxDbReadQuery::new(query)).map(|_| ()))
+        })
+    }
+
+    /// Returns the tag keys of `measurement`
+    pub fn show_tag_keys(
+        &self,
+        measurement: &str,
+    ) -> impl Future<Item = Vec<String>, Error = InfluxDbError> {
+        self.show_values::<(String,)>(format!(
+            "SHOW TAG KEYS FROM {}",
+            quote_identifier(measurement)
+        ))
+        .map(|values| values.into_iter().map(|(key,)| key).collect())
+    }
+
+    /// Returns the values of the tag `key` of `measurement`
+    pub fn show_tag_values(
+        &self,
+        measurement: &str,
+        key: &str,
+    ) -> impl Future<Item = Vec<String>, Error = InfluxDbError> {
+        self.show_values::<(String, String)>(format!(
+            "SHOW TAG VALUES FROM {} WITH KEY = {}",
+            quote_identifier(measurement),
+            quote_identifier(key)
+        ))
+        .map(|values| values.into_iter().map(|(_, value)| value).collect())
+    }
+
+    /// Returns the estimated number of series of `measurement`, e.g. to detect a tag with too
+    /// many distinct values
+    pub fn show_series_cardinality(
+        &self,
+        measurement: &str,
+    ) -> impl Future<Item = u64, Error = InfluxDbError> {
+        self.show_count(format!(
+            "SHOW SERIES CARDINALITY FROM {}",
+            quote_identifier(measurement)
+        ))
+    }
+
+    /// Returns the estimated number of measurements in the database
+    pub fn show_measurement_cardinality(&self) -> impl Future<Item = u64, Error = InfluxDbError> {
+        self.show_count("SHOW MEASUREMENT CARDINALITY".to_string())
+    }
+
+    /// Returns the field keys and their types of `measurement`
+    pub fn show_field_keys(
+        &self,
+        measurement: &str,
+    ) -> impl Future<Item = Vec<FieldKeyInfo>, Error = InfluxDbError> {
+        self.show_values::<(String, String)>(format!(
+            "SHOW FIELD KEYS FROM {}",
+            quote_identifier(measurement)
+        ))
+        .map(|values| {
+            values
+                .into_iter()
+                .map(|(name, field_type)| FieldKeyInfo { name, field_type })
+                .collect()
+        })
+    }
+
+    /// Returns the retention policies of the database of the client
+    pub fn show_retention_policies(
+        &self,
+    ) -> impl Future<Item = Vec<RetentionPolicy>, Error = InfluxDbError> {
+        self.show_values::<(String, String, String, u32, bool)>(format!(
+            "SHOW RETENTION POLICIES ON {}",
+            quote_identifier(self.database_name())
+        ))
+        .map(|values| {
+            values
+                .into_iter()
+                .map(
+                    |(name, duration, shard_group_duration, replication, is_default)| {
+                        RetentionPolicy {
+                            name,
+                            duration,
+                            shard_group_duration,
+                            replication,
+                            is_default,
+                        }
+                    },
+                )
+                .collect()
+        })
+    }
+
+    /// Returns the shard groups of all databases
+    pub fn show_shard_groups(
+        &self,
+    ) -> impl Future<Item = Vec<ShardGroupInfo>, Error = InfluxDbError> {
+        self.show_values::<(u64, String, String, String, String, String)>(
+            "SHOW SHARD GROUPS".to_string(),
+        )
+        .map(|values| {
+            values
+                .into_iter()
+                .map(
+                    |(id, database, retention_policy, start_time, end_time, expiry_time)| {
+                        ShardGroupInfo {
+                            id,
+                            database,
+                            retention_policy,
+                            start_time,
+                            end_time,
+                            expiry_time,
+                        }
+                    },
+                )
+                .collect()
+        })
+    }
+
+    /// Returns the shards of all databases
+    ///
+    /// # Errors
+    ///
+    /// If the owners of a shard are not a comma separated list of node IDs, a
+    /// [`InfluxDbError::DeserializationError`] will be returned.
+    ///
+    /// [`InfluxDbError::DeserializationError`]: crate::error::InfluxDbError::DeserializationError
+    pub fn show_shards(&self) -> impl Future<Item = Vec<ShardInfo>, Error = InfluxDbError> {
+        // Shards are returned in one series per database, which also have a database column
+        self.show_values::<(u64, String, String, u64, String, String, String, String)>(
+            "SHOW SHARDS".to_string(),
+        )
+        .and_then(|values| {
+            values
+                .into_iter()
+                .map(
+                    |(
+                        id,
+                        database,
+                        retention_policy,
+                        shard_group,
+                        start_time,
+                        end_time,
+                        expiry_time,
+                        owners,
+                    )| {
+                        let owners = owners
+                            .split(',')
+                            .filter(|owner| !owner.is_empty())
+                            .map(|owner| {
+                                owner.trim().parse::<u64>().map_err(|_| {
+                                    InfluxDbError::DeserializationError {
+                                        error: format!("invalid shard owner \"{}\"", owner),
+                                    }
+                                })
+                            })
+                            .collect::<Result<Vec<u64>, InfluxDbError>>()?;
+                        Ok(ShardInfo {
+                            id,
+                            database,
+                            retention_policy,
+                            shard_group,
+                            start_time,
+                            end_time,
+                            expiry_time,
+                            owners,
+                        })
+                    },
+                )
+                .collect::<Result<Vec<ShardInfo>, InfluxDbError>>()
+        })
+    }
+
+    /// Returns the subscriptions of all databases
+    pub fn show_subscriptions(
+        &self,
+    ) -> impl Future<Item = Vec<SubscriptionInfo>, Error = InfluxDbError> {
+        // Subscriptions are returned in one series per database, named after the database
+        self.json_query(&InfluxDbReadQuery::new("SHOW SUBSCRIPTIONS"))
+            .and_then(|mut db_result| {
+                db_result.deserialize_next::<(String, String, String, Vec<String>)>()
+            })
+            .map(|result| {
+                result
+                    .series
+                    .into_iter()
+                    .flat_map(|series| {
+                        let database = series.name;
+                        series.values.into_iter().map(
+                            move |(retention_policy, name, mode, destinations)| SubscriptionInfo {
+                                name,
+                                database: database.clone(),
+                                retention_policy,
+                                mode,
+                                destinations,
+                            },
+                        )
+                    })
+                    .collect()
+            })
+    }
+
+    /// Returns all users and whether they are admins
+    pub fn show_users(&self) -> impl Future<Item = Vec<UserInfo>, Error = InfluxDbError> {
+        self.show_values::<(String, bool)>("SHOW USERS".to_string())
+            .map(|values| {
+                values
+                    .into_iter()
+                    .map(|(username, admin)| UserInfo { username, admin })
+                    .collect()
+            })
+    }
+
+    /// Returns the continuous queries of all databases
+    pub fn show_continuous_queries(
+        &self,
+    ) -> impl Future<Item = Vec<ContinuousQuery>, Error = InfluxDbError> {
+        // Continuous queries are returned in one series per database, named after the database
+        self.json_query(&InfluxDbReadQuery::new("SHOW CONTINUOUS QUERIES"))
+            .and_then(|mut db_result| db_result.deserialize_next::<(String, String)>())
+            .map(|result| {
+                result
+                    .series
+                    .into_iter()
+                    .flat_map(|series| {
+                        let database = series.name;
+                        series
+                            .values
+                            .into_iter()
+                            .map(move |(name, query)| ContinuousQuery {
+                                name,
+                                database: database.clone(),
+                                query,
+                            })
+                    })
+                    .collect()
+            })
+    }
+
+    /// Sends a Flux query to InfluxDB 2.x like
+    /// [`flux_query`](crate::client::InfluxDbClient::flux_query), but requests and parses a JSON
+    /// response
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::client::InfluxDbClient;
+    ///
+    /// let client = InfluxDbClient::new("http://localhost:9999", "test")
+    ///     .with_token("my-token")
+    ///     .with_org("openvelo");
+    /// let _future = client.flux_query_json(r#"from(bucket: "weather") |> range(start: -1h)"#);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the client has no token or organization, a
+    /// [`InfluxDbError::InvalidQueryError`](crate::error::InfluxDbError::InvalidQueryError) will
+    /// be returned.
+    pub fn flux_query_json(
+        &self,
+        script: &str,
+    ) -> impl Future<Item = Value, Error = InfluxDbError> {
+        self.send_flux_query(script, "application/json")
+            .and_then(|body| {
+                serde_json::from_str(&body).map_err(|err| InfluxDbError::DeserializationError {
+                    error: format!("serde error: {}", err),
+                })
+            })
+    }
+
+    /// Runs a `SHOW ... CARDINALITY` query and returns the sum of the counts of all series,
+    /// or 0 if the result is empty
+    fn show_count(&self, query: String) -> impl Future<Item = u64, Error = InfluxDbError> {
+        self.show_values::<(u64,)>(query)
+            .map(|values| values.into_iter().map(|(count,)| count).sum())
+    }
+
+    /// Runs a `SHOW` query and returns the values of all series of its result
+    fn show_values<T>(&self, query: String) -> impl Future<Item = Vec<T>, Error = InfluxDbError>
+    where
+        T: DeserializeOwned + 'static,
+    {
+        self.json_query(&InfluxDbReadQuery::new(query))
+            .and_then(|mut db_result| db_result.deserialize_next::<T>())
+            .map(|result| {
+                result
+                    .series
+                    .into_iter()
+                    .flat_map(|series| series.values)
+                    .collect()
+            })
+    }
+}
+
+/// Prefix of the keys [`InfluxDbWriteQuery::from_json`] writes as tags
+const JSON_TAG_PREFIX: &str = "_tag_";
+
+impl InfluxDbWriteQuery {
+    /// Creates a write query from the members of a JSON object
+    ///
+    /// Members are written as fields, except for members whose key starts with `_tag_`, which are
+    /// written as tags without the prefix. Integers are written as integer fields, other numbers
+    /// as float fields. Members which are `null` are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::write_query::InfluxDbWriteQuery;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    /// use serde_json::json;
+    ///
+    /// let query = InfluxDbWriteQuery::from_json(
+    ///     Timestamp::HOURS(11),
+    ///     "weather",
+    ///     json!({ "temperature": 22.5, "_tag_location": "berlin" }),
+    /// );
+    ///
+    /// assert_eq!(
+    ///     query.unwrap().build().unwrap(),
+    ///     "weather,location=berlin temperature=22.5 11"
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `value` is not an object, or a member is an object or an array,
+    /// a [`InfluxDbError::InvalidQueryError`] will be returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn from_json<S>(
+        timestamp: Timestamp,
+        measurement: S,
+        value: Value,
+    ) -> Result<Self, InfluxDbError>
+    where
+        S: Into<String>,
+    {
+        let members = match value {
+            Value::Object(members) => members,
+            value => {
+                return Err(InfluxDbError::InvalidQueryError {
+                    error: format!("expected a JSON object, found {}", value),
+                });
+            }
+        };
+        let mut query = InfluxDbWriteQuery::new(timestamp, measurement.into());
+        for (key, value) in members {
+            query = match (key.strip_prefix(JSON_TAG_PREFIX), value) {
+                (_, Value::Null) => query,
+                (_, value @ Value::Object(_)) | (_, value @ Value::Array(_)) => {
+                    return Err(InfluxDbError::InvalidQueryError {
+                        error: format!("value of \"{}\" must not be nested, found {}", key, value),
+                    });
+                }
+                (Some(tag), Value::String(value)) => query.add_tag(tag, value),
+                (Some(tag), value) => query.add_tag(tag, value.to_string()),
+                (None, Value::Bool(value)) => query.add_field(key, value),
+                (None, Value::String(value)) => query.add_field(key, value),
+                (None, Value::Number(number)) => match (number.as_i64(), number.as_u64()) {
+                    (Some(value), _) => query.add_field(key, value),
+                    (None, Some(value)) => query.add_field(key, value),
+                    // Numbers which are neither `i64` nor `u64` are always representable as `f64`
+                    (None, None) => query.add_field(key, number.as_f64().unwrap_or_default()),
+                },
+            };
+        }
+        Ok(query)
+    }
+}
+
+/// JSON representation of a [`InfluxDbWriteQuery`], e.g.
+/// `{"measurement":"weather","timestamp":{"unit":"h","value":11},"retention_policy":null,"tags":[{"key":"location","value":"berlin"}],"fields":[{"key":"temperature","type":"integer","value":82}]}`
+///
+/// Tags and fields are lists to keep their order, and fields are annotated with their type, so
+/// integers and floats are told apart.
+#[derive(Serialize, Deserialize)]
+struct WriteQueryJson {
+    measurement: String,
+    /// `None` for [`Timestamp::NOW`]
+    #[serde(default)]
+    timestamp: Option<TimestampJson>,
+    #[serde(default)]
+    retention_policy: Option<String>,
+    #[serde(default)]
+    tags: Vec<TagJson>,
+    fields: Vec<FieldJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TimestampJson {
+    /// Precision of the timestamp as in the line protocol, e.g. `ns` or `h`
+    unit: String,
+    value: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TagJson {
+    key: String,
+    value: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FieldJson {
+    key: String,
+    /// `boolean`, `float`, `integer`, `unsigned` or `string`
+    #[serde(rename = "type")]
+    field_type: String,
+    /// A number, or for floats which JSON cannot represent the string `NaN`, `inf` or `-inf`
+    value: Value,
+}
+
+impl From<&InfluxDbWriteQuery> for WriteQueryJson {
+    fn from(query: &InfluxDbWriteQuery) -> Self {
+        let timestamp = match query.timestamp() {
+            Timestamp::NOW => None,
+            Timestamp::NANOSECONDS(value)
+            | Timestamp::MICROSECONDS(value)
+            | Timestamp::MILLISECONDS(value)
+            | Timestamp::SECONDS(value)
+            | Timestamp::MINUTES(value)
+            | Timestamp::HOURS(value) => Some(TimestampJson {
+                unit: query.get_precision(),
+                value,
+            }),
+        };
+        WriteQueryJson {
+            measurement: query.get_measurement().to_string(),
+            timestamp,
+            retention_policy: query.get_retention_policy().map(String::from),
+            tags: query
+                .tags()
+                .iter()
+                .map(|(key, value)| TagJson {
+                    key: key.clone(),
+                    value: value.clone(),
+                })
+                .collect(),
+            fields: query
+                .fields()
+                .iter()
+                .map(|(key, value)| field_json(key, value))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<WriteQueryJson> for InfluxDbWriteQuery {
+    type Error = String;
+
+    fn try_from(json: WriteQueryJson) -> Result<Self, Self::Error> {
+        let timestamp = match json.timestamp {
+            None => Timestamp::NOW,
+            Some(TimestampJson { unit, value }) => match unit.as_str() {
+                "ns" => Timestamp::NANOSECONDS(value),
+                "u" => Timestamp::MICROSECONDS(value),
+                "ms" => Timestamp::MILLISECONDS(value),
+                "s" => Timestamp::SECONDS(value),
+                "m" => Timestamp::MINUTES(value),
+                "h" => Timestamp::HOURS(value),
+                unit => return Err(format!("unknown timestamp unit \"{}\"", unit)),
+            },
+        };
+        let mut query = InfluxDbWriteQuery::new(timestamp, json.measurement);
+        if let Some(retention_policy) = json.retention_policy {
+            query = query.with_retention_policy(retention_policy);
+        }
+        for tag in json.tags {
+            query = query.add_tag(tag.key, tag.value);
+        }
+        for field in json.fields {
+            let value = match (field.field_type.as_str(), &field.value) {
+                ("boolean", Value::Bool(value)) => FieldValue::Boolean(*value),
+                ("float", Value::Number(number)) if number.as_f64().is_some() => {
+                    FieldValue::Float(number.as_f64().unwrap_or_default())
+                }
+                ("float", Value::String(value))
+                    if matches!(value.as_str(), "NaN" | "inf" | "-inf") =>
+                {
+                    FieldValue::Float(value.parse().unwrap_or_default())
+                }
+                ("integer", Value::Number(number)) if number.as_i64().is_some() => {
+                    FieldValue::SignedInteger(number.as_i64().unwrap_or_default())
+                }
+                ("unsigned", Value::Number(number)) if number.as_u64().is_some() => {
+                    FieldValue::UnsignedInteger(number.as_u64().unwrap_or_default())
+                }
+                ("string", Value::String(value)) => FieldValue::Text(value.clone()),
+                (field_type, value) => {
+                    return Err(format!(
+                        "invalid {} value {} of field \"{}\"",
+                        field_type, value, field.key
+                    ));
+                }
+            };
+            query = query.add_field(field.key, value);
+        }
+        Ok(query)
+    }
+}
+
+/// Annotates a field value with its type
+fn field_json(key: &str, value: &FieldValue) -> FieldJson {
+    let (field_type, value) = match value {
+        FieldValue::Boolean(value) => ("boolean", Value::Bool(*value)),
+        FieldValue::Float(value) => (
+            "float",
+            Number::from_f64(*value)
+                .map_or_else(|| Value::String(value.to_string()), Value::Number),
+        ),
+        FieldValue::SignedInteger(value) => ("integer", Value::from(*value)),
+        FieldValue::UnsignedInteger(value) => ("unsigned", Value::from(*value)),
+        FieldValue::Text(value) => ("string", Value::String(value.clone())),
+    };
+    FieldJson {
+        key: key.to_string(),
+        field_type: field_type.to_string(),
+        value,
+    }
+}
+
+impl Serialize for InfluxDbWriteQuery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        WriteQueryJson::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for InfluxDbWriteQuery {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let json = WriteQueryJson::deserialize(deserializer)?;
+        InfluxDbWriteQuery::try_from(json).map_err(serde::de::Error::custom)
+    }
+}
+
+impl InfluxDbWriteQuery {
+    /// Serializes the query to JSON with its measurement, timestamp, retention policy, tags and
+    /// typed fields, e.g. to log it or to store it for a later retry
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::write_query::InfluxDbWriteQuery;
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+    ///     .add_tag("location", "berlin")
+    ///     .add_field("temperature", 82);
+    /// let restored = InfluxDbWriteQuery::from_json_str(&query.to_json()).unwrap();
+    ///
+    /// assert_eq!(restored.to_line_protocol(), query.to_line_protocol());
+    /// ```
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("write queries always serialize to JSON")
+    }
+
+    /// Restores a query serialized with [`to_json`](crate::query::write_query::InfluxDbWriteQuery::to_json)
+    ///
+    /// # Errors
+    ///
+    /// If `json` is not a serialized write query, a [`InfluxDbError::DeserializationError`] will
+    /// be returned.
+    ///
+    /// [`InfluxDbError::DeserializationError`]: crate::error::InfluxDbError::DeserializationError
+    pub fn from_json_str(json: &str) -> Result<Self, InfluxDbError> {
+        serde_json::from_str(json).map_err(|err| InfluxDbError::DeserializationError {
+            error: format!("serde error: {}", err),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        merge_chunks, parse_chunks, parse_query_result, split_lines, BucketInfo, ContinuousQuery,
+        DatabaseQueryResult, FieldKeyInfo, HealthStatus, HealthStatusValue, InfluxDbMessage,
+        QueryStats, RetentionPolicy, RetentionRule, SetupResponse, ShardGroupInfo, ShardInfo,
+        SubscriptionInfo, UserInfo,
+    };
+    use crate::client::tests::{mock_server, mock_server_with};
+    use crate::client::InfluxDbClient;
+    use crate::error::InfluxDbError;
+    use crate::query::read_query::{InfluxDbReadQuery, InfluxDbValue};
+    use crate::query::write_query::InfluxDbWriteQuery;
+    use crate::query::{InfluxDbQuery, Precision, Timestamp};
+    use futures::{stream, Future, Stream};
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use tokio::runtime::current_thread::Runtime;
+
+    const SHOW_DATABASES_RESPONSE: &str = "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 120\r\n\r\n{\"results\":[{\"statement_id\":0,\"series\":[{\"name\":\"databases\",\"columns\":[\"name\"],\"values\":[[\"_internal\"],[\"weather\"]]}]}]}";
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Weather {
+        time: String,
+        temperature: i32,
+    }
+
+    fn query_result(json: &str) -> DatabaseQueryResult {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_deserialize_next() {
+        let mut result = query_result(
+            r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature"],"values":[["1970-01-01T11:00:00Z",82]]}]}]}"#,
+        );
+        assert_eq!(result.len(), 1);
+
+        let weather = result.deserialize_next::<Weather>().wait().unwrap();
+        assert_eq!(weather.series[0].name, "weather");
+        assert_eq!(
+            weather.series[0].values,
+            vec![Weather {
+                time: "1970-01-01T11:00:00Z".to_string(),
+                temperature: 82
+            }]
+        );
+    }
+
+    #[test]
+    fn test_deserialize_next_empty_result() {
+        let mut result = query_result(r#"{"results":[{"statement_id":0}]}"#);
+
+        let weather = result.deserialize_next::<Weather>().wait().unwrap();
+        assert!(weather.series.is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_statement_error() {
+        let json = r#"{"results":[{"statement_id":0,"error":"database not found: x"}]}"#;
+        let database_error = Err(InfluxDbError::DatabaseError {
+            error: "database not found: x".to_string(),
+        });
+
+        let weather = query_result(json).deserialize_next::<Weather>().wait();
+        assert_eq!(weather.map(|_| ()), database_error);
+        let weather = query_result(json).deserialize_all::<Weather>().wait();
+        assert_eq!(weather.map(|_| ()), database_error);
+        let weather = query_result(json)
+            .deserialize_for_statement::<Weather>(0)
+            .wait();
+        assert_eq!(weather.map(|_| ()), database_error);
+    }
+
+    #[test]
+    fn test_show_statement_error() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            json_response(
+                "200 OK",
+                r#"{"results":[{"statement_id":0,"error":"database not found: x"}]}"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "x");
+        let mut rt = Runtime::new().unwrap();
+        let database_error = Err(InfluxDbError::DatabaseError {
+            error: "database not found: x".to_string(),
+        });
+        assert_eq!(
+            rt.block_on(client.show_measurements()).map(|_| ()),
+            database_error
+        );
+        assert_eq!(
+            rt.block_on(client.copy_measurement("weather", "weather_copy", None))
+                .map(|_| ()),
+            database_error
+        );
+    }
+
+    #[test]
+    fn test_deserialize_next_without_results() {
+        let mut result = query_result(r#"{"results":[{"statement_id":0}]}"#);
+        assert!(result.deserialize_next::<Weather>().wait().is_ok());
+
+        // All results have been consumed
+        assert!(result.is_empty());
+        assert!(result.deserialize_next::<Weather>().wait().is_err());
+    }
+
+    #[test]
+    fn test_series_columns() {
+        let mut result = query_result(
+            r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature","humidity"],"values":[["1970-01-01T11:00:00Z",82,0.5]]}]}]}"#,
+        );
+
+        let weather = result
+            .deserialize_next::<serde_json::Value>()
+            .wait()
+            .unwrap();
+        let series = &weather.series[0];
+        assert_eq!(series.columns, vec!["time", "temperature", "humidity"]);
+        assert_eq!(series.column_index("humidity"), Some(2));
+        assert_eq!(series.column_index("pressure"), None);
+        assert_eq!(
+            series.values[0][series.column_index("temperature").unwrap()],
+            82
+        );
+    }
+
+    #[test]
+    fn test_deserialize_rows() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TaggedWeather {
+            temperature: i32,
+            location: String,
+            humidity: Option<f64>,
+        }
+
+        let mut result = query_result(
+            r#"{"results":[{"statement_id":0,"series":[{"name":"weather","tags":{"location":"berlin"},"columns":["time","humidity","temperature"],"values":[["1970-01-01T11:00:00Z",null,82]]}]}]}"#,
+        );
+        let mut weather = result
+            .deserialize_next::<serde_json::Value>()
+            .wait()
+            .unwrap();
+        let rows = weather
+            .series
+            .remove(0)
+            .deserialize_rows::<TaggedWeather>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![TaggedWeather {
+                temperature: 82,
+                location: "berlin".to_string(),
+                humidity: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_deserialize_next_tagged() {
+        let mut result = query_result(
+            r#"{"results":[{"statement_id":0,"series":[{"name":"weather","tags":{"location":"berlin"},"columns":["time","temperature"],"values":[["1970-01-01T11:00:00Z",82]]},{"name":"weather","tags":{"location":"london"},"columns":["time","temperature"],"values":[["1970-01-01T11:00:00Z",61]]}]}]}"#,
+        );
+
+        let weather = result.deserialize_next_tagged::<Weather>().wait().unwrap();
+        assert_eq!(weather.series.len(), 2);
+        assert_eq!(weather.series[0].tags["location"], "berlin");
+        assert_eq!(weather.series[0].values[0].temperature, 82);
+        assert_eq!(weather.series[1].tags["location"], "london");
+        assert_eq!(weather.series[1].values[0].temperature, 61);
+    }
+
+    #[test]
+    fn test_into_time_value_pairs() {
+        let mut result = query_result(
+            r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature"],"values":[[3600,82],[-60,null],[7200,84.5]]}]}]}"#,
+        );
+        let series = result
+            .deserialize_next::<serde_json::Value>()
+            .wait()
+            .unwrap()
+            .series
+            .remove(0);
+
+        assert_eq!(series.time_range(), Ok(Some((-60, 7200))));
+        assert_eq!(
+            series.into_time_value_pairs(|row| row[1].as_f64()),
+            Ok(vec![(3600, Some(82.0)), (-60, None), (7200, Some(84.5))])
+        );
+    }
+
+    #[test]
+    fn test_into_time_value_pairs_invalid() {
+        let mut result = query_result(
+            r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature"],"values":[["1970-01-01T11:00:00Z",82]]},{"name":"weather","columns":["temperature"],"values":[]}]}]}"#,
+        );
+        let mut series = result
+            .deserialize_next::<serde_json::Value>()
+            .wait()
+            .unwrap()
+            .series;
+
+        let without_time = series.pop().unwrap();
+        assert!(without_time.time_range().is_err());
+        let rfc3339 = series.pop().unwrap();
+        assert!(rfc3339.time_range().is_err());
+        assert!(rfc3339.into_time_value_pairs(|row| row).is_err());
+    }
+
+    #[test]
+    fn test_series_without_tags() {
+        let mut result = query_result(
+            r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature"],"values":[["1970-01-01T11:00:00Z",82]]}]}]}"#,
+        );
+
+        let weather = result.deserialize_next::<Weather>().wait().unwrap();
+        assert!(weather.series[0].tags.is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_all() {
+        let mut result = query_result(
+            r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature"],"values":[["1970-01-01T11:00:00Z",82]]}]},{"statement_id":1}]}"#,
+        );
+        assert_eq!(result.len(), 2);
+        assert!(!result.is_empty());
+
+        let weather = result.deserialize_all::<Weather>().wait().unwrap();
+        assert_eq!(weather.len(), 2);
+        assert_eq!(weather[0].series[0].values[0].temperature, 82);
+        assert!(weather[1].series.is_empty());
+        assert!(result.is_empty());
+
+        // All results have been consumed
+        let weather = result.deserialize_all::<Weather>().wait().unwrap();
+        assert!(weather.is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_for_statement() {
+        let body = r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature"],"values":[["1970-01-01T11:00:00Z",82]]}]},{"statement_id":1,"series":[{"name":"weather","columns":["time","temperature"],"values":[["1970-01-01T12:00:00Z",84]]}]}]}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            Box::leak(response.into_boxed_str()),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather LIMIT 1")
+            .add("SELECT * FROM weather ORDER BY time DESC LIMIT 1")
+            .unwrap();
+        let mut result = Runtime::new()
+            .unwrap()
+            .block_on(client.json_query(&query))
+            .unwrap();
+        assert!(requests.recv().unwrap().contains("%3BSELECT"));
+
+        let ids = result
+            .results_with_ids()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<u32>>();
+        assert_eq!(ids, vec![0, 1]);
+
+        let latest = result
+            .deserialize_for_statement::<Weather>(1)
+            .wait()
+            .unwrap();
+        assert_eq!(latest.series[0].values[0].temperature, 84);
+        assert_eq!(result.results_with_ids()[0].0, 0);
+        assert!(result
+            .deserialize_for_statement::<Weather>(1)
+            .wait()
+            .is_err());
+
+        let first = result.deserialize_next::<Weather>().wait().unwrap();
+        assert_eq!(first.series[0].values[0].temperature, 82);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_results_with_ids_skips_missing_ids() {
+        let result =
+            query_result(r#"{"results":[{"statement_id":2},{"series":[]},{"statement_id":-1}]}"#);
+        let results = result.results_with_ids();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, 2);
+        assert_eq!(results[0].1, serde_json::json!({"statement_id": 2}));
+    }
+
+    const CHUNKED_RESPONSE: &str = "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 300\r\n\r\n{\"results\":[{\"statement_id\":0,\"series\":[{\"name\":\"weather\",\"columns\":[\"time\",\"temperature\"],\"values\":[[\"1970-01-01T11:00:00Z\",82]],\"partial\":true}],\"partial\":true}]}\n{\"results\":[{\"statement_id\":0,\"series\":[{\"name\":\"weather\",\"columns\":[\"time\",\"temperature\"],\"values\":[[\"1970-01-01T12:00:00Z\",84]]}]}]}\n";
+
+    #[test]
+    fn test_split_lines() {
+        let mut buffer = b"{\"a\":1}\n\n{\"b\":2}\n{\"c\"".to_vec();
+        assert_eq!(
+            split_lines(&mut buffer),
+            vec![b"{\"a\":1}".to_vec(), b"{\"b\":2}".to_vec()]
+        );
+        assert_eq!(buffer, b"{\"c\"".to_vec());
+
+        // Without a complete line, the buffer is kept as is
+        assert!(split_lines(&mut buffer).is_empty());
+        assert_eq!(buffer, b"{\"c\"".to_vec());
+    }
+
+    #[test]
+    fn test_write_query_json_roundtrip() {
+        let query = InfluxDbQuery::write_query(Timestamp::MILLISECONDS(1500), "weather")
+            .with_retention_policy("one_week")
+            .add_tag("location", "us midwest")
+            .add_tag("station", "7")
+            .add_field("temperature", 82)
+            .add_field("humidity", 0.5)
+            .add_field("counter", std::u64::MAX)
+            .add_field("raining", false)
+            .add_field("description", "say \"hi\" \\ bye");
+        let json = query.to_json();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({
+                "measurement": "weather",
+                "timestamp": {"unit": "ms", "value": 1500},
+                "retention_policy": "one_week",
+                "tags": [
+                    {"key": "location", "value": "us midwest"},
+                    {"key": "station", "value": "7"},
+                ],
+                "fields": [
+                    {"key": "temperature", "type": "integer", "value": 82},
+                    {"key": "humidity", "type": "float", "value": 0.5},
+                    {"key": "counter", "type": "unsigned", "value": std::u64::MAX},
+                    {"key": "raining", "type": "boolean", "value": false},
+                    {"key": "description", "type": "string", "value": "say \"hi\" \\ bye"},
+                ],
+            })
+        );
+
+        let restored = InfluxDbWriteQuery::from_json_str(&json).unwrap();
+        assert_eq!(restored.to_line_protocol(), query.to_line_protocol());
+        assert_eq!(restored.get_retention_policy(), Some("one_week"));
+
+        let query = InfluxDbQuery::write_query(Timestamp::NOW, "weather").add_field("a", 1);
+        let restored = InfluxDbWriteQuery::from_json_str(&query.to_json()).unwrap();
+        assert_eq!(restored.build().unwrap(), "weather a=1i");
+    }
+
+    #[test]
+    fn test_write_query_json_roundtrip_non_finite_floats() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("a", std::f64::NAN)
+            .add_field("b", std::f64::INFINITY)
+            .add_field("c", std::f64::NEG_INFINITY);
+        let json = query.to_json();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap()["fields"],
+            serde_json::json!([
+                {"key": "a", "type": "float", "value": "NaN"},
+                {"key": "b", "type": "float", "value": "inf"},
+                {"key": "c", "type": "float", "value": "-inf"},
+            ])
+        );
+
+        let restored = InfluxDbWriteQuery::from_json_str(&json).unwrap();
+        assert_eq!(restored.to_line_protocol(), "weather a=NaN,b=inf,c=-inf 11");
+    }
+
+    #[test]
+    fn test_write_query_from_json_str_invalid() {
+        for json in &[
+            r#"{"fields":[]}"#,
+            r#"{"measurement":"weather","timestamp":{"unit":"d","value":1},"fields":[]}"#,
+            r#"{"measurement":"weather","fields":[{"key":"a","type":"integer","value":1.5}]}"#,
+            r#"{"measurement":"weather","fields":[{"key":"a","type":"float","value":"1.5"}]}"#,
+        ] {
+            match InfluxDbWriteQuery::from_json_str(json) {
+                Err(InfluxDbError::DeserializationError { .. }) => {}
+                result => panic!(
+                    "Expected DeserializationError for {}, got {:?}",
+                    json,
+                    result.map(|query| query.to_json())
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_query_with_params() {
+        let (url, requests) = mock_server(Duration::from_millis(0), SHOW_DATABASES_RESPONSE);
+        let client = InfluxDbClient::new(url, "database");
+        let mut params = HashMap::new();
+        params.insert("meas".to_string(), InfluxDbValue::from("cpu"));
+        params.insert("host".to_string(), InfluxDbValue::from("server1"));
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.json_query_with_params(
+                &InfluxDbQuery::raw_read_query("SELECT * FROM $meas WHERE host = $host"),
+                params,
+            ));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+        let request = requests.recv().unwrap();
+        assert!(
+            request.starts_with("GET /query?db=database&q=SELECT+*+FROM+%24meas+WHERE+host+%3D+%24host&params=%7B%22host%22%3A%22server1%22%2C%22meas%22%3A%22cpu%22%7D "),
+            "{}",
+            request
+        );
+    }
+
+    #[test]
+    fn test_statistics() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            json_response(
+                "200 OK",
+                r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature"],"values":[["2019-08-16T11:00:00Z",82]]}],"statistics":{"executionTime":1500000,"plannerTime":200000,"queueDuration":3000,"executeDuration":1200000,"concurrency":2}}]}"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.json_query(&InfluxDbQuery::raw_read_query("SELECT * FROM weather")))
+            .unwrap();
+        assert_eq!(
+            result.statistics(),
+            Some(&QueryStats {
+                execution_time_ns: 1_500_000,
+                planner_time_ns: 200_000,
+                queue_duration_ns: 3_000,
+                execute_duration_ns: 1_200_000,
+                concurrency: 2,
+            })
+        );
+
+        let chunks = vec![
+            parse_query_result(
+                br#"{"results":[{"statement_id":0,"statistics":{"executionTime":10},"partial":true}]}"#,
+            )
+            .unwrap(),
+            parse_query_result(br#"{"results":[{"statement_id":0}]}"#).unwrap(),
+        ];
+        assert_eq!(chunks[1].statistics(), None);
+        assert_eq!(
+            merge_chunks(chunks).statistics(),
+            Some(&QueryStats {
+                execution_time_ns: 10,
+                ..QueryStats::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_messages() {
+        let body = r#"{"results":[{"statement_id":0,"messages":[{"level":"warning","text":"deprecated use of 'SHOW SERIES'"}]}],"messages":[{"level":"info","text":"maintenance at noon"}]}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            Box::leak(response.into_boxed_str()),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.json_query(&InfluxDbQuery::raw_read_query("SHOW SERIES")))
+            .unwrap();
+        assert_eq!(
+            result.messages(),
+            &[
+                InfluxDbMessage {
+                    level: "info".to_string(),
+                    text: "maintenance at noon".to_string(),
+                },
+                InfluxDbMessage {
+                    level: "warning".to_string(),
+                    text: "deprecated use of 'SHOW SERIES'".to_string(),
+                },
+            ][..]
+        );
+        assert!(result.has_warnings());
+
+        let result = query_result(r#"{"results":[{"statement_id":0}]}"#);
+        assert!(result.messages().is_empty());
+        assert!(!result.has_warnings());
+    }
+
+    #[test]
+    fn test_merge_chunks() {
+        let mut result = merge_chunks(vec![
+            query_result(
+                r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature"],"values":[["1970-01-01T11:00:00Z",82]],"partial":true}],"partial":true}]}"#,
+            ),
+            query_result(
+                r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature"],"values":[["1970-01-01T12:00:00Z",84]]},{"name":"traffic","columns":["time","temperature"],"values":[["1970-01-01T12:00:00Z",20]]}]}]}"#,
+            ),
+            query_result(r#"{"results":[{"statement_id":1}]}"#),
+        ]);
+        assert_eq!(result.len(), 2);
+
+        let weather = result.deserialize_next::<Weather>().wait().unwrap();
+        assert_eq!(weather.series.len(), 2);
+        assert_eq!(weather.series[0].name, "weather");
+        assert_eq!(weather.series[0].values.len(), 2);
+        assert_eq!(weather.series[0].values[1].temperature, 84);
+        assert_eq!(weather.series[1].name, "traffic");
+        assert!(result
+            .deserialize_next::<Weather>()
+            .wait()
+            .unwrap()
+            .series
+            .is_empty());
+    }
+
+    #[test]
+    fn test_json_query_with_timeout() {
+        let (url, _) = mock_server(Duration::from_secs(2), SHOW_DATABASES_RESPONSE);
+        let client = InfluxDbClient::new(url, "database");
+        let query = InfluxDbReadQuery::new("SHOW DATABASES");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.json_query_with_timeout(&query, Duration::from_millis(100)));
+        match result {
+            Err(InfluxDbError::TimeoutError { elapsed }) => {
+                assert_eq!(elapsed, Duration::from_millis(100))
+            }
+            _ => panic!("Should cause a TimeoutError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_json_query_chunked() {
+        let (url, requests) = mock_server(Duration::from_millis(0), CHUNKED_RESPONSE);
+        let client = InfluxDbClient::new(url, "database").with_chunk_size(1);
+        let query = InfluxDbReadQuery::new("SELECT * FROM weather");
+        let mut result = Runtime::new()
+            .unwrap()
+            .block_on(client.json_query(&query))
+            .unwrap();
+
+        let weather = result.deserialize_next::<Weather>().wait().unwrap();
+        assert_eq!(weather.series.len(), 1);
+        assert_eq!(weather.series[0].values.len(), 2);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with(
+            "GET /query?db=database&q=SELECT+*+FROM+weather&chunked=true&chunk_size=1 "
+        ));
+    }
+
+    #[test]
+    fn test_json_query_chunk_size_ignored_for_show() {
+        let (url, requests) = mock_server(Duration::from_millis(0), SHOW_DATABASES_RESPONSE);
+        let client = InfluxDbClient::new(url, "database").with_chunk_size(1);
+        let databases = Runtime::new().unwrap().block_on(client.show_databases());
+        assert_eq!(databases.unwrap(), vec!["_internal", "weather"]);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SHOW+DATABASES "));
+    }
+
+    #[test]
+    fn test_query_chunked() {
+        let (url, requests) = mock_server(Duration::from_millis(0), CHUNKED_RESPONSE);
+        let client = InfluxDbClient::new(url, "database");
+        let query = InfluxDbReadQuery::new("SELECT * FROM weather");
+        let chunks = Runtime::new()
+            .unwrap()
+            .block_on(client.query_chunked(&query).collect())
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+
+        let temperatures = chunks
+            .into_iter()
+            .map(|mut chunk| {
+                chunk.deserialize_next::<Weather>().wait().unwrap().series[0].values[0].temperature
+            })
+            .collect::<Vec<i32>>();
+        assert_eq!(temperatures, vec![82, 84]);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SELECT+*+FROM+weather&chunked=true "));
+    }
+
+    #[test]
+    fn test_json_query_stream() {
+        let chunk = r#"{"results":[{"statement_id":0,"series":[{"name":"weather","columns":["time","temperature"],"values":[["1970-01-01T11:00:00Z",82]]}]}]}"#;
+        let body = format!("{}\n{}\n{}\n", chunk, chunk, chunk);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            Box::leak(response.into_boxed_str()),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let query = InfluxDbReadQuery::new("SELECT * FROM weather");
+        let chunks = Runtime::new()
+            .unwrap()
+            .block_on(
+                client
+                    .json_query_stream(&query)
+                    .and_then(|chunks| chunks.collect()),
+            )
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+    }
+
+    #[test]
+    fn test_json_query_stream_http_error() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 404 Not Found\r\nConnection: close\r\nContent-Length: 18\r\n\r\ndatabase not found",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let query = InfluxDbReadQuery::new("SELECT * FROM weather");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.json_query_stream(&query));
+        match result {
+            Err(InfluxDbError::HttpError { status, .. }) => assert_eq!(status, 404),
+            Err(err) => panic!("Should cause a HttpError: {:?}", err),
+            Ok(_) => panic!("Should cause a HttpError"),
+        }
+    }
+
+    #[test]
+    fn test_parse_chunks_reassembles_lines() {
+        let body = concat!(
+            r#"{"results":[{"statement_id":0}]}"#,
+            "\n",
+            r#"{"results":[{"statement_id":1}]}"#,
+            "\n",
+            r#"{"results":[{"statement_id":2}]}"#
+        );
+        // Split the body inside of the JSON objects
+        let parts = body
+            .as_bytes()
+            .chunks(7)
+            .map(|part| part.to_vec())
+            .collect::<Vec<Vec<u8>>>();
+        let chunks = parse_chunks(stream::iter_ok(parts))
+            .collect()
+            .wait()
+            .unwrap();
+        let statement_ids = chunks
+            .iter()
+            .map(|chunk| chunk.results[0]["statement_id"].as_u64().unwrap())
+            .collect::<Vec<u64>>();
+        assert_eq!(statement_ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_query_chunked_database_error() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 27\r\n\r\n{\"error\":\"query aborted\"}\n",
+        );
+        let client = InfluxDbClient::new(url, "database").with_chunk_size(1);
+        let query = InfluxDbReadQuery::new("SELECT * FROM weather");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.query_chunked(&query).collect());
+        match result {
+            Err(InfluxDbError::DatabaseError { error }) => assert_eq!(error, "query aborted"),
+            _ => panic!("Should cause a DatabaseError: {:?}", result),
+        }
+    }
+
+    /// Responds to `SELECT * FROM <measurement>` with a series named after the measurement, and
+    /// with an error for the measurement `invalid`
+    fn measurement_response(request: &str) -> String {
+        let measurement = request
+            .split(' ')
+            .nth(1)
+            .and_then(|path| path.rsplit("+FROM+").next())
+            .unwrap_or_default();
+        let body = if measurement == "invalid" {
+            r#"{"error":"measurement not found"}"#.to_string()
+        } else {
+            format!(
+                r#"{{"results":[{{"statement_id":0,"series":[{{"name":"{}","columns":["time"],"values":[]}}]}}]}}"#,
+                measurement
+            )
+        };
+        format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    fn series_names(results: Vec<DatabaseQueryResult>) -> Vec<String> {
+        results
+            .into_iter()
+            .map(|result| {
+                result.results[0]["series"][0]["name"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_query_many() {
+        let (url, _) = mock_server_with(Duration::from_millis(0), measurement_response);
+        let client = InfluxDbClient::new(url, "database");
+        let queries = vec![
+            InfluxDbReadQuery::new("SELECT * FROM a"),
+            InfluxDbReadQuery::new("SELECT * FROM b"),
+            InfluxDbReadQuery::new("SELECT * FROM c"),
+        ];
+        let mut rt = Runtime::new().unwrap();
+        let results = rt.block_on(client.query_many(&queries)).unwrap();
+        assert_eq!(series_names(results), vec!["a", "b", "c"]);
+
+        let results = rt
+            .block_on(client.query_many_with_limit(&queries, 2))
+            .unwrap();
+        assert_eq!(series_names(results), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_query_many_error() {
+        let (url, _) = mock_server_with(Duration::from_millis(0), measurement_response);
+        let client = InfluxDbClient::new(url, "database");
+        let queries = vec![
+            InfluxDbReadQuery::new("SELECT * FROM a"),
+            InfluxDbReadQuery::new("SELECT * FROM invalid"),
+        ];
+        let mut rt = Runtime::new().unwrap();
+        for result in vec![
+            rt.block_on(client.query_many(&queries)),
+            rt.block_on(client.query_many_with_limit(&queries, 0)),
+        ] {
+            match result {
+                Err(InfluxDbError::BatchError { index, inner }) => {
+                    assert_eq!(index, 1);
+                    match *inner {
+                        InfluxDbError::DatabaseError { .. } => {}
+                        inner => panic!("Should be a DatabaseError: {:?}", inner),
+                    }
+                }
+                _ => panic!("Should cause a BatchError: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_query_from_json() {
+        let query = InfluxDbWriteQuery::from_json(
+            Timestamp::HOURS(11),
+            "weather",
+            serde_json::json!({
+                "temperature": 22.5,
+                "active": true,
+                "_tag_host": "server1",
+                "_tag_rack": 3,
+                "readings": 7,
+                "note": "sunny",
+                "humidity": null,
+            }),
+        );
+        assert_eq!(
+            query.unwrap().build().unwrap(),
+            "weather,host=server1,rack=3 active=t,note=\"sunny\",readings=7i,temperature=22.5 11"
+        );
+    }
+
+    #[test]
+    fn test_write_query_from_json_invalid() {
+        for value in vec![
+            serde_json::json!({ "location": { "city": "berlin" } }),
+            serde_json::json!({ "_tag_location": ["berlin"] }),
+            serde_json::json!([22.5]),
+        ] {
+            match InfluxDbWriteQuery::from_json(Timestamp::NOW, "weather", value) {
+                Err(InfluxDbError::InvalidQueryError { .. }) => {}
+                result => panic!(
+                    "Should cause an InvalidQueryError: {:?}",
+                    result.map(|query| query.to_line_protocol())
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_show_databases() {
+        let (url, requests) = mock_server(Duration::from_millis(0), SHOW_DATABASES_RESPONSE);
+        let client = InfluxDbClient::new(url, "database");
+        let databases = Runtime::new().unwrap().block_on(client.show_databases());
+        assert_eq!(databases.unwrap(), vec!["_internal", "weather"]);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SHOW+DATABASES "));
+    }
+
+    #[test]
+    fn test_json_query_with_precision() {
+        let (url, requests) = mock_server(Duration::from_millis(0), SHOW_DATABASES_RESPONSE);
+        let client = InfluxDbClient::new(url, "database").with_precision(Precision::Seconds);
+        let query = InfluxDbReadQuery::new("SELECT * FROM weather");
+        let result = Runtime::new().unwrap().block_on(client.json_query(&query));
+        assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SELECT+*+FROM+weather&epoch=s "));
+    }
+
+    #[test]
+    fn test_health() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 121\r\n\r\n{\"name\":\"influxdb\",\"message\":\"ready for queries and writes\",\"status\":\"pass\",\"checks\":[],\"version\":\"2.0.0\",\"commit\":\"abc\"}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let health = Runtime::new().unwrap().block_on(client.health());
+        assert_eq!(
+            health.unwrap(),
+            HealthStatus {
+                name: "influxdb".to_string(),
+                message: "ready for queries and writes".to_string(),
+                status: HealthStatusValue::Pass,
+                version: "2.0.0".to_string(),
+            }
+        );
+        assert!(requests.recv().unwrap().starts_with("GET /health "));
+    }
+
+    #[test]
+    fn test_flux_query_json() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 15\r\n\r\n[{\"_value\":82}]",
+        );
+        let client = InfluxDbClient::new(url, "database")
+            .with_token("token")
+            .with_org("org");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.flux_query_json("buckets()"));
+        assert_eq!(result.unwrap(), serde_json::json!([{ "_value": 82 }]));
+
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(request.starts_with("post /api/v2/query?org=org "));
+        assert!(request.contains("\r\naccept: application/json\r\n"));
+    }
+
+    fn json_response(status: &str, body: &str) -> &'static str {
+        let response = format!(
+            "HTTP/1.1 {}\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        Box::leak(response.into_boxed_str())
+    }
+
+    #[test]
+    fn test_setup() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            json_response(
+                "201 Created",
+                r#"{"user":{"id":"u1","name":"admin"},"org":{"id":"o1","name":"openvelo"},"bucket":{"id":"b1","name":"weather"},"auth":{"id":"a1","token":"secret"}}"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let setup = Runtime::new().unwrap().block_on(client.setup(
+            "admin",
+            "password",
+            "openvelo",
+            "weather",
+            Some(3600),
+            Some("secret"),
+        ));
+        assert_eq!(
+            setup.unwrap(),
+            SetupResponse {
+                user_id: "u1".to_string(),
+                org_id: "o1".to_string(),
+                bucket_id: "b1".to_string(),
+                auth_token: "secret".to_string(),
+            }
+        );
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /api/v2/setup "));
+        assert!(request
+            .to_lowercase()
+            .contains("\r\ncontent-type: application/json\r\n"));
+        let body: serde_json::Value =
+            serde_json::from_str(&request[request.find("\r\n\r\n").unwrap() + 4..]).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "username": "admin",
+                "password": "password",
+                "org": "openvelo",
+                "bucket": "weather",
+                "retentionPeriodSeconds": 3600,
+                "token": "secret",
+            })
+        );
+    }
+
+    #[test]
+    fn test_setup_already_done() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            json_response(
+                "422 Unprocessable Entity",
+                r#"{"code":"conflict","message":"onboarding has already been completed"}"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new()
+            .unwrap()
+            .block_on(client.setup("admin", "password", "openvelo", "weather", None, None));
+        match result {
+            Err(InfluxDbError::HttpError { status: 422, .. }) => {}
+            _ => panic!("Should cause an HttpError: {:?}", result),
+        }
+
+        let request = requests.recv().unwrap();
+        assert!(!request.contains("retentionPeriodSeconds"));
+        assert!(!request.contains("token"));
+    }
+
+    const WEATHER_BUCKET: &str = r#"{"id":"b1","name":"weather","orgID":"o1","retentionRules":[{"type":"expire","everySeconds":3600}]}"#;
+
+    fn bucket_response(request: &str) -> String {
+        let request_line = request.lines().next().unwrap_or_default();
+        let (status, body) = if request_line.starts_with("POST /api/v2/buckets ") {
+            ("201 Created", WEATHER_BUCKET.to_string())
+        } else if request_line.starts_with("GET /api/v2/buckets?name=weather ") {
+            ("200 OK", format!(r#"{{"buckets":[{}]}}"#, WEATHER_BUCKET))
+        } else if request_line.starts_with("GET /api/v2/buckets?name=") {
+            ("404 Not Found", r#"{"code":"not found"}"#.to_string())
+        } else if request_line.starts_with("DELETE /api/v2/buckets/b1 ") {
+            return "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n".to_string();
+        } else {
+            ("500 Internal Server Error", String::new())
+        };
+        json_response(status, &body).to_string()
+    }
+
+    #[test]
+    fn test_bucket_lifecycle() {
+        let (url, requests) = mock_server_with(Duration::from_millis(0), bucket_response);
+        let client = InfluxDbClient::new(url, "database").with_token("token");
+        let mut rt = Runtime::new().unwrap();
+        let weather = BucketInfo {
+            id: "b1".to_string(),
+            name: "weather".to_string(),
+            org_id: "o1".to_string(),
+            retention_rules: vec![RetentionRule::expire(3600)],
+        };
+
+        let created =
+            rt.block_on(client.create_bucket("weather", "o1", vec![RetentionRule::expire(3600)]));
+        assert_eq!(created, Ok(weather.clone()));
+        let request = requests.recv().unwrap();
+        assert!(request
+            .to_lowercase()
+            .contains("\r\nauthorization: token token\r\n"));
+        let body: serde_json::Value =
+            serde_json::from_str(&request[request.find("\r\n\r\n").unwrap() + 4..]).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "name": "weather",
+                "orgID": "o1",
+                "retentionRules": [{"type": "expire", "everySeconds": 3600}],
+            })
+        );
+
+        let found = rt.block_on(client.find_bucket_by_name("weather"));
+        assert_eq!(found, Ok(Some(weather)));
+        assert_eq!(rt.block_on(client.find_bucket_by_name("missing")), Ok(None));
+
+        assert_eq!(rt.block_on(client.delete_bucket("b1")), Ok(()));
+        let requests = requests.try_iter().collect::<Vec<String>>();
+        assert!(requests[2].starts_with("DELETE /api/v2/buckets/b1 "));
+    }
+
+    #[test]
+    fn test_list_buckets_pages() {
+        let (url, requests) = mock_server_with(Duration::from_millis(0), |request: &str| {
+            let count = if request.starts_with("GET /api/v2/buckets?limit=100&offset=0 ") {
+                100
+            } else {
+                1
+            };
+            let buckets = (0..count)
+                .map(|i| format!(r#"{{"id":"{}","name":"bucket","orgID":"o1"}}"#, i))
+                .collect::<Vec<String>>()
+                .join(",");
+            json_response("200 OK", &format!(r#"{{"buckets":[{}]}}"#, buckets)).to_string()
+        });
+        let client = InfluxDbClient::new(url, "database").with_token("token");
+        let buckets = Runtime::new()
+            .unwrap()
+            .block_on(client.list_buckets())
+            .unwrap();
+        assert_eq!(buckets.len(), 101);
+        assert!(buckets[0].retention_rules.is_empty());
+
+        let requests = requests.try_iter().collect::<Vec<String>>();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("GET /api/v2/buckets?limit=100&offset=100 "));
+    }
+
+    #[test]
+    fn test_buckets_require_token() {
+        let client = InfluxDbClient::new("http://localhost:9999", "database");
+        let mut rt = Runtime::new().unwrap();
+        match rt.block_on(client.list_buckets()) {
+            Err(InfluxDbError::InvalidQueryError { .. }) => {}
+            result => panic!("Should cause an InvalidQueryError: {:?}", result),
+        }
+        assert!(rt.block_on(client.delete_bucket("b1")).is_err());
+    }
+
+    #[test]
+    fn test_is_setup_required() {
+        for &allowed in &[true, false] {
+            let (url, requests) = mock_server(
+                Duration::from_millis(0),
+                json_response("200 OK", &format!(r#"{{"allowed":{}}}"#, allowed)),
+            );
+            let client = InfluxDbClient::new(url, "database");
+            let result = Runtime::new().unwrap().block_on(client.is_setup_required());
+            assert_eq!(result, Ok(allowed));
+            assert!(requests.recv().unwrap().starts_with("GET /api/v2/setup "));
+        }
+    }
+
+    #[test]
+    fn test_health_fail() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nContent-Length: 69\r\n\r\n{\"name\":\"influxdb\",\"message\":\"not ready\",\"status\":\"fail\",\"checks\":[]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let result = Runtime::new().unwrap().block_on(client.health());
+        match result {
+            Err(InfluxDbError::DatabaseError { error }) => assert_eq!(error, "not ready"),
+            _ => panic!("Should cause a DatabaseError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_health_falls_back_to_ping() {
+        let (url, requests) = mock_server_with(Duration::from_millis(0), |request| {
+            if request.starts_with("GET /ping ") {
+                "HTTP/1.1 204 No Content\r\nX-Influxdb-Version: 1.7.6\r\nConnection: close\r\n\r\n"
+            } else {
+                "HTTP/1.1 404 Not Found\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+            }
+        });
+        let client = InfluxDbClient::new(url, "database");
+        let health = Runtime::new().unwrap().block_on(client.health()).unwrap();
+        assert_eq!(health.status, HealthStatusValue::Pass);
+        assert_eq!(health.version, "1.7.6");
+        assert!(requests.recv().unwrap().starts_with("GET /health "));
+        assert!(requests.recv().unwrap().starts_with("GET /ping "));
+    }
+
+    #[test]
+    fn test_json_query_with_epoch() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            series_response(r#"["time","temperature"]"#, r#"[[1565956800000000000,82]]"#),
+        );
+        let client = InfluxDbClient::new(url, "database").with_precision(Precision::Seconds);
+        let query =
+            InfluxDbReadQuery::new("SELECT * FROM weather").with_epoch(Precision::Nanoseconds);
+        let result = Runtime::new().unwrap().block_on(
+            client
+                .json_query(&query)
+                .and_then(|mut db_result| db_result.deserialize_next::<(u64, i32)>()),
+        );
+        assert_eq!(
+            result.unwrap().series[0].values,
+            vec![(1_565_956_800_000_000_000, 82)]
+        );
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SELECT+*+FROM+weather&epoch=ns "));
+    }
+
+    #[test]
+    fn test_database_exists() {
+        let (url, _) = mock_server(Duration::from_millis(0), SHOW_DATABASES_RESPONSE);
+        let client = InfluxDbClient::new(url, "database");
+        let mut rt = Runtime::new().unwrap();
+        assert!(rt.block_on(client.database_exists("weather")).unwrap());
+        assert!(!rt.block_on(client.database_exists("traffic")).unwrap());
+    }
+
+    /// Returns a HTTP response containing a single series with the given columns and values
+    fn series_response(columns: &str, values: &str) -> &'static str {
+        let body = format!(
+            r#"{{"results":[{{"statement_id":0,"series":[{{"name":"weather","columns":{},"values":{}}}]}}]}}"#,
+            columns, values
+        );
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        Box::leak(response.into_boxed_str())
+    }
+
+    #[test]
+    fn test_show_measurements() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            series_response(r#"["name"]"#, r#"[["temperature"],["weather"]]"#),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let measurements = Runtime::new().unwrap().block_on(client.show_measurements());
+        assert_eq!(measurements.unwrap(), vec!["temperature", "weather"]);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SHOW+MEASUREMENTS "));
+    }
+
+    #[test]
+    fn test_show_measurements_empty() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let measurements = Runtime::new().unwrap().block_on(client.show_measurements());
+        assert!(measurements.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_show_tag_keys() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            series_response(r#"["tagKey"]"#, r#"[["location"],["season"]]"#),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let keys = Runtime::new()
+            .unwrap()
+            .block_on(client.show_tag_keys("weather"));
+        assert_eq!(keys.unwrap(), vec!["location", "season"]);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SHOW+TAG+KEYS+FROM+%22weather%22 "));
+    }
+
+    #[test]
+    fn test_show_tag_values() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            series_response(
+                r#"["key","value"]"#,
+                r#"[["location","berlin"],["location","london"]]"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let values = Runtime::new()
+            .unwrap()
+            .block_on(client.show_tag_values("weather", "location"));
+        assert_eq!(values.unwrap(), vec!["berlin", "london"]);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with(
+            "GET /query?db=database&q=SHOW+TAG+VALUES+FROM+%22weather%22+WITH+KEY+%3D+%22location%22 "
+        ));
+    }
+
+    #[test]
+    fn test_show_series_cardinality() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            series_response(r#"["count"]"#, r#"[[42]]"#),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let cardinality = Runtime::new()
+            .unwrap()
+            .block_on(client.show_series_cardinality("weather"));
+        assert_eq!(cardinality, Ok(42));
+
+        let request = requests.recv().unwrap();
+        assert!(request
+            .starts_with("GET /query?db=database&q=SHOW+SERIES+CARDINALITY+FROM+%22weather%22 "));
+    }
+
+    #[test]
+    fn test_show_measurement_cardinality() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            series_response(r#"["count"]"#, r#"[[3]]"#),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let cardinality = Runtime::new()
+            .unwrap()
+            .block_on(client.show_measurement_cardinality());
+        assert_eq!(cardinality, Ok(3));
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SHOW+MEASUREMENT+CARDINALITY "));
+    }
+
+    #[test]
+    fn test_show_cardinality_empty() {
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 32\r\n\r\n{\"results\":[{\"statement_id\":0}]}",
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let cardinality = Runtime::new()
+            .unwrap()
+            .block_on(client.show_series_cardinality("weather"));
+        assert_eq!(cardinality, Ok(0));
+    }
+
+    #[test]
+    fn test_show_continuous_queries() {
+        let body = r#"{"results":[{"statement_id":0,"series":[{"name":"_internal","columns":["name","query"]},{"name":"weather","columns":["name","query"],"values":[["hourly","CREATE CONTINUOUS QUERY hourly ON weather BEGIN SELECT mean(temperature) INTO weather.autogen.hourly FROM weather.autogen.weather GROUP BY time(1h) END"]]}]}]}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            Box::leak(response.into_boxed_str()),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let queries = Runtime::new()
+            .unwrap()
+            .block_on(client.show_continuous_queries());
+        assert_eq!(
+            queries.unwrap(),
+            vec![ContinuousQuery {
+                name: "hourly".to_string(),
+                database: "weather".to_string(),
+                query: "CREATE CONTINUOUS QUERY hourly ON weather BEGIN SELECT mean(temperature) INTO weather.autogen.hourly FROM weather.autogen.weather GROUP BY time(1h) END".to_string(),
+            }]
+        );
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("GET /query?db=database&q=SHOW+CONTINUOUS+QUERIES "));
+    }
+
+    #[test]
+    fn test_show_retention_policies() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            series_response(
+                r#"["name","duration","shardGroupDuration","replicaN","default"]"#,
+                r#"[["autogen","0s","168h0m0s",1,true],["one_month","720h0m0s","24h0m0s",1,false]]"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let policies = Runtime::new()
+            .unwrap()
+            .block_on(client.show_retention_policies());
+        assert_eq!(
+            policies.unwrap(),
+            vec![
+                RetentionPolicy {
+                    name: "autogen".to_string(),
+                    duration: "0s".to_string(),
+                    shard_group_duration: "168h0m0s".to_string(),
+                    replication: 1,
+                    is_default: true,
+                },
+                RetentionPolicy {
+                    name: "one_month".to_string(),
+                    duration: "720h0m0s".to_string(),
+                    shard_group_duration: "24h0m0s".to_string(),
+                    replication: 1,
+                    is_default: false,
+                },
+            ]
+        );
+
+        let request = requests.recv().unwrap();
+        assert!(request
+            .starts_with("GET /query?db=database&q=SHOW+RETENTION+POLICIES+ON+%22database%22 "));
+    }
+
+    #[test]
+    fn test_show_subscriptions() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            json_response(
+                "200 OK",
+                r#"{"results":[{"statement_id":0,"series":[{"name":"_internal","columns":["retention_policy","name","mode","destinations"]},{"name":"weather","columns":["retention_policy","name","mode","destinations"],"values":[["autogen","kapacitor","ANY",["udp://h1:9090","udp://h2:9090"]]]}]}]}"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let subscriptions = Runtime::new()
+            .unwrap()
+            .block_on(client.show_subscriptions());
+        assert_eq!(
+            subscriptions.unwrap(),
+            vec![SubscriptionInfo {
+                name: "kapacitor".to_string(),
+                database: "weather".to_string(),
+                retention_policy: "autogen".to_string(),
+                mode: "ANY".to_string(),
+                destinations: vec!["udp://h1:9090".to_string(), "udp://h2:9090".to_string()],
+            }]
+        );
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("GET /query?db=database&q=SHOW+SUBSCRIPTIONS "));
+    }
+
+    #[test]
+    fn test_show_shard_groups() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            json_response(
+                "200 OK",
+                r#"{"results":[{"statement_id":0,"series":[{"name":"shard groups","columns":["id","database","retention_policy","start_time","end_time","expiry_time"],"values":[[1,"_internal","monitor","2019-08-16T00:00:00Z","2019-08-17T00:00:00Z","2019-08-24T00:00:00Z"],[2,"weather","autogen","2019-08-12T00:00:00Z","2019-08-19T00:00:00Z","2019-08-19T00:00:00Z"]]}]}]}"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let shard_groups = Runtime::new().unwrap().block_on(client.show_shard_groups());
+        assert_eq!(
+            shard_groups.unwrap(),
+            vec![
+                ShardGroupInfo {
+                    id: 1,
+                    database: "_internal".to_string(),
+                    retention_policy: "monitor".to_string(),
+                    start_time: "2019-08-16T00:00:00Z".to_string(),
+                    end_time: "2019-08-17T00:00:00Z".to_string(),
+                    expiry_time: "2019-08-24T00:00:00Z".to_string(),
+                },
+                ShardGroupInfo {
+                    id: 2,
+                    database: "weather".to_string(),
+                    retention_policy: "autogen".to_string(),
+                    start_time: "2019-08-12T00:00:00Z".to_string(),
+                    end_time: "2019-08-19T00:00:00Z".to_string(),
+                    expiry_time: "2019-08-19T00:00:00Z".to_string(),
+                },
+            ]
+        );
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("GET /query?db=database&q=SHOW+SHARD+GROUPS "));
+    }
+
+    fn copy_response(request: &str) -> String {
+        let request_line = request.lines().next().unwrap_or_default();
+        if request_line.contains("MEASUREMENT+%3D+%22weather%22 ") {
+            series_response(r#"["name"]"#, r#"[["weather"]]"#).to_string()
+        } else if request_line.starts_with("POST /query?") {
+            series_response(r#"["time","written"]"#, "[[0,2]]").to_string()
+        } else {
+            json_response("200 OK", r#"{"results":[{"statement_id":0}]}"#).to_string()
+        }
+    }
+
+    #[test]
+    fn test_copy_measurement() {
+        let (url, requests) = mock_server_with(Duration::from_millis(0), copy_response);
+        let client = InfluxDbClient::new(url, "database");
+        let mut rt = Runtime::new().unwrap();
+
+        let copied = rt.block_on(client.copy_measurement("weather", "weather_copy", None));
+        assert_eq!(copied, Ok(()));
+        assert!(requests.recv().unwrap().starts_with(
+            "GET /query?db=database&q=SHOW+MEASUREMENTS+WITH+MEASUREMENT+%3D+%22weather%22 "
+        ));
+        assert!(requests.recv().unwrap().starts_with(
+            "POST /query?db=database&q=SELECT+*+INTO+%22weather_copy%22+FROM+%22weather%22+GROUP+BY+* "
+        ));
+
+        let copied = rt.block_on(client.copy_measurement(
+            "weather",
+            "weather_2020",
+            Some(("2020-01-01T00:00:00Z", "2020-12-31T23:59:59Z")),
+        ));
+        assert_eq!(copied, Ok(()));
+        requests.recv().unwrap();
+        assert!(requests.recv().unwrap().starts_with(
+            "POST /query?db=database&q=SELECT+*+INTO+%22weather_2020%22+FROM+%22weather%22+WHERE+time+%3E%3D+%272020-01-01T00%3A00%3A00Z%27+AND+time+%3C%3D+%272020-12-31T23%3A59%3A59Z%27+GROUP+BY+* "
+        ));
+    }
+
+    #[test]
+    fn test_copy_measurement_missing_source() {
+        let (url, requests) = mock_server_with(Duration::from_millis(0), copy_response);
+        let client = InfluxDbClient::new(url, "database");
+        let copied = Runtime::new().unwrap().block_on(client.copy_measurement(
+            "humidity",
+            "humidity_copy",
+            None,
+        ));
+        assert_eq!(
+            copied,
+            Err(InfluxDbError::DatabaseError {
+                error: "measurement \"humidity\" does not exist".to_string()
+            })
+        );
+        // Nothing is copied
+        requests.recv().unwrap();
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_show_shards() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            json_response(
+                "200 OK",
+                r#"{"results":[{"statement_id":0,"series":[{"name":"_internal","columns":["id","database","retention_policy","shard_group","start_time","end_time","expiry_time","owners"],"values":[[1,"_internal","monitor",1,"2019-08-16T00:00:00Z","2019-08-17T00:00:00Z","2019-08-24T00:00:00Z",""]]},{"name":"weather","columns":["id","database","retention_policy","shard_group","start_time","end_time","expiry_time","owners"],"values":[[3,"weather","autogen",2,"2019-08-12T00:00:00Z","2019-08-19T00:00:00Z","2019-08-19T00:00:00Z","4,5"]]}]}]}"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let shards = Runtime::new().unwrap().block_on(client.show_shards());
+        assert_eq!(
+            shards.unwrap(),
+            vec![
+                ShardInfo {
+                    id: 1,
+                    database: "_internal".to_string(),
+                    retention_policy: "monitor".to_string(),
+                    shard_group: 1,
+                    start_time: "2019-08-16T00:00:00Z".to_string(),
+                    end_time: "2019-08-17T00:00:00Z".to_string(),
+                    expiry_time: "2019-08-24T00:00:00Z".to_string(),
+                    owners: Vec::new(),
+                },
+                ShardInfo {
+                    id: 3,
+                    database: "weather".to_string(),
+                    retention_policy: "autogen".to_string(),
+                    shard_group: 2,
+                    start_time: "2019-08-12T00:00:00Z".to_string(),
+                    end_time: "2019-08-19T00:00:00Z".to_string(),
+                    expiry_time: "2019-08-19T00:00:00Z".to_string(),
+                    owners: vec![4, 5],
+                },
+            ]
+        );
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("GET /query?db=database&q=SHOW+SHARDS "));
+
+        let (url, _) = mock_server(
+            Duration::from_millis(0),
+            series_response(
+                r#"["id","database","retention_policy","shard_group","start_time","end_time","expiry_time","owners"]"#,
+                r#"[[1,"weather","autogen",1,"","","","node1"]]"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        match Runtime::new().unwrap().block_on(client.show_shards()) {
+            Err(InfluxDbError::DeserializationError { .. }) => {}
+            result => panic!("Should cause a DeserializationError: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_show_users() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            series_response(
+                r#"["user","admin"]"#,
+                r#"[["admin",true],["grafana",false]]"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let users = Runtime::new().unwrap().block_on(client.show_users());
+        assert_eq!(
+            users.unwrap(),
+            vec![
+                UserInfo {
+                    username: "admin".to_string(),
+                    admin: true,
+                },
+                UserInfo {
+                    username: "grafana".to_string(),
+                    admin: false,
+                },
+            ]
+        );
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SHOW+USERS "));
+    }
+
+    #[test]
+    fn test_show_field_keys() {
+        let (url, requests) = mock_server(
+            Duration::from_millis(0),
+            series_response(
+                r#"["fieldKey","fieldType"]"#,
+                r#"[["humidity","integer"],["temperature","float"]]"#,
+            ),
+        );
+        let client = InfluxDbClient::new(url, "database");
+        let keys = Runtime::new()
+            .unwrap()
+            .block_on(client.show_field_keys("weather"));
+        assert_eq!(
+            keys.unwrap(),
+            vec![
+                FieldKeyInfo {
+                    name: "humidity".to_string(),
+                    field_type: "integer".to_string(),
+                },
+                FieldKeyInfo {
+                    name: "temperature".to_string(),
+                    field_type: "float".to_string(),
+                },
+            ]
+        );
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /query?db=database&q=SHOW+FIELD+KEYS+FROM+%22weather%22 "));
+    }
 }
@@ -10,11 +10,16 @@
 //!
 //!  * Reading and Writing to InfluxDB
 //!  * Optional Serde Support for Deserialization
+//!  * Optional Chrono Support for Timestamps
+//!  * Query Builder for `SELECT` queries
+//!  * Optional `#[derive(InfluxDbWritable)]` for writing structs
+//!  * Optional gzip compression of writes
+//!  * Optional deserialization of CSV responses
+//!  * Optional structured logging of queries and OpenTelemetry trace context propagation
 //!
 //! # Planned Features
 //!
 //!  * Running multiple queries in one request (e.g. `SELECT * FROM weather_berlin; SELECT * FROM weather_london`)
-//!  * Read Query Builder instead of supplying raw queries
 //!  * Authentication against InfluxDB
 //!  * Methods for setting time and time precision in a query
 //!
@@ -70,15 +75,23 @@
 //! For further examples, check out the Integration Tests in `tests/integration_tests.rs`
 //! in the repository.
 
-#[macro_use]
-extern crate failure;
-
 pub mod client;
 pub mod error;
 pub mod query;
 
 #[cfg(feature = "use-serde")]
+#[doc(hidden)]
+pub use serde;
+#[cfg(feature = "use-serde")]
+#[doc(hidden)]
+pub use serde_json;
+
+#[cfg(any(feature = "use-serde", feature = "chrono"))]
 pub mod integrations {
+    #[cfg(feature = "chrono")]
+    pub mod chrono_integration;
+    #[cfg(all(feature = "csv", feature = "use-serde"))]
+    pub mod csv_integration;
     #[cfg(feature = "use-serde")]
     pub mod serde_integration;
 }
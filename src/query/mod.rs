@@ -1,4 +1,5 @@
-//! Used to create queries of type [`InfluxDbReadQuery`](crate::query::read_query::InfluxDbReadQuery) or
+//! Used to create queries of type [`InfluxDbReadQuery`](crate::query::read_query::InfluxDbReadQuery),
+//! [`InfluxDbSelectBuilder`](crate::query::select_query::InfluxDbSelectBuilder) or
 //! [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery) which can be executed in InfluxDB
 //!
 //! # Examples
@@ -20,23 +21,114 @@
 //! ```
 
 pub mod read_query;
+pub mod select_query;
+pub mod write_batch;
 pub mod write_query;
 
+use std::cmp::Ordering;
 use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::error::InfluxDbError;
 use crate::query::read_query::InfluxDbReadQuery;
+use crate::query::select_query::InfluxDbSelectBuilder;
 use crate::query::write_query::InfluxDbWriteQuery;
 
-#[derive(PartialEq)]
+/// Time of a point
+///
+/// Timestamps with a value are ordered by the time they represent, regardless of their unit.
+/// [`Timestamp::NOW`](crate::query::Timestamp::NOW) is assigned by InfluxDB when the point
+/// arrives, so it cannot be compared to other timestamps.
+///
+/// # Examples
+///
+/// ```rust
+/// use influxdb::query::Timestamp;
+///
+/// assert!(Timestamp::SECONDS(1) < Timestamp::MILLISECONDS(1_500));
+/// assert_eq!(Timestamp::NOW.partial_cmp(&Timestamp::SECONDS(1)), None);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Timestamp {
     NOW,
-    NANOSECONDS(usize),
-    MICROSECONDS(usize),
-    MILLISECONDS(usize),
-    SECONDS(usize),
-    MINUTES(usize),
-    HOURS(usize),
+    NANOSECONDS(i64),
+    MICROSECONDS(i64),
+    MILLISECONDS(i64),
+    SECONDS(i64),
+    MINUTES(i64),
+    HOURS(i64),
+}
+
+impl Timestamp {
+    /// Returns the current time as [`Timestamp::NANOSECONDS`](crate::query::Timestamp::NANOSECONDS)
+    /// since the Unix epoch.
+    ///
+    /// Unlike [`Timestamp::NOW`](crate::query::Timestamp::NOW), which lets InfluxDB assign the time
+    /// on arrival, this is the time the point was created at.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// InfluxDbQuery::write_query(Timestamp::now(), "measurement").add_field("field1", 5);
+    /// ```
+    pub fn now() -> Timestamp {
+        // `i64` nanoseconds cover the years 1677 to 2262
+        let nanos = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_nanos() as i64,
+            Err(err) => -(err.duration().as_nanos() as i64),
+        };
+        Timestamp::NANOSECONDS(nanos)
+    }
+
+    /// Returns the number of nanoseconds since the Unix epoch, negative for times before the
+    /// epoch, or `None` for [`Timestamp::NOW`](crate::query::Timestamp::NOW)
+    ///
+    /// The result is an `i128`, as coarse units can represent times beyond the range of `i64`
+    /// nanoseconds.
+    pub fn nanoseconds(&self) -> Option<i128> {
+        use Timestamp::*;
+        let (ts, nanos_per_unit) = match self {
+            NOW => return None,
+            NANOSECONDS(ts) => (ts, 1),
+            MICROSECONDS(ts) => (ts, 1_000),
+            MILLISECONDS(ts) => (ts, 1_000_000),
+            SECONDS(ts) => (ts, 1_000_000_000),
+            MINUTES(ts) => (ts, 60_000_000_000),
+            HOURS(ts) => (ts, 3_600_000_000_000),
+        };
+        Some(i128::from(*ts) * nanos_per_unit)
+    }
+
+    /// Position of the unit from the finest to the coarsest, which orders timestamps
+    /// representing the same time consistently with `==`
+    fn unit_rank(&self) -> u8 {
+        use Timestamp::*;
+        match self {
+            NOW => 0,
+            NANOSECONDS(_) => 1,
+            MICROSECONDS(_) => 2,
+            MILLISECONDS(_) => 3,
+            SECONDS(_) => 4,
+            MINUTES(_) => 5,
+            HOURS(_) => 6,
+        }
+    }
+}
+
+impl PartialOrd for Timestamp {
+    fn partial_cmp(&self, other: &Timestamp) -> Option<Ordering> {
+        match (self.nanoseconds(), other.nanoseconds()) {
+            (Some(nanos), Some(other_nanos)) => Some(
+                nanos
+                    .cmp(&other_nanos)
+                    .then(self.unit_rank().cmp(&other.unit_rank())),
+            ),
+            (None, None) => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Timestamp {
@@ -50,6 +142,40 @@ impl fmt::Display for Timestamp {
     }
 }
 
+/// Format of the timestamps InfluxDB returns for read queries
+///
+/// Set with [`InfluxDbClient::with_precision`](crate::client::InfluxDbClient::with_precision).
+/// Unlike the precision of a write query, which follows its [`Timestamp`](crate::query::Timestamp),
+/// this only affects the responses of read queries.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Precision {
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+    Minutes,
+    Hours,
+    /// RFC3339 formatted timestamps like `2019-08-16T11:00:00Z`, which InfluxDB returns by default
+    #[default]
+    Rfc3339,
+}
+
+impl Precision {
+    /// Returns the value of the `epoch` query parameter, or `None` for RFC3339 timestamps
+    pub fn epoch(&self) -> Option<&'static str> {
+        use Precision::*;
+        match self {
+            Nanoseconds => Some("ns"),
+            Microseconds => Some("u"),
+            Milliseconds => Some("ms"),
+            Seconds => Some("s"),
+            Minutes => Some("m"),
+            Hours => Some("h"),
+            Rfc3339 => None,
+        }
+    }
+}
+
 pub trait InfluxDbQuery {
     /// Builds valid InfluxSQL which can be run against the Database.
     /// In case no fields have been specified, it will return an error,
@@ -103,21 +229,57 @@ impl InfluxDbQuery {
     {
         InfluxDbReadQuery::new(read_query)
     }
+
+    /// Returns a [`InfluxDbSelectBuilder`](crate::query::select_query::InfluxDbSelectBuilder) to build a `SELECT` query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// InfluxDbQuery::select_query().all_fields().from("weather"); // Is of type [`InfluxDbSelectBuilder`](crate::query::select_query::InfluxDbSelectBuilder)
+    /// ```
+    pub fn select_query() -> InfluxDbSelectBuilder {
+        InfluxDbSelectBuilder::new()
+    }
 }
 
 #[derive(Debug)]
 #[doc(hidden)]
 pub struct ValidQuery(String);
 impl ValidQuery {
+    #[deprecated(note = "use `into_string` instead")]
     pub fn get(self) -> String {
+        self.into_string()
+    }
+
+    /// Returns the query, consuming it
+    pub fn into_string(self) -> String {
         self.0
     }
+
+    /// Returns the query without consuming it
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+impl AsRef<str> for ValidQuery {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+impl fmt::Display for ValidQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+impl From<String> for ValidQuery {
+    fn from(string: String) -> Self {
+        Self(string)
+    }
 }
-impl<T> From<T> for ValidQuery
-where
-    T: ToString,
-{
-    fn from(string: T) -> Self {
+impl From<&str> for ValidQuery {
+    fn from(string: &str) -> Self {
         Self(string.to_string())
     }
 }
@@ -132,8 +294,17 @@ impl PartialEq<&str> for ValidQuery {
     }
 }
 
-/// Internal Enum used to decide if a `POST` or `GET` request should be sent to InfluxDB. See [InfluxDB Docs](https://docs.influxdata.com/influxdb/v1.7/tools/api/#query-http-endpoint).
-#[derive(PartialEq, Debug)]
+/// Quotes an identifier such as a measurement or database name with double quotes
+pub(crate) fn quote_identifier(identifier: &str) -> String {
+    format!(
+        "\"{}\"",
+        identifier.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+/// Type of a query as returned by [`InfluxDbQuery::get_type`](crate::query::InfluxDbQuery::get_type),
+/// which decides the endpoint and whether a `POST` or `GET` request should be sent to InfluxDB. See [InfluxDB Docs](https://docs.influxdata.com/influxdb/v1.7/tools/api/#query-http-endpoint).
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum QueryType {
     ReadQuery,
     WriteQuery,
@@ -141,7 +312,8 @@ pub enum QueryType {
 
 #[cfg(test)]
 mod tests {
-    use crate::query::{Timestamp, ValidQuery};
+    use crate::query::{InfluxDbQuery, Precision, Timestamp, ValidQuery};
+    use std::cmp::Ordering;
 
     #[test]
     fn test_equality_str() {
@@ -156,6 +328,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_borrowed_valid_query() {
+        let query = ValidQuery::from("SELECT * FROM weather");
+        let borrowed = &query;
+        assert_eq!(format!("{}", borrowed), "SELECT * FROM weather");
+        assert_eq!(borrowed.as_str(), "SELECT * FROM weather");
+        assert_eq!(borrowed.as_ref(), "SELECT * FROM weather");
+        assert_eq!(*borrowed, "SELECT * FROM weather");
+        assert_eq!(query.into_string(), "SELECT * FROM weather");
+    }
+
     #[test]
     fn test_format_for_timestamp_now() {
         assert!(format!("{}", Timestamp::NOW) == String::from(""));
@@ -165,4 +348,105 @@ mod tests {
     fn test_format_for_timestamp_else() {
         assert!(format!("{}", Timestamp::NANOSECONDS(100)) == String::from("100"));
     }
+
+    #[test]
+    fn test_format_for_timestamp_epoch_nanoseconds() {
+        // Exceeds 32 bits, so this must not be truncated on 32-bit targets
+        assert_eq!(
+            format!("{}", Timestamp::NANOSECONDS(1_700_000_000_000_000_000)),
+            "1700000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_timestamp_now() {
+        match Timestamp::now() {
+            // 2019-08-16T00:00:00Z
+            Timestamp::NANOSECONDS(nanos) => assert!(nanos > 1_565_913_600_000_000_000),
+            _ => panic!("Timestamp::now() should return nanoseconds"),
+        }
+    }
+
+    #[test]
+    fn test_timestamp_same_unit_comparison() {
+        assert!(Timestamp::SECONDS(1) < Timestamp::SECONDS(2));
+        assert!(Timestamp::HOURS(3) > Timestamp::HOURS(2));
+        assert!(Timestamp::MILLISECONDS(5) <= Timestamp::MILLISECONDS(5));
+    }
+
+    #[test]
+    fn test_timestamp_cross_unit_comparison() {
+        assert!(Timestamp::SECONDS(1) < Timestamp::MILLISECONDS(1_001));
+        assert!(Timestamp::MINUTES(1) > Timestamp::SECONDS(59));
+        assert!(Timestamp::HOURS(1) < Timestamp::NANOSECONDS(3_600_000_000_001));
+        // Same time, but not equal as the units differ
+        assert_eq!(
+            Timestamp::NANOSECONDS(1_000).partial_cmp(&Timestamp::MICROSECONDS(1)),
+            Some(Ordering::Less)
+        );
+    }
+
+    #[test]
+    fn test_timestamp_now_is_unordered() {
+        assert_eq!(Timestamp::NOW.partial_cmp(&Timestamp::SECONDS(1)), None);
+        assert_eq!(Timestamp::SECONDS(1).partial_cmp(&Timestamp::NOW), None);
+        assert_eq!(
+            Timestamp::NOW.partial_cmp(&Timestamp::NOW),
+            Some(Ordering::Equal)
+        );
+    }
+
+    #[test]
+    fn test_sort_timestamps() {
+        let mut timestamps = vec![
+            Timestamp::MINUTES(1),
+            Timestamp::NANOSECONDS(5),
+            Timestamp::SECONDS(30),
+            Timestamp::MILLISECONDS(70_000),
+        ];
+        timestamps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            timestamps,
+            vec![
+                Timestamp::NANOSECONDS(5),
+                Timestamp::SECONDS(30),
+                Timestamp::MINUTES(1),
+                Timestamp::MILLISECONDS(70_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timestamp_nanoseconds() {
+        assert_eq!(Timestamp::NOW.nanoseconds(), None);
+        assert_eq!(Timestamp::MILLISECONDS(2).nanoseconds(), Some(2_000_000));
+        assert_eq!(
+            Timestamp::HOURS(i64::MAX).nanoseconds(),
+            Some(i128::from(i64::MAX) * 3_600_000_000_000)
+        );
+        assert_eq!(
+            Timestamp::SECONDS(-86_400).nanoseconds(),
+            Some(-86_400_000_000_000)
+        );
+    }
+
+    #[test]
+    fn test_timestamp_before_epoch() {
+        let query = InfluxDbQuery::write_query(Timestamp::SECONDS(-86_400), "weather")
+            .add_field("temperature", 82)
+            .build()
+            .unwrap();
+        assert_eq!(query.as_str(), "weather temperature=82i -86400");
+        assert!(Timestamp::NANOSECONDS(-1) < Timestamp::NANOSECONDS(0));
+        assert!(Timestamp::MINUTES(-1) < Timestamp::SECONDS(-59));
+    }
+
+    #[test]
+    fn test_precision_epoch() {
+        assert_eq!(Precision::default(), Precision::Rfc3339);
+        assert_eq!(Precision::Rfc3339.epoch(), None);
+        assert_eq!(Precision::Nanoseconds.epoch(), Some("ns"));
+        assert_eq!(Precision::Microseconds.epoch(), Some("u"));
+        assert_eq!(Precision::Hours.epoch(), Some("h"));
+    }
 }
@@ -2,11 +2,72 @@
 //!
 //! Can only be instantiated by using InfluxDbQuery::raw_read_query
 
+use std::fmt;
+
 use crate::error::InfluxDbError;
-use crate::query::{InfluxDbQuery, QueryType, ValidQuery};
+use crate::query::select_query::FillOption;
+use crate::query::{quote_identifier, InfluxDbQuery, Precision, QueryType, Timestamp, ValidQuery};
 
 pub struct InfluxDbReadQuery {
     queries: Vec<String>,
+    epoch: Option<Precision>,
+    params: Vec<(String, InfluxDbValue)>,
+    group_by_time: Option<(String, FillOption)>,
+    retention_policy: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+/// Value bound to a placeholder of a [`InfluxDbReadQuery`](crate::query::read_query::InfluxDbReadQuery)
+///
+/// Serializes to an InfluxQL literal: strings in single quotes with quotes and backslashes
+/// escaped, numbers and booleans as is and timestamps as nanoseconds since the Unix epoch.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InfluxDbValue {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Timestamp(Timestamp),
+}
+
+impl fmt::Display for InfluxDbValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use InfluxDbValue::*;
+
+        match self {
+            Str(text) => write!(f, "'{}'", text.replace('\\', "\\\\").replace('\'', "\\'")),
+            Int(x) => write!(f, "{}", x),
+            Float(x) => write!(f, "{}", x),
+            Bool(x) => write!(f, "{}", x),
+            Timestamp(timestamp) => match timestamp.nanoseconds() {
+                Some(nanoseconds) => write!(f, "{}ns", nanoseconds),
+                None => write!(f, "now()"),
+            },
+        }
+    }
+}
+
+macro_rules! value_from_impl {
+        ( $variant:ident => $( $typ:ident ),+ ) => (
+                $(
+                    impl From<$typ> for InfluxDbValue {
+                        fn from(b: $typ) -> Self {
+                            InfluxDbValue::$variant(b.into())
+                        }
+                    }
+                )+
+        )
+}
+value_from_impl! {Bool => bool}
+value_from_impl! {Float => f32, f64}
+value_from_impl! {Int => i8, i16, i32, i64, u8, u16, u32}
+value_from_impl! {Str => String}
+value_from_impl! {Timestamp => Timestamp}
+impl From<&str> for InfluxDbValue {
+    fn from(b: &str) -> Self {
+        InfluxDbValue::Str(b.into())
+    }
 }
 
 impl InfluxDbReadQuery {
@@ -17,22 +78,592 @@ impl InfluxDbReadQuery {
     {
         InfluxDbReadQuery {
             queries: vec![query.to_string()],
+            epoch: None,
+            params: Vec::new(),
+            group_by_time: None,
+            retention_policy: None,
+            limit: None,
+            offset: None,
+        }
+    }
+
+    /// Binds `value` to the placeholder `$key`
+    ///
+    /// Placeholders are replaced with the escaped value when the query is built, so values from
+    /// user input cannot change the meaning of the query. Placeholders inside quoted strings and
+    /// identifiers as well as placeholders without a bound value are left untouched. Binding a key
+    /// again replaces its value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let query = InfluxDbQuery::raw_read_query(
+    ///     "SELECT * FROM weather WHERE location = $location AND temperature > $temperature",
+    /// )
+    /// .bind("location", "berlin")
+    /// .bind("temperature", 20)
+    /// .build();
+    ///
+    /// assert_eq!(
+    ///     query.unwrap(),
+    ///     "SELECT * FROM weather WHERE location = 'berlin' AND temperature > 20"
+    /// );
+    /// ```
+    pub fn bind<S, V>(mut self, key: S, value: V) -> Self
+    where
+        S: ToString,
+        V: Into<InfluxDbValue>,
+    {
+        let key = key.to_string();
+        let key = key.trim_start_matches('$').to_string();
+        let value = value.into();
+        match self.params.iter_mut().find(|(bound, _)| *bound == key) {
+            Some(param) => param.1 = value,
+            None => self.params.push((key, value)),
         }
+        self
+    }
+
+    /// Groups the results of the last statement into intervals of `interval`, e.g. `1m`, and fills
+    /// intervals without data according to `fill`
+    ///
+    /// `GROUP BY time(<interval>) fill(<fill>)` is inserted when the query is built, before an
+    /// `ORDER BY`, `LIMIT`, `OFFSET`, `SLIMIT`, `SOFFSET` or `tz()` clause. If the statement already
+    /// groups by tags, the time interval is added to its `GROUP BY` clause. For new queries,
+    /// [`InfluxDbSelectBuilder`](crate::query::select_query::InfluxDbSelectBuilder) offers the
+    /// same without parsing the query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::select_query::FillOption;
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let query = InfluxDbQuery::raw_read_query("SELECT mean(temperature) FROM weather LIMIT 10")
+    ///     .with_group_by_time("1m", FillOption::None)
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     query.unwrap(),
+    ///     "SELECT mean(temperature) FROM weather GROUP BY time(1m) fill(none) LIMIT 10"
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `interval` is not a duration literal such as `10s` or `1h30m`, building the query fails
+    /// with a [`InfluxDbError::InvalidQueryError`].
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn with_group_by_time<S>(mut self, interval: S, fill: FillOption) -> Self
+    where
+        S: Into<String>,
+    {
+        self.group_by_time = Some((interval.into(), fill));
+        self
+    }
+
+    /// Returns at most `limit` points per series from the last statement
+    ///
+    /// `LIMIT <limit>` is added when the query is built, after an `ORDER BY` clause and before an
+    /// `OFFSET`, `SLIMIT`, `SOFFSET` or `tz()` clause. If the statement already has a `LIMIT`
+    /// clause, its value is replaced, so calling this again changes the limit.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather ORDER BY time DESC")
+    ///     .with_limit(10)
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     query.unwrap(),
+    ///     "SELECT * FROM weather ORDER BY time DESC LIMIT 10"
+    /// );
+    /// ```
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skips the first `offset` points per series of the last statement
+    ///
+    /// `OFFSET <offset>` is added when the query is built, after a `LIMIT` clause and before an
+    /// `SLIMIT`, `SOFFSET` or `tz()` clause. If the statement already has an `OFFSET` clause, its
+    /// value is replaced.
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Returns the page `page`, counted from 0, of the points per series of the last statement,
+    /// with `page_size` points per page
+    ///
+    /// Shorthand for [`with_limit`](crate::query::read_query::InfluxDbReadQuery::with_limit)
+    /// and [`with_offset`](crate::query::read_query::InfluxDbReadQuery::with_offset).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather")
+    ///     .paginate(2, 100)
+    ///     .build();
+    ///
+    /// assert_eq!(query.unwrap(), "SELECT * FROM weather LIMIT 100 OFFSET 200");
+    /// ```
+    pub fn paginate(self, page: usize, page_size: usize) -> Self {
+        self.with_limit(page_size)
+            .with_offset(page.saturating_mul(page_size))
+    }
+
+    /// Reads the measurements of every statement from the retention policy `retention_policy`
+    /// instead of the default retention policy of the database
+    ///
+    /// The measurements in the `FROM` clause are prefixed with `"<retention_policy>".` when the
+    /// query is built. Measurements which are already qualified with a retention policy or database
+    /// and measurements of subqueries are left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather, \"air quality\"")
+    ///     .with_retention_policy("one_year")
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     query.unwrap(),
+    ///     "SELECT * FROM \"one_year\".weather, \"one_year\".\"air quality\""
+    /// );
+    /// ```
+    pub fn with_retention_policy<S>(mut self, retention_policy: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.retention_policy = Some(retention_policy.into());
+        self
+    }
+
+    /// Requests the timestamps in the response in `epoch` precision, overriding the precision
+    /// set with [`InfluxDbClient::with_precision`](crate::client::InfluxDbClient::with_precision)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::{InfluxDbQuery, Precision};
+    ///
+    /// InfluxDbQuery::raw_read_query("SELECT * FROM weather").with_epoch(Precision::Nanoseconds);
+    /// ```
+    pub fn with_epoch(mut self, epoch: Precision) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Returns the precision set with [`with_epoch`](crate::query::read_query::InfluxDbReadQuery::with_epoch)
+    pub fn get_epoch(&self) -> Option<Precision> {
+        self.epoch
     }
 
     /// Adds a query to the [`InfluxDbReadQuery`]
-    pub fn add<S>(mut self, query: S) -> Self
+    ///
+    /// Only `SELECT` (without `INTO`) and `SHOW` statements can be run in one request.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather_berlin")
+    ///     .add("SELECT * FROM weather_london");
+    /// assert!(query.is_ok());
+    ///
+    /// let query = InfluxDbQuery::raw_read_query("CREATE DATABASE weather")
+    ///     .add("SELECT * FROM weather_london");
+    /// assert!(query.is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If any statement is not a `SELECT` or `SHOW` statement,
+    /// a [`InfluxDbError::InvalidQueryError`] will be returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<S>(mut self, query: S) -> Result<Self, InfluxDbError>
     where
         S: ToString,
     {
         self.queries.push(query.to_string());
-        self
+        self.validate_batch()?;
+        Ok(self)
+    }
+
+    /// Checks that every statement of a multi-statement query can be batched
+    fn validate_batch(&self) -> Result<(), InfluxDbError> {
+        if self.queries.len() < 2 {
+            return Ok(());
+        }
+        match self.queries.iter().find(|query| !is_batchable(query)) {
+            Some(query) => Err(InfluxDbError::InvalidQueryError {
+                error: format!(
+                    "only SELECT and SHOW statements can be batched, found \"{}\"",
+                    query
+                ),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Replaces the placeholders of `query` outside of quotes with the bound values of `params`
+fn bind_params(query: &str, params: &[(String, InfluxDbValue)]) -> String {
+    let mut bound = String::with_capacity(query.len());
+    let mut quote = None;
+    let mut chars = query.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                bound.push(c);
+                if let Some((_, escaped)) = chars.next() {
+                    bound.push(escaped);
+                }
+                continue;
+            }
+            (Some(open), _) if c == open => quote = None,
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (None, '$') => {
+                let start = index + 1;
+                let mut end = start;
+                while let Some(&(next, c)) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    chars.next();
+                    end = next + c.len_utf8();
+                }
+                let key = &query[start..end];
+                match params.iter().find(|(bound, _)| bound == key) {
+                    Some((_, value)) => bound.push_str(&value.to_string()),
+                    None => bound.push_str(&query[index..end]),
+                }
+                continue;
+            }
+            _ => {}
+        }
+        bound.push(c);
+    }
+    bound
+}
+
+/// Clauses which follow the `GROUP BY` and `fill` clauses of a `SELECT` statement
+const CLAUSES_AFTER_FILL: &[&[&str]] = &[
+    &["ORDER", "BY"],
+    &["LIMIT"],
+    &["OFFSET"],
+    &["SLIMIT"],
+    &["SOFFSET"],
+    &["TZ("],
+];
+
+/// Inserts `GROUP BY time(<interval>) fill(<fill>)` into `query`
+fn splice_group_by_time(query: &str, interval: &str, fill: FillOption) -> String {
+    let tail_start = find_clause(query, CLAUSES_AFTER_FILL)
+        .map(|(start, _)| start)
+        .unwrap_or_else(|| query.len());
+    let (head, tail) = query.split_at(tail_start);
+    let head = head.trim_end();
+    let mut spliced = match find_clause(head, &[&["GROUP", "BY"]]) {
+        Some((_, end)) => format!("{} time({}),{}", &head[..end], interval, &head[end..]),
+        None => format!("{} GROUP BY time({})", head, interval),
+    };
+    spliced.push_str(&format!(" fill({})", fill));
+    if !tail.is_empty() {
+        spliced.push(' ');
+        spliced.push_str(tail);
+    }
+    spliced
+}
+
+/// Sets the `LIMIT` and `OFFSET` clauses of `query`
+fn splice_pagination(query: &str, limit: Option<usize>, offset: Option<usize>) -> String {
+    let tail_start = find_clause(query, &[&["SLIMIT"], &["SOFFSET"], &["TZ("]])
+        .map(|(start, _)| start)
+        .unwrap_or_else(|| query.len());
+    let (head, tail) = query.split_at(tail_start);
+    let mut spliced = head.trim_end().to_string();
+    if let Some(limit) = limit {
+        spliced = set_clause(&spliced, "LIMIT", limit, &[&["OFFSET"]]);
+    }
+    if let Some(offset) = offset {
+        spliced = set_clause(&spliced, "OFFSET", offset, &[]);
+    }
+    if !tail.is_empty() {
+        spliced.push(' ');
+        spliced.push_str(tail);
+    }
+    spliced
+}
+
+/// Replaces the value of the clause `keyword` of `query` with `value`, or inserts the clause
+/// before the first of `following` or at the end if `query` does not have it
+fn set_clause(query: &str, keyword: &str, value: usize, following: &[&[&str]]) -> String {
+    if let Some((_, end)) = find_clause(query, &[&[keyword]]) {
+        let rest = query[end..].trim_start();
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let value_end = query.len() - rest.len() + digits;
+        return format!("{} {}{}", &query[..end], value, &query[value_end..]);
+    }
+    match find_clause(query, following) {
+        Some((start, _)) => format!(
+            "{}{} {} {}",
+            &query[..start],
+            keyword,
+            value,
+            &query[start..]
+        ),
+        None => format!("{} {} {}", query, keyword, value),
+    }
+}
+
+/// Returns the start and end of the first of `clauses` in `query`, ignoring keywords in quotes
+/// and subqueries
+///
+/// A clause is a sequence of keywords separated by whitespace, matched case-insensitively. A
+/// keyword ending with `(` is a function, which only matches if it is called, so that e.g. a
+/// `tz` tag is not taken for the `tz()` clause.
+fn find_clause(query: &str, clauses: &[&[&str]]) -> Option<(usize, usize)> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut quote = None;
+    let mut escaped = false;
+    let mut depth = 0;
+    let mut previous = ' ';
+    for (index, c) in query.char_indices() {
+        let word_start = !is_word(previous);
+        previous = c;
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' => quote = Some(c),
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth == 0 && word_start && is_word(c) => {
+                for clause in clauses {
+                    if let Some(end) = match_clause(query, index, clause) {
+                        return Some((index, end));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the end of `clause` if it starts at `start` of `query`
+fn match_clause(query: &str, start: usize, clause: &[&str]) -> Option<usize> {
+    let mut end = start;
+    for (position, keyword) in clause.iter().enumerate() {
+        if position > 0 {
+            let rest = &query[end..];
+            let trimmed = rest.trim_start();
+            if trimmed.len() == rest.len() {
+                return None;
+            }
+            end += rest.len() - trimmed.len();
+        }
+        let (keyword, is_function) = match keyword.strip_suffix('(') {
+            Some(name) => (name, true),
+            None => (*keyword, false),
+        };
+        let word = query.get(end..end + keyword.len())?;
+        if !word.eq_ignore_ascii_case(keyword) {
+            return None;
+        }
+        end += keyword.len();
+        if is_function {
+            let rest = &query[end..];
+            let trimmed = rest.trim_start();
+            return if trimmed.starts_with('(') {
+                Some(end + rest.len() - trimmed.len() + 1)
+            } else {
+                None
+            };
+        }
+    }
+    match query[end..].chars().next() {
+        Some(c) if c.is_ascii_alphanumeric() || c == '_' => None,
+        _ => Some(end),
+    }
+}
+
+/// Prefixes the unqualified measurements in the `FROM` clause of `query` with `retention_policy`
+fn qualify_measurements(query: &str, retention_policy: &str) -> String {
+    let mut position = match find_clause(query, &[&["FROM"]]) {
+        Some((_, end)) => end,
+        None => return query.to_string(),
+    };
+    let prefix = format!("{}.", quote_identifier(retention_policy));
+    let mut qualified = String::with_capacity(query.len() + prefix.len());
+    let mut copied = 0;
+    loop {
+        position += query[position..].len() - query[position..].trim_start().len();
+        let end = match identifier_end(query, position) {
+            Some(end) => end,
+            None => break,
+        };
+        if !query[end..].starts_with('.') {
+            qualified.push_str(&query[copied..position]);
+            qualified.push_str(&prefix);
+            copied = position;
+        }
+        position = end;
+        while query[position..].starts_with('.') {
+            position += 1;
+            position = identifier_end(query, position).unwrap_or(position);
+        }
+        let rest = query[position..].trim_start();
+        if !rest.starts_with(',') {
+            break;
+        }
+        position = query.len() - rest.len() + 1;
+    }
+    qualified.push_str(&query[copied..]);
+    qualified
+}
+
+/// Returns the end of the identifier or regular expression starting at `start` of `query`
+fn identifier_end(query: &str, start: usize) -> Option<usize> {
+    let mut chars = query[start..].char_indices();
+    match chars.next() {
+        Some((_, delimiter)) if delimiter == '"' || delimiter == '/' => {
+            let mut escaped = false;
+            for (index, c) in chars {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == delimiter {
+                    return Some(start + index + 1);
+                }
+            }
+            None
+        }
+        Some((_, c)) if c.is_ascii_alphanumeric() || c == '_' => {
+            let rest = &query[start..];
+            let word = rest.len()
+                - rest
+                    .trim_start_matches(|c: char| c.is_ascii_alphanumeric() || c == '_')
+                    .len();
+            Some(start + word)
+        }
+        _ => None,
+    }
+}
+
+/// Returns whether `interval` is an InfluxQL duration literal, e.g. `10s` or `1h30m`
+pub(crate) fn is_duration_literal(interval: &str) -> bool {
+    const UNITS: &[&str] = &["ns", "us", "µs", "ms", "u", "µ", "s", "m", "h", "d", "w"];
+    let mut rest = interval;
+    if rest.is_empty() {
+        return false;
+    }
+    while !rest.is_empty() {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return false;
+        }
+        rest = &rest[digits..];
+        match UNITS.iter().find(|unit| rest.starts_with(*unit)) {
+            Some(unit) => rest = &rest[unit.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Returns whether the statement is a `SELECT` without `INTO` or a `SHOW` statement
+fn is_batchable(query: &str) -> bool {
+    let upper = query.trim_start().to_uppercase();
+    (upper.starts_with("SELECT") && !has_into_clause(query)) || upper.starts_with("SHOW")
+}
+
+/// Returns whether `query` has an `INTO` clause outside of quotes and subqueries
+pub(crate) fn has_into_clause(query: &str) -> bool {
+    find_clause(query, &[&["INTO"]]).is_some()
+}
+
+/// Writes the statements joined with `;` as they were added, without binding placeholders or
+/// applying the options of the query, which [`build`](crate::query::InfluxDbQuery::build) does
+impl fmt::Display for InfluxDbReadQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, query) in self.queries.iter().enumerate() {
+            if index > 0 {
+                f.write_str(";")?;
+            }
+            f.write_str(query)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for InfluxDbReadQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InfluxDbReadQuery")
+            .field("query", &self.to_string())
+            .field("queries", &self.queries)
+            .field("epoch", &self.epoch)
+            .field("params", &self.params)
+            .field("group_by_time", &self.group_by_time)
+            .field("retention_policy", &self.retention_policy)
+            .field("limit", &self.limit)
+            .field("offset", &self.offset)
+            .finish()
     }
 }
 
 impl InfluxDbQuery for InfluxDbReadQuery {
     fn build(&self) -> Result<ValidQuery, InfluxDbError> {
-        Ok(ValidQuery(self.queries.join(";")))
+        self.validate_batch()?;
+        let mut queries: Vec<String> = self
+            .queries
+            .iter()
+            .map(|query| bind_params(query, &self.params))
+            .map(|query| match &self.retention_policy {
+                Some(retention_policy) => qualify_measurements(&query, retention_policy),
+                None => query,
+            })
+            .collect();
+        if let Some((interval, fill)) = &self.group_by_time {
+            if !is_duration_literal(interval) {
+                return Err(InfluxDbError::InvalidQueryError {
+                    error: format!("invalid interval \"{}\"", interval),
+                });
+            }
+            if let Some(last) = queries.last_mut() {
+                *last = splice_group_by_time(last, interval, *fill);
+            }
+        }
+        if self.limit.is_some() || self.offset.is_some() {
+            if let Some(last) = queries.last_mut() {
+                *last = splice_pagination(last, self.limit, self.offset);
+            }
+        }
+        Ok(ValidQuery(queries.join(";")))
     }
 
     fn get_type(&self) -> QueryType {
@@ -42,7 +673,9 @@ impl InfluxDbQuery for InfluxDbReadQuery {
 
 #[cfg(test)]
 mod tests {
-    use crate::query::{InfluxDbQuery, QueryType};
+    use super::InfluxDbValue;
+    use crate::query::select_query::FillOption;
+    use crate::query::{InfluxDbQuery, Precision, QueryType, Timestamp};
 
     #[test]
     fn test_read_builder_single_query() {
@@ -55,11 +688,364 @@ mod tests {
     fn test_read_builder_multi_query() {
         let query = InfluxDbQuery::raw_read_query("SELECT * FROM aachen")
             .add("SELECT * FROM cologne")
+            .unwrap()
             .build();
 
         assert_eq!(query.unwrap(), "SELECT * FROM aachen;SELECT * FROM cologne");
     }
 
+    #[test]
+    fn test_read_builder_multi_query_show() {
+        let query = InfluxDbQuery::raw_read_query("SHOW MEASUREMENTS")
+            .add("select * from cologne")
+            .unwrap()
+            .build();
+
+        assert_eq!(query.unwrap(), "SHOW MEASUREMENTS;select * from cologne");
+    }
+
+    #[test]
+    fn test_read_builder_multi_query_rejects_create() {
+        let query =
+            InfluxDbQuery::raw_read_query("CREATE DATABASE aachen").add("SELECT * FROM cologne");
+        assert!(query.is_err(), "CREATE should not be batched");
+
+        let query =
+            InfluxDbQuery::raw_read_query("SELECT * FROM aachen").add("DROP DATABASE cologne");
+        assert!(query.is_err(), "DROP should not be batched");
+    }
+
+    #[test]
+    fn test_read_builder_multi_query_rejects_select_into() {
+        let query = InfluxDbQuery::raw_read_query("SELECT * INTO aachen_copy FROM aachen")
+            .add("SELECT * FROM cologne");
+
+        assert!(query.is_err(), "SELECT INTO should not be batched");
+
+        let query = InfluxDbQuery::raw_read_query("SELECT *\n\tINTO aachen_copy FROM aachen")
+            .add("SELECT * FROM cologne");
+        assert!(
+            query.is_err(),
+            "SELECT INTO after a newline should not be batched"
+        );
+
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM aachen WHERE city = ' INTO '")
+            .add("SELECT * FROM cologne");
+        assert!(query.is_ok());
+    }
+
+    #[test]
+    fn test_read_builder_single_non_select_query() {
+        let query = InfluxDbQuery::raw_read_query("CREATE DATABASE aachen").build();
+
+        assert_eq!(query.unwrap(), "CREATE DATABASE aachen");
+    }
+
+    #[test]
+    fn test_with_epoch() {
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM aachen");
+        assert_eq!(query.get_epoch(), None);
+
+        let query = query.with_epoch(Precision::Seconds);
+        assert_eq!(query.get_epoch(), Some(Precision::Seconds));
+    }
+
+    #[test]
+    fn test_bind() {
+        let query = InfluxDbQuery::raw_read_query(
+            "SELECT * FROM weather WHERE location = $location AND temperature > $temperature AND raining = $raining AND time > $time",
+        )
+        .bind("location", "berlin")
+        .bind("temperature", 20.5)
+        .bind("raining", false)
+        .bind("time", Timestamp::SECONDS(1))
+        .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM weather WHERE location = 'berlin' AND temperature > 20.5 AND raining = false AND time > 1000000000ns"
+        );
+    }
+
+    #[test]
+    fn test_bind_escapes_quotes() {
+        let query =
+            InfluxDbQuery::raw_read_query("SELECT * FROM weather WHERE location = $measurement")
+                .bind("measurement", "berlin' OR location = 'london")
+                .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM weather WHERE location = 'berlin\\' OR location = \\'london'"
+        );
+
+        let query =
+            InfluxDbQuery::raw_read_query("SELECT * FROM weather WHERE location = $location")
+                .bind("location", "berlin\\' OR true")
+                .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM weather WHERE location = 'berlin\\\\\\' OR true'"
+        );
+    }
+
+    #[test]
+    fn test_bind_leaves_quoted_and_unbound_placeholders() {
+        let query = InfluxDbQuery::raw_read_query(
+            "SELECT \"$value\" FROM weather WHERE location = '$location' AND temperature > $temperature",
+        )
+        .bind("value", 1)
+        .bind("location", "berlin")
+        .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT \"$value\" FROM weather WHERE location = '$location' AND temperature > $temperature"
+        );
+    }
+
+    #[test]
+    fn test_bind_multi_query() {
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM aachen WHERE id = $id")
+            .add("SELECT * FROM cologne WHERE id = $id")
+            .unwrap()
+            .bind("id", 1)
+            .bind("$id", 2)
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM aachen WHERE id = 2;SELECT * FROM cologne WHERE id = 2"
+        );
+    }
+
+    #[test]
+    fn test_value_display() {
+        assert_eq!(InfluxDbValue::from(-1).to_string(), "-1");
+        assert_eq!(InfluxDbValue::from(true).to_string(), "true");
+        assert_eq!(InfluxDbValue::from(Timestamp::NOW).to_string(), "now()");
+        assert_eq!(
+            InfluxDbValue::from(Timestamp::MILLISECONDS(5)).to_string(),
+            "5000000ns"
+        );
+    }
+
+    #[test]
+    fn test_with_group_by_time() {
+        let query = InfluxDbQuery::raw_read_query("SELECT mean(temperature) FROM weather")
+            .with_group_by_time("1m", FillOption::None)
+            .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT mean(temperature) FROM weather GROUP BY time(1m) fill(none)"
+        );
+
+        let query = InfluxDbQuery::raw_read_query(
+            "SELECT mean(temperature) FROM weather WHERE time > now() - 1d order by time desc LIMIT 10 OFFSET 5",
+        )
+        .with_group_by_time("1h30m", FillOption::Literal(0.0))
+        .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT mean(temperature) FROM weather WHERE time > now() - 1d GROUP BY time(1h30m) fill(0) order by time desc LIMIT 10 OFFSET 5"
+        );
+    }
+
+    #[test]
+    fn test_with_group_by_time_existing_group_by() {
+        let query = InfluxDbQuery::raw_read_query(
+            "SELECT max(temperature) FROM weather GROUP BY location SLIMIT 2",
+        )
+        .with_group_by_time("10m", FillOption::Previous)
+        .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT max(temperature) FROM weather GROUP BY time(10m), location fill(previous) SLIMIT 2"
+        );
+    }
+
+    #[test]
+    fn test_with_group_by_time_ignores_quotes_and_subqueries() {
+        let query = InfluxDbQuery::raw_read_query(
+            "SELECT mean(max) FROM (SELECT max(temperature) FROM weather GROUP BY location LIMIT 5) WHERE \"limit\" = 'order by'",
+        )
+        .with_group_by_time("1d", FillOption::Null)
+        .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT mean(max) FROM (SELECT max(temperature) FROM weather GROUP BY location LIMIT 5) WHERE \"limit\" = 'order by' GROUP BY time(1d) fill(null)"
+        );
+    }
+
+    #[test]
+    fn test_with_group_by_time_tz_tag() {
+        let query =
+            InfluxDbQuery::raw_read_query("SELECT mean(value) FROM weather WHERE tz = 'UTC'")
+                .with_group_by_time("1m", FillOption::None)
+                .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT mean(value) FROM weather WHERE tz = 'UTC' GROUP BY time(1m) fill(none)"
+        );
+
+        let query = InfluxDbQuery::raw_read_query("SELECT mean(value) FROM weather tz ('UTC')")
+            .with_group_by_time("1m", FillOption::None)
+            .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT mean(value) FROM weather GROUP BY time(1m) fill(none) tz ('UTC')"
+        );
+    }
+
+    #[test]
+    fn test_with_group_by_time_last_statement() {
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather LIMIT 1")
+            .add("SELECT mean(temperature) FROM weather")
+            .unwrap()
+            .with_group_by_time("1w", FillOption::Linear)
+            .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM weather LIMIT 1;SELECT mean(temperature) FROM weather GROUP BY time(1w) fill(linear)"
+        );
+    }
+
+    #[test]
+    fn test_with_group_by_time_invalid_interval() {
+        for interval in &["", "1", "m", "1x", "1m;DROP DATABASE weather", "1.5h"] {
+            let query = InfluxDbQuery::raw_read_query("SELECT mean(temperature) FROM weather")
+                .with_group_by_time(*interval, FillOption::None)
+                .build();
+            assert!(query.is_err(), "{} should be invalid", interval);
+        }
+    }
+
+    #[test]
+    fn test_with_retention_policy() {
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather WHERE time > now() - 1h")
+            .with_retention_policy("autogen")
+            .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM \"autogen\".weather WHERE time > now() - 1h"
+        );
+
+        let query = InfluxDbQuery::raw_read_query("select * from \"weather\",/^air/")
+            .add("SHOW TAG KEYS FROM weather")
+            .unwrap()
+            .with_retention_policy("one \"year\"")
+            .build();
+        assert_eq!(
+            query.unwrap(),
+            "select * from \"one \\\"year\\\"\".\"weather\",\"one \\\"year\\\"\"./^air/;SHOW TAG KEYS FROM \"one \\\"year\\\"\".weather"
+        );
+    }
+
+    #[test]
+    fn test_with_retention_policy_leaves_qualified_measurements() {
+        let query = InfluxDbQuery::raw_read_query(
+            "SELECT * FROM \"autogen\".weather, db..\"air.quality\", noise",
+        )
+        .add("SELECT mean(max) FROM (SELECT max(temperature) FROM weather)")
+        .unwrap()
+        .add("SHOW MEASUREMENTS")
+        .unwrap()
+        .with_retention_policy("one_year")
+        .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM \"autogen\".weather, db..\"air.quality\", \"one_year\".noise;SELECT mean(max) FROM (SELECT max(temperature) FROM weather);SHOW MEASUREMENTS"
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let query =
+            InfluxDbQuery::raw_read_query("SELECT * FROM weather WHERE location = $location")
+                .add("SHOW MEASUREMENTS")
+                .unwrap()
+                .bind("location", "berlin");
+        assert_eq!(
+            query.to_string(),
+            "SELECT * FROM weather WHERE location = $location;SHOW MEASUREMENTS"
+        );
+        assert_eq!(
+            format!("{:?}", query.with_epoch(Precision::Seconds)),
+            "InfluxDbReadQuery { query: \"SELECT * FROM weather WHERE location = $location;SHOW MEASUREMENTS\", queries: [\"SELECT * FROM weather WHERE location = $location\", \"SHOW MEASUREMENTS\"], epoch: Some(Seconds), params: [(\"location\", Str(\"berlin\"))], group_by_time: None, retention_policy: None, limit: None, offset: None }"
+        );
+    }
+
+    #[test]
+    fn test_with_limit_and_offset() {
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather GROUP BY location")
+            .with_limit(10)
+            .with_offset(20)
+            .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM weather GROUP BY location LIMIT 10 OFFSET 20"
+        );
+
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather ORDER BY time DESC")
+            .with_limit(10)
+            .with_limit(5)
+            .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM weather ORDER BY time DESC LIMIT 5"
+        );
+
+        let query =
+            InfluxDbQuery::raw_read_query("SELECT * FROM weather SLIMIT 2 tz('Europe/Berlin')")
+                .with_offset(3)
+                .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM weather OFFSET 3 SLIMIT 2 tz('Europe/Berlin')"
+        );
+    }
+
+    #[test]
+    fn test_with_limit_replaces_existing_clauses() {
+        let query = InfluxDbQuery::raw_read_query(
+            "SELECT * FROM weather WHERE \"limit\" = 'LIMIT 1' limit 100 offset 100 SOFFSET 1",
+        )
+        .paginate(3, 50)
+        .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM weather WHERE \"limit\" = 'LIMIT 1' limit 50 offset 150 SOFFSET 1"
+        );
+
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather OFFSET 5")
+            .with_limit(10)
+            .build();
+        assert_eq!(query.unwrap(), "SELECT * FROM weather LIMIT 10 OFFSET 5");
+    }
+
+    #[test]
+    fn test_with_limit_tz_column() {
+        let query = InfluxDbQuery::raw_read_query("SELECT tz, value FROM weather")
+            .with_limit(10)
+            .build();
+        assert_eq!(query.unwrap(), "SELECT tz, value FROM weather LIMIT 10");
+    }
+
+    #[test]
+    fn test_paginate_last_statement() {
+        let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather LIMIT 1")
+            .add("SELECT mean(temperature) FROM weather")
+            .unwrap()
+            .with_group_by_time("1h", FillOption::None)
+            .paginate(0, 24)
+            .build();
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM weather LIMIT 1;SELECT mean(temperature) FROM weather GROUP BY time(1h) fill(none) LIMIT 24 OFFSET 0"
+        );
+    }
+
     #[test]
     fn test_correct_query_type() {
         let query = InfluxDbQuery::raw_read_query("SELECT * FROM aachen");
@@ -0,0 +1,655 @@
+//! Select Query Builder returned by InfluxDbQuery::select_query
+//!
+//! Builds InfluxQL `SELECT` statements as an alternative to supplying raw queries
+//! with InfluxDbQuery::raw_read_query
+
+use std::fmt;
+
+use crate::error::InfluxDbError;
+use crate::query::read_query::is_duration_literal;
+use crate::query::{quote_identifier, InfluxDbQuery, QueryType, Timestamp, ValidQuery};
+
+/// Value used to fill time intervals without data in a `GROUP BY time()` query.
+/// See [InfluxDB Docs](https://docs.influxdata.com/influxdb/v1.7/query_language/data_exploration/#group-by-time-intervals-and-fill).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FillOption {
+    /// Omits intervals without data
+    None,
+    /// Reports `null` for intervals without data
+    Null,
+    /// Reports the value of the previous interval
+    Previous,
+    /// Reports the result of a linear interpolation
+    Linear,
+    /// Reports the given value
+    Literal(f64),
+}
+
+impl fmt::Display for FillOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use FillOption::*;
+        match self {
+            None => write!(f, "none"),
+            Null => write!(f, "null"),
+            Previous => write!(f, "previous"),
+            Linear => write!(f, "linear"),
+            Literal(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct InfluxDbSelectBuilder {
+    fields: Vec<String>,
+    into: Option<String>,
+    into_retention_policy: Option<String>,
+    measurement: Option<String>,
+    time_conditions: Vec<String>,
+    conditions: Vec<String>,
+    group_by_time: Option<String>,
+    fill: Option<FillOption>,
+    having: Vec<String>,
+    order_desc: bool,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+impl InfluxDbSelectBuilder {
+    /// Creates a new [`InfluxDbSelectBuilder`](crate::query::select_query::InfluxDbSelectBuilder)
+    pub fn new() -> Self {
+        InfluxDbSelectBuilder::default()
+    }
+
+    /// Sets the measurement to select from
+    pub fn from<S>(mut self, measurement: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.measurement = Some(measurement.into());
+        self
+    }
+
+    /// Adds a field, tag or expression such as `mean("temperature")` to select
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let query = InfluxDbQuery::select_query()
+    ///     .field("temperature")
+    ///     .field("location")
+    ///     .from("weather")
+    ///     .build();
+    ///
+    /// assert_eq!(query.unwrap(), "SELECT temperature, location FROM \"weather\"");
+    /// ```
+    pub fn field<S>(mut self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.fields.push(name.into());
+        self
+    }
+
+    /// Selects all fields and tags, replacing any previously added fields
+    pub fn all_fields(mut self) -> Self {
+        self.fields = vec![String::from("*")];
+        self
+    }
+
+    /// Restricts the query to points between `start` and `end`, both inclusive
+    ///
+    /// [`Timestamp::NOW`](crate::query::Timestamp::NOW) refers to the time of the query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let query = InfluxDbQuery::select_query()
+    ///     .all_fields()
+    ///     .from("weather")
+    ///     .where_time_range(Timestamp::HOURS(11), Timestamp::NOW)
+    ///     .build();
+    ///
+    /// assert_eq!(query.unwrap(), "SELECT * FROM \"weather\" WHERE time >= 11h AND time <= now()");
+    /// ```
+    pub fn where_time_range(mut self, start: Timestamp, end: Timestamp) -> Self {
+        self.time_conditions
+            .push(format!("time >= {}", time_literal(&start)));
+        self.time_conditions
+            .push(format!("time <= {}", time_literal(&end)));
+        self
+    }
+
+    /// Adds a condition to the `WHERE` clause
+    ///
+    /// Multiple conditions are combined with `AND`, following any time range.
+    pub fn where_clause<S>(mut self, expr: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.conditions.push(expr.into());
+        self
+    }
+
+    /// Groups the results into intervals of the given duration, e.g. `1m`
+    ///
+    /// Building the query fails if `interval` is not a duration literal such as `10s` or `1h30m`.
+    pub fn group_by_time(mut self, interval: &str) -> Self {
+        self.group_by_time = Some(interval.to_string());
+        self
+    }
+
+    /// Sets the value reported for intervals without data
+    ///
+    /// Requires [`group_by_time`](crate::query::select_query::InfluxDbSelectBuilder::group_by_time),
+    /// building the query will fail otherwise.
+    pub fn fill(mut self, fill_option: FillOption) -> Self {
+        self.fill = Some(fill_option);
+        self
+    }
+
+    /// Keeps only the intervals whose aggregated values match `expr`, e.g. `mean > 100`
+    ///
+    /// InfluxQL has no `HAVING` clause, so the grouped query is wrapped in a subquery, which is
+    /// filtered by `expr`. `expr` therefore refers to the columns of the grouped query, e.g. `mean`
+    /// for `mean("value")`, or its alias. `ORDER BY`, `LIMIT`, `OFFSET` and `INTO` apply to the
+    /// filtered results. Multiple expressions are combined with `AND`.
+    ///
+    /// Requires [`group_by_time`](crate::query::select_query::InfluxDbSelectBuilder::group_by_time),
+    /// building the query will fail otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let query = InfluxDbQuery::select_query()
+    ///     .field("mean(\"value\")")
+    ///     .from("cpu")
+    ///     .group_by_time("1h")
+    ///     .having("mean > 100")
+    ///     .limit(10)
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     query.unwrap(),
+    ///     "SELECT * FROM (SELECT mean(\"value\") FROM \"cpu\" GROUP BY time(1h)) WHERE mean > 100 LIMIT 10"
+    /// );
+    /// ```
+    pub fn having<S>(mut self, expr: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.having.push(expr.into());
+        self
+    }
+
+    /// Limits the number of returned points
+    pub fn limit(mut self, n: usize) -> Self {
+        self.limit = Some(n);
+        self
+    }
+
+    /// Skips the first `n` points
+    pub fn offset(mut self, n: usize) -> Self {
+        self.offset = Some(n);
+        self
+    }
+
+    /// Returns the most recent points first
+    pub fn order_desc(mut self) -> Self {
+        self.order_desc = true;
+        self
+    }
+
+    /// Writes the results to the given measurement instead of returning them
+    ///
+    /// Queries with an `INTO` clause are sent as `POST` request, as they write data.
+    pub fn into_measurement<S>(mut self, target: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.into = Some(target.into());
+        self.into_retention_policy = None;
+        self
+    }
+
+    /// Writes the results to the given measurement of the retention policy `retention_policy`
+    /// instead of returning them, e.g. to downsample data into a policy with a longer duration
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::InfluxDbQuery;
+    ///
+    /// let query = InfluxDbQuery::select_query()
+    ///     .field("mean(temperature)")
+    ///     .into_measurement_in_policy("weather_hourly", "one_year")
+    ///     .from("weather")
+    ///     .group_by_time("1h")
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     query.unwrap(),
+    ///     "SELECT mean(temperature) INTO \"one_year\".\"weather_hourly\" FROM \"weather\" GROUP BY time(1h)"
+    /// );
+    /// ```
+    pub fn into_measurement_in_policy<S1, S2>(mut self, target: S1, retention_policy: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        self.into = Some(target.into());
+        self.into_retention_policy = Some(retention_policy.into());
+        self
+    }
+}
+
+impl InfluxDbQuery for InfluxDbSelectBuilder {
+    fn build(&self) -> Result<ValidQuery, InfluxDbError> {
+        if self.fields.is_empty() {
+            return Err(InfluxDbError::InvalidQueryError {
+                error: "fields cannot be empty".to_string(),
+            });
+        }
+        let measurement = match &self.measurement {
+            Some(measurement) => measurement,
+            None => {
+                return Err(InfluxDbError::InvalidQueryError {
+                    error: "measurement cannot be empty".to_string(),
+                })
+            }
+        };
+        if let Some(interval) = &self.group_by_time {
+            if !is_duration_literal(interval) {
+                return Err(InfluxDbError::InvalidQueryError {
+                    error: format!("invalid interval \"{}\"", interval),
+                });
+            }
+        }
+        if self.fill.is_some() && self.group_by_time.is_none() {
+            return Err(InfluxDbError::InvalidQueryError {
+                error: "fill requires grouping by time".to_string(),
+            });
+        }
+        if !self.having.is_empty() && self.group_by_time.is_none() {
+            return Err(InfluxDbError::InvalidQueryError {
+                error: "having requires grouping by time".to_string(),
+            });
+        }
+
+        let mut into_clause = String::new();
+        if let Some(into) = &self.into {
+            into_clause.push_str(" INTO ");
+            if let Some(retention_policy) = &self.into_retention_policy {
+                into_clause.push_str(&format!("{}.", quote_identifier(retention_policy)));
+            }
+            into_clause.push_str(&quote_identifier(into));
+        }
+
+        let mut query = format!("SELECT {}", self.fields.join(", "));
+        if self.having.is_empty() {
+            query.push_str(&into_clause);
+        }
+        query.push_str(&format!(" FROM {}", quote_identifier(measurement)));
+        if let Some(conditions) = join_conditions(&self.time_conditions, &self.conditions) {
+            query.push_str(&format!(" WHERE {}", conditions));
+        }
+        if let Some(interval) = &self.group_by_time {
+            query.push_str(&format!(" GROUP BY time({})", interval));
+        }
+        if let Some(fill) = &self.fill {
+            query.push_str(&format!(" fill({})", fill));
+        }
+        if let Some(having) = join_conditions(&[], &self.having) {
+            query = format!("SELECT *{} FROM ({}) WHERE {}", into_clause, query, having);
+        }
+        if self.order_desc {
+            query.push_str(" ORDER BY time DESC");
+        }
+        if let Some(limit) = self.limit {
+            query.push_str(&format!(" LIMIT {}", limit));
+        }
+        if let Some(offset) = self.offset {
+            query.push_str(&format!(" OFFSET {}", offset));
+        }
+
+        Ok(ValidQuery(query))
+    }
+
+    fn get_type(&self) -> QueryType {
+        QueryType::ReadQuery
+    }
+}
+
+/// Combines `time_conditions` and `conditions` with `AND`, or returns `None` if there are none
+fn join_conditions(time_conditions: &[String], conditions: &[String]) -> Option<String> {
+    let condition_count = time_conditions.len() + conditions.len();
+    if condition_count == 0 {
+        return None;
+    }
+    // Parenthesize user supplied conditions so an `OR` does not bind across them
+    let conditions = time_conditions
+        .iter()
+        .cloned()
+        .chain(conditions.iter().map(|condition| {
+            if condition_count > 1 {
+                format!("({})", condition)
+            } else {
+                condition.clone()
+            }
+        }))
+        .collect::<Vec<String>>()
+        .join(" AND ");
+    Some(conditions)
+}
+
+/// Formats a timestamp as an InfluxQL time literal, e.g. `11h`
+fn time_literal(timestamp: &Timestamp) -> String {
+    use Timestamp::*;
+    match timestamp {
+        NOW => String::from("now()"),
+        NANOSECONDS(ts) => format!("{}ns", ts),
+        MICROSECONDS(ts) => format!("{}u", ts),
+        MILLISECONDS(ts) => format!("{}ms", ts),
+        SECONDS(ts) => format!("{}s", ts),
+        MINUTES(ts) => format!("{}m", ts),
+        HOURS(ts) => format!("{}h", ts),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::InfluxDbClient;
+    use crate::error::InfluxDbError;
+    use crate::query::select_query::FillOption;
+    use crate::query::{InfluxDbQuery, QueryType, Timestamp};
+
+    #[test]
+    fn test_select_all_fields() {
+        let query = InfluxDbQuery::select_query()
+            .all_fields()
+            .from("weather")
+            .build();
+
+        assert_eq!(query.unwrap(), "SELECT * FROM \"weather\"");
+    }
+
+    #[test]
+    fn test_select_fields() {
+        let query = InfluxDbQuery::select_query()
+            .field("temperature")
+            .field("mean(\"humidity\")")
+            .from("weather")
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT temperature, mean(\"humidity\") FROM \"weather\""
+        );
+    }
+
+    #[test]
+    fn test_all_fields_replaces_fields() {
+        let query = InfluxDbQuery::select_query()
+            .field("temperature")
+            .all_fields()
+            .from("weather")
+            .build();
+
+        assert_eq!(query.unwrap(), "SELECT * FROM \"weather\"");
+    }
+
+    #[test]
+    fn test_select_without_fields() {
+        let query = InfluxDbQuery::select_query().from("weather").build();
+
+        assert!(query.is_err(), "Query without fields should fail");
+    }
+
+    #[test]
+    fn test_select_without_measurement() {
+        let query = InfluxDbQuery::select_query().all_fields().build();
+
+        assert!(query.is_err(), "Query without measurement should fail");
+    }
+
+    #[test]
+    fn test_quoted_measurement() {
+        let query = InfluxDbQuery::select_query()
+            .all_fields()
+            .from("weather \"berlin\"")
+            .build();
+
+        assert_eq!(query.unwrap(), "SELECT * FROM \"weather \\\"berlin\\\"\"");
+    }
+
+    #[test]
+    fn test_where_time_range() {
+        let query = InfluxDbQuery::select_query()
+            .all_fields()
+            .from("weather")
+            .where_time_range(Timestamp::SECONDS(1_565_956_800), Timestamp::NOW)
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM \"weather\" WHERE time >= 1565956800s AND time <= now()"
+        );
+    }
+
+    #[test]
+    fn test_time_literal_units() {
+        let query = InfluxDbQuery::select_query()
+            .all_fields()
+            .from("weather")
+            .where_time_range(Timestamp::NANOSECONDS(1), Timestamp::MICROSECONDS(2))
+            .where_time_range(Timestamp::MILLISECONDS(3), Timestamp::MINUTES(4))
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM \"weather\" WHERE time >= 1ns AND time <= 2u AND time >= 3ms AND time <= 4m"
+        );
+    }
+
+    #[test]
+    fn test_single_where_clause() {
+        let query = InfluxDbQuery::select_query()
+            .all_fields()
+            .from("weather")
+            .where_clause("location = 'berlin'")
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM \"weather\" WHERE location = 'berlin'"
+        );
+    }
+
+    #[test]
+    fn test_combined_where_clauses() {
+        let query = InfluxDbQuery::select_query()
+            .all_fields()
+            .from("weather")
+            .where_time_range(Timestamp::HOURS(1), Timestamp::HOURS(2))
+            .where_clause("location = 'berlin' OR location = 'london'")
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM \"weather\" WHERE time >= 1h AND time <= 2h AND (location = 'berlin' OR location = 'london')"
+        );
+    }
+
+    #[test]
+    fn test_group_by_time_with_fill() {
+        let query = InfluxDbQuery::select_query()
+            .field("mean(temperature)")
+            .from("weather")
+            .where_time_range(Timestamp::HOURS(1), Timestamp::HOURS(2))
+            .group_by_time("10m")
+            .fill(FillOption::None)
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT mean(temperature) FROM \"weather\" WHERE time >= 1h AND time <= 2h GROUP BY time(10m) fill(none)"
+        );
+    }
+
+    #[test]
+    fn test_fill_options() {
+        let fill = |fill_option| {
+            InfluxDbQuery::select_query()
+                .field("mean(temperature)")
+                .from("weather")
+                .group_by_time("1h")
+                .fill(fill_option)
+                .build()
+                .unwrap()
+                .into_string()
+        };
+
+        assert!(fill(FillOption::Null).ends_with(" fill(null)"));
+        assert!(fill(FillOption::Previous).ends_with(" fill(previous)"));
+        assert!(fill(FillOption::Linear).ends_with(" fill(linear)"));
+        assert!(fill(FillOption::Literal(-1.5)).ends_with(" fill(-1.5)"));
+    }
+
+    #[test]
+    fn test_group_by_time_invalid_interval() {
+        for interval in &["", "1", "1x", "1m) fill(none", "1.5h"] {
+            let query = InfluxDbQuery::select_query()
+                .field("mean(temperature)")
+                .from("weather")
+                .group_by_time(interval)
+                .build();
+            match query {
+                Err(InfluxDbError::InvalidQueryError { error }) => {
+                    assert_eq!(error, format!("invalid interval \"{}\"", interval))
+                }
+                _ => panic!("{} should be invalid: {:?}", interval, query),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill_without_group_by_time() {
+        let query = InfluxDbQuery::select_query()
+            .field("mean(temperature)")
+            .from("weather")
+            .fill(FillOption::Linear)
+            .build();
+
+        assert!(query.is_err(), "Fill without grouping by time should fail");
+    }
+
+    #[test]
+    fn test_order_limit_offset() {
+        let query = InfluxDbQuery::select_query()
+            .all_fields()
+            .from("weather")
+            .order_desc()
+            .limit(10)
+            .offset(20)
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM \"weather\" ORDER BY time DESC LIMIT 10 OFFSET 20"
+        );
+    }
+
+    #[test]
+    fn test_into_measurement() {
+        let query = InfluxDbQuery::select_query()
+            .field("mean(temperature)")
+            .into_measurement("weather_hourly")
+            .from("weather")
+            .group_by_time("1h")
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT mean(temperature) INTO \"weather_hourly\" FROM \"weather\" GROUP BY time(1h)"
+        );
+    }
+
+    #[test]
+    fn test_into_measurement_in_policy() {
+        let query = InfluxDbQuery::select_query()
+            .field("mean(temperature)")
+            .into_measurement_in_policy("weather_hourly", "one_year")
+            .from("weather")
+            .group_by_time("1h");
+
+        assert_eq!(
+            query.build().unwrap(),
+            "SELECT mean(temperature) INTO \"one_year\".\"weather_hourly\" FROM \"weather\" GROUP BY time(1h)"
+        );
+        assert_eq!(
+            query.into_measurement("weather_hourly").build().unwrap(),
+            "SELECT mean(temperature) INTO \"weather_hourly\" FROM \"weather\" GROUP BY time(1h)"
+        );
+    }
+
+    #[test]
+    fn test_into_measurement_method() {
+        let client = InfluxDbClient::new("http://localhost:8086", "database").dry_run();
+        let query = InfluxDbQuery::select_query().all_fields().from("weather");
+
+        assert_eq!(client.query(&query).unwrap().method, "GET");
+        let query = query.into_measurement_in_policy("weather_copy", "one_year");
+        assert_eq!(client.query(&query).unwrap().method, "POST");
+    }
+
+    #[test]
+    fn test_having() {
+        let query = InfluxDbQuery::select_query()
+            .field("mean(\"value\")")
+            .into_measurement("cpu_hourly")
+            .from("cpu")
+            .where_time_range(Timestamp::HOURS(1), Timestamp::NOW)
+            .where_clause("host = 'server1'")
+            .group_by_time("1h")
+            .fill(FillOption::None)
+            .having("mean > 100")
+            .having("mean < 200 OR mean > 300")
+            .order_desc()
+            .limit(10)
+            .offset(20)
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * INTO \"cpu_hourly\" FROM (SELECT mean(\"value\") FROM \"cpu\" WHERE time >= 1h AND time <= now() AND (host = 'server1') GROUP BY time(1h) fill(none)) WHERE (mean > 100) AND (mean < 200 OR mean > 300) ORDER BY time DESC LIMIT 10 OFFSET 20"
+        );
+    }
+
+    #[test]
+    fn test_having_without_group_by_time() {
+        let query = InfluxDbQuery::select_query()
+            .field("mean(\"value\")")
+            .from("cpu")
+            .having("mean > 100")
+            .build();
+
+        match query {
+            Err(InfluxDbError::InvalidQueryError { error }) => {
+                assert_eq!(error, "having requires grouping by time")
+            }
+            _ => panic!("Should cause an InvalidQueryError: {:?}", query),
+        }
+    }
+
+    #[test]
+    fn test_correct_query_type() {
+        let query = InfluxDbQuery::select_query().all_fields().from("weather");
+
+        assert_eq!(query.get_type(), QueryType::ReadQuery);
+    }
+}
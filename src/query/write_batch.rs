@@ -0,0 +1,177 @@
+//! Batch of write queries which are sent in a single request
+//!
+//! Returned by converting a `Vec` of [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery)s
+//! and sent with [`InfluxDbClient::write_batch`](crate::client::InfluxDbClient::write_batch).
+
+use std::iter::FromIterator;
+use std::vec;
+
+use crate::query::write_query::InfluxDbWriteQuery;
+
+/// Write queries which are sent to InfluxDB together
+///
+/// # Examples
+///
+/// ```rust
+/// use influxdb::query::write_batch::InfluxDbWriteBatch;
+/// use influxdb::query::{InfluxDbQuery, Timestamp};
+///
+/// let batch: InfluxDbWriteBatch = (0..5)
+///     .map(|hour| InfluxDbQuery::write_query(Timestamp::HOURS(hour), "weather").add_field("temperature", 82))
+///     .collect();
+/// let chunks: Vec<InfluxDbWriteBatch> = batch.chunks(2).collect();
+///
+/// assert_eq!(chunks.len(), 3);
+/// assert_eq!(
+///     chunks[2].to_line_protocol(),
+///     "weather temperature=82i 4"
+/// );
+/// ```
+#[derive(Default)]
+pub struct InfluxDbWriteBatch {
+    queries: Vec<InfluxDbWriteQuery>,
+}
+
+impl InfluxDbWriteBatch {
+    /// Creates an empty [`InfluxDbWriteBatch`](crate::query::write_batch::InfluxDbWriteBatch)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `query` to the end of the batch
+    pub fn push(&mut self, query: InfluxDbWriteQuery) {
+        self.queries.push(query);
+    }
+
+    /// Returns the number of write queries in the batch
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    /// Returns `true` if the batch contains no write queries
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    /// Splits the batch into batches of `size` write queries, keeping their order
+    ///
+    /// The last batch contains the remaining queries, so it may be smaller than `size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn chunks(self, size: usize) -> impl Iterator<Item = InfluxDbWriteBatch> {
+        assert!(size != 0, "chunk size must not be 0");
+        let mut queries = self.queries.into_iter().peekable();
+        std::iter::from_fn(move || {
+            queries.peek()?;
+            Some(queries.by_ref().take(size).collect())
+        })
+    }
+
+    /// Returns the line protocol representation of all write queries, separated by newlines
+    ///
+    /// Like [`InfluxDbWriteQuery::to_line_protocol`](crate::query::write_query::InfluxDbWriteQuery::to_line_protocol),
+    /// this does not check whether the queries are valid.
+    pub fn to_line_protocol(&self) -> String {
+        self.queries
+            .iter()
+            .map(InfluxDbWriteQuery::to_line_protocol)
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl From<Vec<InfluxDbWriteQuery>> for InfluxDbWriteBatch {
+    fn from(queries: Vec<InfluxDbWriteQuery>) -> Self {
+        InfluxDbWriteBatch { queries }
+    }
+}
+
+impl FromIterator<InfluxDbWriteQuery> for InfluxDbWriteBatch {
+    fn from_iter<I: IntoIterator<Item = InfluxDbWriteQuery>>(iter: I) -> Self {
+        InfluxDbWriteBatch {
+            queries: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for InfluxDbWriteBatch {
+    type Item = InfluxDbWriteQuery;
+    type IntoIter = vec::IntoIter<InfluxDbWriteQuery>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.queries.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InfluxDbWriteBatch;
+    use crate::query::{InfluxDbQuery, Timestamp};
+
+    fn batch(len: i64) -> InfluxDbWriteBatch {
+        (0..len)
+            .map(|hour| {
+                InfluxDbQuery::write_query(Timestamp::HOURS(hour), "weather")
+                    .add_field("temperature", 82)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunks() {
+        let chunks: Vec<InfluxDbWriteBatch> = batch(7).chunks(3).collect();
+        assert_eq!(
+            chunks
+                .iter()
+                .map(InfluxDbWriteBatch::len)
+                .collect::<Vec<_>>(),
+            vec![3, 3, 1]
+        );
+        assert_eq!(
+            chunks[1].to_line_protocol(),
+            "weather temperature=82i 3\nweather temperature=82i 4\nweather temperature=82i 5"
+        );
+        assert_eq!(chunks[2].to_line_protocol(), "weather temperature=82i 6");
+    }
+
+    #[test]
+    fn test_chunks_exact_and_empty() {
+        let chunks: Vec<InfluxDbWriteBatch> = batch(4).chunks(2).collect();
+        assert_eq!(
+            chunks
+                .iter()
+                .map(InfluxDbWriteBatch::len)
+                .collect::<Vec<_>>(),
+            vec![2, 2]
+        );
+        assert_eq!(batch(3).chunks(10).count(), 1);
+        assert_eq!(InfluxDbWriteBatch::new().chunks(10).count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must not be 0")]
+    fn test_chunks_zero() {
+        let _ = batch(1).chunks(0);
+    }
+
+    #[test]
+    fn test_to_line_protocol() {
+        let mut batch = InfluxDbWriteBatch::from(vec![InfluxDbQuery::write_query(
+            Timestamp::HOURS(1),
+            "weather",
+        )
+        .add_field("temperature", 82)]);
+        batch.push(
+            InfluxDbQuery::write_query(Timestamp::HOURS(2), "weather")
+                .add_tag("location", "us-midwest")
+                .add_field("temperature", 83),
+        );
+        assert_eq!(
+            batch.to_line_protocol(),
+            "weather temperature=82i 1\nweather,location=us-midwest temperature=83i 2"
+        );
+        assert_eq!(InfluxDbWriteBatch::new().to_line_protocol(), "");
+    }
+}
@@ -5,14 +5,18 @@
 use crate::error::InfluxDbError;
 use crate::query::{InfluxDbQuery, QueryType, Timestamp, ValidQuery};
 
-// todo: batch write queries
+use std::fmt;
+
+#[cfg(feature = "derive")]
+pub use influxdb_derive::InfluxDbWritable;
 
 /// Internal Representation of a Write query that has not yet been built
 pub struct InfluxDbWriteQuery {
-    fields: Vec<(String, String)>,
+    fields: Vec<(String, FieldValue)>,
     tags: Vec<(String, String)>,
     measurement: String,
     timestamp: Timestamp,
+    retention_policy: Option<String>,
 }
 
 impl InfluxDbWriteQuery {
@@ -26,9 +30,55 @@ impl InfluxDbWriteQuery {
             tags: vec![],
             measurement: measurement.to_string(),
             timestamp,
+            retention_policy: None,
         }
     }
 
+    /// Writes the [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery) to the given retention policy instead of the default one
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let query = InfluxDbQuery::write_query(Timestamp::NOW, "weather")
+    ///     .add_field("temperature", 82)
+    ///     .with_retention_policy("one_week");
+    ///
+    /// assert_eq!(query.get_retention_policy(), Some("one_week"));
+    /// ```
+    pub fn with_retention_policy<S>(mut self, retention_policy: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.retention_policy = Some(retention_policy.into());
+        self
+    }
+
+    /// Replaces the timestamp of the [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery),
+    /// e.g. once the time of a measurement is known
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let query = InfluxDbQuery::write_query(Timestamp::NOW, "weather")
+    ///     .add_field("temperature", 82)
+    ///     .with_timestamp(Timestamp::HOURS(11));
+    ///
+    /// assert_eq!(query.build().unwrap(), "weather temperature=82i 11");
+    /// ```
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.set_timestamp(timestamp);
+        self
+    }
+
+    /// Replaces the timestamp of the [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery) in place
+    pub fn set_timestamp(&mut self, timestamp: Timestamp) {
+        self.timestamp = timestamp;
+    }
+
     /// Adds a field to the [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery)
     ///
     /// # Examples
@@ -41,10 +91,9 @@ impl InfluxDbWriteQuery {
     pub fn add_field<S, I>(mut self, tag: S, value: I) -> Self
     where
         S: ToString,
-        I: Into<InfluxDbType>,
+        I: Into<FieldValue>,
     {
-        let val: InfluxDbType = value.into();
-        self.fields.push((tag.to_string(), val.to_string()));
+        self.fields.push((tag.to_string(), value.into()));
         self
     }
 
@@ -66,11 +115,359 @@ impl InfluxDbWriteQuery {
         S: ToString,
         I: Into<InfluxDbType>,
     {
-        let val: InfluxDbType = value.into();
-        self.tags.push((tag.to_string(), val.to_string()));
+        // Tag values are always strings in the line protocol and must not be quoted
+        let val = match value.into() {
+            InfluxDbType::Text(text) => text,
+            val => val.to_string(),
+        };
+        self.tags.push((tag.to_string(), val));
         self
     }
 
+    /// Adds every key-value pair of `fields` as a field, like chaining calls to
+    /// [`add_field`](crate::query::write_query::InfluxDbWriteQuery::add_field)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut fields = BTreeMap::new();
+    /// fields.insert("humidity", 0.5);
+    /// fields.insert("temperature", 82.0);
+    /// let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+    ///     .add_fields_from_iter(fields);
+    ///
+    /// assert_eq!(query.build().unwrap(), "weather humidity=0.5,temperature=82 11");
+    /// ```
+    pub fn add_fields_from_iter<T, S, I>(self, fields: T) -> Self
+    where
+        T: IntoIterator<Item = (S, I)>,
+        S: ToString,
+        I: Into<FieldValue>,
+    {
+        fields
+            .into_iter()
+            .fold(self, |query, (field, value)| query.add_field(field, value))
+    }
+
+    /// Adds every key-value pair of `tags` as a tag, like chaining calls to
+    /// [`add_tag`](crate::query::write_query::InfluxDbWriteQuery::add_tag)
+    pub fn add_tags_from_iter<T, S, I>(self, tags: T) -> Self
+    where
+        T: IntoIterator<Item = (S, I)>,
+        S: ToString,
+        I: Into<InfluxDbType>,
+    {
+        tags.into_iter()
+            .fold(self, |query, (tag, value)| query.add_tag(tag, value))
+    }
+
+    /// Adds a field to the [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery) if `value` is `Some`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let humidity: Option<i32> = None;
+    /// let query = InfluxDbQuery::write_query(Timestamp::NOW, "weather")
+    ///     .add_field("temperature", 82)
+    ///     .add_optional_field("humidity", humidity)
+    ///     .build();
+    ///
+    /// assert_eq!(query.unwrap(), "weather temperature=82i");
+    /// ```
+    pub fn add_optional_field<S, I>(self, tag: S, value: Option<I>) -> Self
+    where
+        S: ToString,
+        I: Into<FieldValue>,
+    {
+        match value {
+            Some(value) => self.add_field(tag, value),
+            None => self,
+        }
+    }
+
+    /// Adds a tag to the [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery) if `value` is `Some`
+    pub fn add_optional_tag<S, I>(self, tag: S, value: Option<I>) -> Self
+    where
+        S: ToString,
+        I: Into<InfluxDbType>,
+    {
+        match value {
+            Some(value) => self.add_tag(tag, value),
+            None => self,
+        }
+    }
+
+    /// Combines the fields and tags of `other` with the fields and tags of this query
+    ///
+    /// The merged query keeps the timestamp of this query. Fields and tags which are set to the
+    /// same value in both queries are only kept once, and the retention policy of `other` is used
+    /// if this query has none.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let temperature = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+    ///     .add_tag("location", "berlin")
+    ///     .add_field("temperature", 82);
+    /// let humidity = InfluxDbQuery::write_query(Timestamp::HOURS(12), "weather")
+    ///     .add_tag("location", "berlin")
+    ///     .add_field("humidity", 40);
+    ///
+    /// assert_eq!(
+    ///     temperature.merge(humidity).unwrap().build().unwrap(),
+    ///     "weather,location=berlin temperature=82i,humidity=40i 11"
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the queries have different measurements or retention policies, or set a field or tag
+    /// to different values, a [`InfluxDbError::InvalidQueryError`] will be returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn merge(mut self, other: InfluxDbWriteQuery) -> Result<Self, InfluxDbError> {
+        if self.measurement != other.measurement {
+            return Err(InfluxDbError::InvalidQueryError {
+                error: format!(
+                    "cannot merge queries for different measurements \"{}\" and \"{}\"",
+                    self.measurement, other.measurement
+                ),
+            });
+        }
+        match (&self.retention_policy, other.retention_policy) {
+            (Some(retention_policy), Some(other)) if *retention_policy != other => {
+                return Err(InfluxDbError::InvalidQueryError {
+                    error: format!(
+                        "cannot merge queries for different retention policies \"{}\" and \"{}\"",
+                        retention_policy, other
+                    ),
+                });
+            }
+            (None, other) => self.retention_policy = other,
+            _ => {}
+        }
+        merge_pairs("field", &mut self.fields, other.fields)?;
+        merge_pairs("tag", &mut self.tags, other.tags)?;
+        Ok(self)
+    }
+
+    /// Returns the line protocol representation of the [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery)
+    ///
+    /// Unlike [`build`](crate::query::InfluxDbQuery::build), this does not check whether the query
+    /// is valid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+    ///     .add_tag("location", "us-midwest")
+    ///     .add_field("temperature", 82);
+    ///
+    /// assert_eq!(query.to_line_protocol(), "weather,location=us-midwest temperature=82i 11");
+    /// ```
+    pub fn to_line_protocol(&self) -> String {
+        self.line_protocol(&self.tags)
+    }
+
+    /// Checks the query against the rules of the line protocol before it is sent, so invalid points
+    /// from external sources can be rejected without a request to InfluxDB
+    ///
+    /// A query is valid if
+    ///
+    ///  * the measurement is not empty and does not start with `_`, which InfluxDB reserves,
+    ///  * it has at least one field,
+    ///  * no tag or field key is empty or contains a newline or `=`,
+    ///  * no float field is NaN or infinite,
+    ///  * no unsigned integer field is greater than `i64::MAX`, and
+    ///  * the timestamp is `NOW` or positive.
+    ///
+    /// String field values are always valid UTF-8, as they are Rust strings.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influxdb::query::{InfluxDbQuery, Timestamp};
+    ///
+    /// let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+    ///     .add_field("temperature", std::f64::NAN);
+    ///
+    /// assert!(query.validate().is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// A [`InfluxDbError::InvalidQueryError`] describing the first violated rule will be returned.
+    ///
+    /// [`InfluxDbError::InvalidQueryError`]: crate::error::InfluxDbError::InvalidQueryError
+    pub fn validate(&self) -> Result<(), InfluxDbError> {
+        let invalid = |error: String| Err(InfluxDbError::InvalidQueryError { error });
+        if self.measurement.is_empty() {
+            return invalid("measurement cannot be empty".to_string());
+        }
+        if self.measurement.starts_with('_') {
+            return invalid(format!(
+                "measurement \"{}\" cannot start with _",
+                self.measurement
+            ));
+        }
+        if self.fields.is_empty() {
+            return invalid("at least one field is required".to_string());
+        }
+        validate_keys("tag", &self.tags)?;
+        validate_keys("field", &self.fields)?;
+        for (kind, key) in self
+            .tags
+            .iter()
+            .map(|(key, _)| ("tag", key))
+            .chain(self.fields.iter().map(|(key, _)| ("field", key)))
+        {
+            if key.contains('=') {
+                return invalid(format!("{} key \"{}\" cannot contain =", kind, key));
+            }
+        }
+        for (key, value) in &self.fields {
+            if let FieldValue::Float(float) = value {
+                if !float.is_finite() {
+                    return invalid(format!(
+                        "field \"{}\" must be finite, but is {}",
+                        key, float
+                    ));
+                }
+            }
+        }
+        validate_unsigned_fields(&self.fields)?;
+        match self.timestamp {
+            Timestamp::NOW => Ok(()),
+            Timestamp::NANOSECONDS(ts)
+            | Timestamp::MICROSECONDS(ts)
+            | Timestamp::MILLISECONDS(ts)
+            | Timestamp::SECONDS(ts)
+            | Timestamp::MINUTES(ts)
+            | Timestamp::HOURS(ts) => {
+                if ts > 0 {
+                    Ok(())
+                } else {
+                    invalid(format!("timestamp must be positive, but is {}", ts))
+                }
+            }
+        }
+    }
+
+    /// Builds the query like [`build`](crate::query::InfluxDbQuery::build), with `default_tags`
+    /// added unless the query has a tag with the same key
+    pub(crate) fn build_with_default_tags(
+        &self,
+        default_tags: &[(String, String)],
+    ) -> Result<ValidQuery, InfluxDbError> {
+        let mut tags = self.tags.clone();
+        tags.extend(
+            default_tags
+                .iter()
+                .filter(|(key, _)| !self.tags.iter().any(|(tag, _)| tag == key))
+                .cloned(),
+        );
+        if self.fields.is_empty() {
+            return Err(InfluxDbError::InvalidQueryError {
+                error: "at least one field is required".to_string(),
+            });
+        }
+        validate_keys("tag", &tags)?;
+        validate_keys("field", &self.fields)?;
+        validate_unsigned_fields(&self.fields)?;
+
+        Ok(ValidQuery(self.line_protocol(&tags)))
+    }
+
+    /// Returns the line protocol representation of the query with the given tags
+    fn line_protocol(&self, tags: &[(String, String)]) -> String {
+        let mut tags = tags
+            .iter()
+            .map(|(tag, value)| {
+                format!(
+                    "{tag}={value}",
+                    tag = escape_tag_key(tag),
+                    value = escape_tag_value(value)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+        if !tags.is_empty() {
+            tags.insert(0, ',');
+        }
+        let fields = self
+            .fields
+            .iter()
+            .map(|(field, value)| {
+                format!(
+                    "{field}={value}",
+                    field = escape_field_key(field),
+                    value = value
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+
+        format!(
+            "{measurement}{tags} {fields}{time}",
+            measurement = escape_measurement(&self.measurement),
+            tags = tags,
+            fields = fields,
+            time = match self.timestamp {
+                Timestamp::NOW => String::from(""),
+                _ => format!(" {}", self.timestamp),
+            }
+        )
+    }
+
+    /// Returns the measurement the point is written to
+    pub fn get_measurement(&self) -> &str {
+        &self.measurement
+    }
+
+    /// Returns the timestamp of the point
+    #[cfg(feature = "use-serde")]
+    pub(crate) fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    /// Returns the fields of the point
+    #[cfg(feature = "use-serde")]
+    pub(crate) fn fields(&self) -> &[(String, FieldValue)] {
+        &self.fields
+    }
+
+    /// Returns the tags of the point
+    #[cfg(feature = "use-serde")]
+    pub(crate) fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// Returns the number of fields added to the point
+    #[cfg(feature = "tracing")]
+    pub(crate) fn field_count(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns the number of tags added to the point
+    #[cfg(feature = "tracing")]
+    pub(crate) fn tag_count(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Returns the retention policy set with [`with_retention_policy`](crate::query::write_query::InfluxDbWriteQuery::with_retention_policy)
+    pub fn get_retention_policy(&self) -> Option<&str> {
+        self.retention_policy.as_deref()
+    }
+
     pub fn get_precision(&self) -> String {
         let modifier = match self.timestamp {
             Timestamp::NOW => return String::from(""),
@@ -107,6 +504,138 @@ impl ToString for InfluxDbType {
     }
 }
 
+/// Value of a field in a [`InfluxDbWriteQuery`](crate::query::write_query::InfluxDbWriteQuery)
+///
+/// Serializes to the line protocol representation of the respective type: booleans as `t` or `f`,
+/// integers with an `i` suffix, floats as is and text in double quotes. Building a query fails if
+/// an unsigned integer is greater than `i64::MAX`, as it cannot be written with the `i` suffix.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Boolean(bool),
+    Float(f64),
+    SignedInteger(i64),
+    UnsignedInteger(u64),
+    Text(String),
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use FieldValue::*;
+
+        match self {
+            Boolean(true) => write!(f, "t"),
+            Boolean(false) => write!(f, "f"),
+            Float(x) => write!(f, "{}", x),
+            SignedInteger(x) => write!(f, "{}i", x),
+            // InfluxDB 1.x only accepts the `u` suffix when unsigned integers are enabled
+            UnsignedInteger(x) => write!(f, "{}i", x),
+            Text(text) => write!(f, "\"{text}\"", text = escape_field_string(text)),
+        }
+    }
+}
+
+/// Adds the pairs of `other` to `pairs`, failing if a key of both has different values
+fn merge_pairs<V>(
+    kind: &str,
+    pairs: &mut Vec<(String, V)>,
+    other: Vec<(String, V)>,
+) -> Result<(), InfluxDbError>
+where
+    V: PartialEq + fmt::Display,
+{
+    for (key, value) in other {
+        match pairs.iter().find(|(existing, _)| *existing == key) {
+            Some((_, existing)) if *existing != value => {
+                return Err(InfluxDbError::InvalidQueryError {
+                    error: format!(
+                        "conflicting values {} and {} for {} \"{}\"",
+                        existing, value, kind, key
+                    ),
+                });
+            }
+            Some(_) => {}
+            None => pairs.push((key, value)),
+        }
+    }
+    Ok(())
+}
+
+/// Checks that no key of `pairs` is empty or contains a newline, which the line protocol cannot represent
+fn validate_keys<V>(kind: &str, pairs: &[(String, V)]) -> Result<(), InfluxDbError> {
+    for (key, _) in pairs {
+        if key.is_empty() {
+            return Err(InfluxDbError::InvalidQueryError {
+                error: format!("{} keys cannot be empty", kind),
+            });
+        }
+        if key.contains('\n') {
+            return Err(InfluxDbError::InvalidQueryError {
+                error: format!(
+                    "{} key \"{}\" cannot contain a newline",
+                    kind,
+                    key.escape_debug()
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that no unsigned integer field is greater than `i64::MAX`, which InfluxDB 1.x cannot store
+fn validate_unsigned_fields(fields: &[(String, FieldValue)]) -> Result<(), InfluxDbError> {
+    for (key, value) in fields {
+        if let FieldValue::UnsignedInteger(integer) = value {
+            if *integer > i64::MAX as u64 {
+                return Err(InfluxDbError::InvalidQueryError {
+                    error: format!(
+                        "field \"{}\" must be at most {}, but is {}",
+                        key,
+                        i64::MAX,
+                        integer
+                    ),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Escapes commas and spaces in a measurement name
+fn escape_measurement(measurement: &str) -> String {
+    escape(measurement, &[',', ' '])
+}
+
+/// Escapes commas, equals signs and spaces in a tag key
+fn escape_tag_key(key: &str) -> String {
+    escape(key, &[',', '=', ' '])
+}
+
+/// Escapes commas, equals signs and spaces in a tag value
+fn escape_tag_value(value: &str) -> String {
+    escape(value, &[',', '=', ' '])
+}
+
+/// Escapes commas, equals signs and spaces in a field key
+fn escape_field_key(key: &str) -> String {
+    escape(key, &[',', '=', ' '])
+}
+
+/// Escapes double quotes and backslashes inside a string field value
+fn escape_field_string(value: &str) -> String {
+    escape(value, &['"', '\\'])
+}
+
+fn escape(value: &str, special_characters: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special_characters.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 macro_rules! from_impl {
         ( $variant:ident => $( $typ:ident ),+ ) => (
                 $(
@@ -129,50 +658,105 @@ impl From<&str> for InfluxDbType {
     }
 }
 
-impl InfluxDbQuery for InfluxDbWriteQuery {
-    fn build(&self) -> Result<ValidQuery, InfluxDbError> {
-        if self.fields.is_empty() {
-            return Err(InfluxDbError::InvalidQueryError {
-                error: "fields cannot be empty".to_string(),
-            });
-        }
+macro_rules! field_value_from_impl {
+        ( $variant:ident => $( $typ:ident ),+ ) => (
+                $(
+                    impl From<$typ> for FieldValue {
+                        fn from(b: $typ) -> Self {
+                            FieldValue::$variant(b.into())
+                        }
+                    }
+                )+
+        )
+}
+field_value_from_impl! {Boolean => bool}
+field_value_from_impl! {Float => f32, f64}
+field_value_from_impl! {SignedInteger => i8, i16, i32, i64}
+field_value_from_impl! {UnsignedInteger => u8, u16, u32, u64}
+field_value_from_impl! {Text => String}
+impl From<&str> for FieldValue {
+    fn from(b: &str) -> Self {
+        FieldValue::Text(b.into())
+    }
+}
 
-        let mut tags = self
-            .tags
-            .iter()
-            .map(|(tag, value)| format!("{tag}={value}", tag = tag, value = value))
-            .collect::<Vec<String>>()
-            .join(",");
-        if !tags.is_empty() {
-            tags.insert_str(0, ",");
-        }
-        let fields = self
-            .fields
-            .iter()
-            .map(|(field, value)| format!("{field}={value}", field = field, value = value))
-            .collect::<Vec<String>>()
-            .join(",");
+/// Types which can be written to InfluxDB as a single point
+///
+/// With the `derive` feature, this can be derived with `#[derive(InfluxDbWritable)]`. Fields annotated
+/// with `#[influxdb(tag)]` are written as tags, all other fields as fields. `Option` fields are
+/// skipped if they are `None`. A field annotated with `#[influxdb(timestamp)]` takes precedence over
+/// the timestamp passed to [`into_query`](crate::query::write_query::InfluxDbWritable::into_query) and
+/// must implement `Into<Timestamp>`. The measurement defaults to the name of the struct and can be
+/// set with `#[influxdb(measurement = "name")]` on the struct.
+///
+/// # Examples
+///
+/// ```rust
+/// use influxdb::query::write_query::{InfluxDbWritable, InfluxDbWriteQuery};
+/// use influxdb::query::{InfluxDbQuery, Timestamp};
+///
+/// struct Weather {
+///     temperature: i32,
+///     location: String,
+/// }
+///
+/// impl InfluxDbWritable for Weather {
+///     fn into_query(self, timestamp: Timestamp) -> InfluxDbWriteQuery {
+///         InfluxDbQuery::write_query(timestamp, "weather")
+///             .add_field("temperature", self.temperature)
+///             .add_tag("location", self.location)
+///     }
+/// }
+///
+/// let weather = Weather { temperature: 82, location: "us-midwest".to_string() };
+/// assert_eq!(
+///     weather.into_query(Timestamp::HOURS(11)).to_line_protocol(),
+///     "weather,location=us-midwest temperature=82i 11"
+/// );
+/// ```
+pub trait InfluxDbWritable {
+    /// Converts `self` into a write query at `timestamp`
+    fn into_query(self, timestamp: Timestamp) -> InfluxDbWriteQuery;
+}
 
-        Ok(ValidQuery(format!(
-            "{measurement}{tags} {fields}{time}",
-            measurement = self.measurement,
-            tags = tags,
-            fields = fields,
-            time = match self.timestamp {
-                Timestamp::NOW => String::from(""),
-                _ => format!(" {}", self.timestamp),
-            }
-        )))
+/// Writes the point in line protocol, see
+/// [`to_line_protocol`](crate::query::write_query::InfluxDbWriteQuery::to_line_protocol)
+impl fmt::Display for InfluxDbWriteQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_line_protocol())
     }
+}
 
-    fn get_type(&self) -> QueryType {
-        QueryType::WriteQuery
+impl fmt::Debug for InfluxDbWriteQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InfluxDbWriteQuery")
+            .field("line_protocol", &self.to_line_protocol())
+            .field("measurement", &self.measurement)
+            .field("tags", &self.tags)
+            .field("fields", &self.fields)
+            .field("timestamp", &self.timestamp)
+            .field("retention_policy", &self.retention_policy)
+            .finish()
     }
 }
 
-#[cfg(test)]
+impl InfluxDbQuery for InfluxDbWriteQuery {
+    fn build(&self) -> Result<ValidQuery, InfluxDbError> {
+        self.build_with_default_tags(&[])
+    }
+
+    fn get_type(&self) -> QueryType {
+        QueryType::WriteQuery
+    }
+}
+
+#[cfg(test)]
 mod tests {
+    use crate::error::InfluxDbError;
+    use crate::query::write_query::{FieldValue, InfluxDbWriteQuery};
     use crate::query::{InfluxDbQuery, Timestamp};
+    use std::collections::BTreeMap;
+    use std::f64;
 
     #[test]
     fn test_write_builder_empty_query() {
@@ -181,6 +765,54 @@ mod tests {
         assert!(query.is_err(), "Query was not empty");
     }
 
+    #[test]
+    fn test_write_builder_tags_without_fields() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(5), "marina_3")
+            .add_tag("location", "us-midwest")
+            .build();
+
+        match query {
+            Err(InfluxDbError::InvalidQueryError { error }) => {
+                assert_eq!(error, "at least one field is required")
+            }
+            _ => panic!("Should cause an InvalidQueryError: {:?}", query),
+        }
+    }
+
+    #[test]
+    fn test_write_builder_empty_keys() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("", 82)
+            .build();
+        assert!(query.is_err(), "Empty field key was accepted");
+
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .add_tag("", "us-midwest")
+            .build();
+        assert!(query.is_err(), "Empty tag key was accepted");
+    }
+
+    #[test]
+    fn test_write_builder_keys_with_newline() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temper\nature", 82)
+            .build();
+        match query {
+            Err(InfluxDbError::InvalidQueryError { error }) => assert_eq!(
+                error,
+                "field key \"temper\\nature\" cannot contain a newline"
+            ),
+            _ => panic!("Should cause an InvalidQueryError: {:?}", query),
+        }
+
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .add_tag("loca\ntion", "us-midwest")
+            .build();
+        assert!(query.is_err(), "Tag key with newline was accepted");
+    }
+
     #[test]
     fn test_write_builder_single_field() {
         let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
@@ -188,7 +820,7 @@ mod tests {
             .build();
 
         assert!(query.is_ok(), "Query was empty");
-        assert_eq!(query.unwrap(), "weather temperature=82 11");
+        assert_eq!(query.unwrap(), "weather temperature=82i 11");
     }
 
     #[test]
@@ -201,7 +833,7 @@ mod tests {
         assert!(query.is_ok(), "Query was empty");
         assert_eq!(
             query.unwrap(),
-            "weather temperature=82,wind_strength=3.7 11"
+            "weather temperature=82i,wind_strength=3.7 11"
         );
     }
 
@@ -225,8 +857,236 @@ mod tests {
         assert!(query.is_ok(), "Query was empty");
         assert_eq!(
             query.unwrap(),
-            "weather,location=\"us-midwest\",season=\"summer\" temperature=82 11"
+            "weather,location=us-midwest,season=summer temperature=82i 11"
+        );
+    }
+
+    #[test]
+    fn test_write_builder_boolean_fields() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("sunny", true)
+            .add_field("raining", false)
+            .build();
+
+        assert_eq!(query.unwrap(), "weather sunny=t,raining=f 11");
+    }
+
+    #[test]
+    fn test_write_builder_integer_fields() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("signed", -5i64)
+            .add_field("unsigned", 5u64)
+            .build();
+
+        assert_eq!(query.unwrap(), "weather signed=-5i,unsigned=5i 11");
+    }
+
+    #[test]
+    fn test_write_builder_unsigned_field_out_of_range() {
+        let query = |value: u64| {
+            InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather").add_field("count", value)
+        };
+        assert_eq!(
+            query(i64::MAX as u64).build().unwrap(),
+            "weather count=9223372036854775807i 11"
+        );
+
+        let error = InfluxDbError::InvalidQueryError {
+            error:
+                "field \"count\" must be at most 9223372036854775807, but is 9223372036854775808"
+                    .to_string(),
+        };
+        assert_eq!(
+            query(i64::MAX as u64 + 1).build().map(|_| ()),
+            Err(error.clone())
+        );
+        assert_eq!(query(i64::MAX as u64 + 1).validate(), Err(error));
+    }
+
+    #[test]
+    fn test_write_builder_float_and_text_fields() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("wind_strength", 3.5)
+            .add_field("description", "windy")
+            .add_field("forecast", String::from("rain"))
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "weather wind_strength=3.5,description=\"windy\",forecast=\"rain\" 11"
+        );
+    }
+
+    #[test]
+    fn test_write_builder_mixed_fields() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .add_field("wind_strength", 3.7)
+            .add_field("sunny", true)
+            .add_field("description", "hot")
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "weather temperature=82i,wind_strength=3.7,sunny=t,description=\"hot\" 11"
+        );
+    }
+
+    #[test]
+    fn test_write_builder_escapes_measurement() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "my,measurement name")
+            .add_field("temperature", 82)
+            .build();
+
+        assert_eq!(query.unwrap(), "my\\,measurement\\ name temperature=82i 11");
+    }
+
+    #[test]
+    fn test_write_builder_escapes_tags() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .add_tag("host name", "server 1")
+            .add_tag("a=b", "c,d=e")
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "weather,host\\ name=server\\ 1,a\\=b=c\\,d\\=e temperature=82i 11"
+        );
+    }
+
+    #[test]
+    fn test_write_builder_escapes_field_keys() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("wind strength", 3.7)
+            .add_field("a,b=c", 1)
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "weather wind\\ strength=3.7,a\\,b\\=c=1i 11"
+        );
+    }
+
+    #[test]
+    fn test_write_builder_escapes_string_fields() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("description", "it's \"complicated\"")
+            .add_field("path", "C:\\weather")
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "weather description=\"it's \\\"complicated\\\"\",path=\"C:\\\\weather\" 11"
+        );
+    }
+
+    #[test]
+    fn test_write_builder_does_not_escape_other_characters() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("description", "sunny, hot = perfect")
+            .add_tag("location", "us\"midwest")
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "weather,location=us\"midwest description=\"sunny, hot = perfect\" 11"
+        );
+    }
+
+    #[test]
+    fn test_write_builder_optional_fields_and_tags() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .add_optional_field("humidity", Some(69))
+            .add_optional_field::<_, f64>("wind_strength", None)
+            .add_optional_tag("location", Some("us-midwest"))
+            .add_optional_tag::<_, &str>("season", None)
+            .build();
+
+        assert_eq!(
+            query.unwrap(),
+            "weather,location=us-midwest temperature=82i,humidity=69i 11"
+        );
+    }
+
+    #[test]
+    fn test_write_builder_all_optional_fields_none() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .add_optional_field::<_, i32>("humidity", None)
+            .add_optional_field::<_, bool>("sunny", None)
+            .build();
+
+        assert_eq!(query.unwrap(), "weather temperature=82i 11");
+    }
+
+    #[test]
+    fn test_write_builder_only_optional_fields_none() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_optional_field::<_, i32>("humidity", None)
+            .build();
+
+        assert!(query.is_err(), "Query missing one or more fields");
+    }
+
+    #[test]
+    fn test_to_line_protocol() {
+        let query = InfluxDbQuery::write_query(Timestamp::SECONDS(1_565_956_800), "weather")
+            .add_field("temperature", 82)
+            .add_field("wind_strength", 3.7)
+            .add_tag("location", "us-midwest")
+            .add_tag("season", "summer");
+
+        assert_eq!(
+            query.to_line_protocol(),
+            "weather,location=us-midwest,season=summer temperature=82i,wind_strength=3.7 1565956800"
         );
+        // The query is not consumed and builds to the same line
+        assert_eq!(query.build().unwrap(), query.to_line_protocol());
+    }
+
+    #[test]
+    fn test_to_line_protocol_without_timestamp() {
+        let query = InfluxDbQuery::write_query(Timestamp::NOW, "weather")
+            .add_field("temperature", 82)
+            .add_tag("location", "us-midwest");
+
+        assert_eq!(
+            query.to_line_protocol(),
+            "weather,location=us-midwest temperature=82i"
+        );
+    }
+
+    #[test]
+    fn test_add_from_iter() {
+        let mut fields = BTreeMap::new();
+        fields.insert("temperature".to_string(), FieldValue::from(82));
+        fields.insert("raining".to_string(), FieldValue::from(false));
+        let mut tags = BTreeMap::new();
+        tags.insert("season", "summer");
+        tags.insert("location", "us-midwest");
+
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_fields_from_iter(fields)
+            .add_tags_from_iter(tags);
+        assert_eq!(
+            query.build().unwrap(),
+            "weather,location=us-midwest,season=summer raining=f,temperature=82i 11"
+        );
+
+        // Equivalent to chaining individual calls
+        let chained = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("raining", false)
+            .add_field("temperature", 82)
+            .add_tag("location", "us-midwest")
+            .add_tag("season", "summer");
+        assert_eq!(query.to_line_protocol(), chained.to_line_protocol());
+
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_fields_from_iter(vec![("temperature".to_string(), 82)])
+            .add_tags_from_iter(Vec::<(String, String)>::new());
+        assert_eq!(query.build().unwrap(), "weather temperature=82i 11");
     }
 
     #[test]
@@ -240,4 +1100,237 @@ mod tests {
 
         assert_eq!(query.get_type(), QueryType::WriteQuery);
     }
+
+    #[test]
+    fn test_with_timestamp() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .with_timestamp(Timestamp::MILLISECONDS(1_500));
+        assert_eq!(query.get_precision(), "ms");
+        assert_eq!(query.build().unwrap(), "weather temperature=82i 1500");
+
+        let mut query = query;
+        query.set_timestamp(Timestamp::NOW);
+        assert_eq!(query.get_precision(), "");
+        assert_eq!(query.build().unwrap(), "weather temperature=82i");
+    }
+
+    #[test]
+    fn test_merge() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_tag("location", "us-midwest")
+            .add_field("temperature", 82)
+            .merge(
+                InfluxDbQuery::write_query(Timestamp::HOURS(12), "weather")
+                    .add_tag("location", "us-midwest")
+                    .add_tag("season", "summer")
+                    .add_field("temperature", 82)
+                    .add_field("humidity", 40)
+                    .with_retention_policy("one_week"),
+            )
+            .unwrap();
+
+        assert_eq!(query.get_retention_policy(), Some("one_week"));
+        assert_eq!(
+            query.build().unwrap(),
+            "weather,location=us-midwest,season=summer temperature=82i,humidity=40i 11"
+        );
+    }
+
+    #[test]
+    fn test_merge_measurement_mismatch() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .merge(
+                InfluxDbQuery::write_query(Timestamp::HOURS(11), "traffic").add_field("cars", 10),
+            );
+
+        match query {
+            Err(InfluxDbError::InvalidQueryError { error }) => assert_eq!(
+                error,
+                "cannot merge queries for different measurements \"weather\" and \"traffic\""
+            ),
+            _ => panic!("Should cause an InvalidQueryError"),
+        }
+    }
+
+    #[test]
+    fn test_merge_conflicts() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82)
+            .merge(
+                InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+                    .add_field("temperature", 83),
+            );
+        match query {
+            Err(InfluxDbError::InvalidQueryError { error }) => assert_eq!(
+                error,
+                "conflicting values 82i and 83i for field \"temperature\""
+            ),
+            _ => panic!("Should cause an InvalidQueryError"),
+        }
+
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_tag("location", "us-midwest")
+            .merge(
+                InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+                    .add_tag("location", "us-east"),
+            );
+        assert!(query.is_err(), "Conflicting tags were merged");
+
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .with_retention_policy("one_week")
+            .merge(
+                InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+                    .with_retention_policy("one_month"),
+            );
+        assert!(query.is_err(), "Conflicting retention policies were merged");
+    }
+
+    #[test]
+    fn test_with_retention_policy() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_field("temperature", 82);
+        assert_eq!(query.get_retention_policy(), None);
+
+        let query = query.with_retention_policy("one_week");
+        assert_eq!(query.get_retention_policy(), Some("one_week"));
+        // The retention policy is not part of the line protocol
+        assert_eq!(query.build().unwrap(), "weather temperature=82i 11");
+    }
+
+    fn validation_error(query: InfluxDbWriteQuery) -> String {
+        match query.validate() {
+            Err(InfluxDbError::InvalidQueryError { error }) => error,
+            result => panic!("Expected InvalidQueryError, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_tag("location", "us-midwest")
+            .add_field("temperature", 82.5)
+            .add_field("description", "warm = sunny")
+            .add_field("hot", true);
+        assert_eq!(query.validate(), Ok(()));
+        assert_eq!(
+            InfluxDbQuery::write_query(Timestamp::NOW, "weather")
+                .add_field("temperature", 82)
+                .validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_validate_measurement() {
+        assert_eq!(
+            validation_error(InfluxDbQuery::write_query(Timestamp::NOW, "").add_field("a", 1)),
+            "measurement cannot be empty"
+        );
+        assert_eq!(
+            validation_error(
+                InfluxDbQuery::write_query(Timestamp::NOW, "_weather").add_field("a", 1)
+            ),
+            "measurement \"_weather\" cannot start with _"
+        );
+    }
+
+    #[test]
+    fn test_validate_requires_field() {
+        assert_eq!(
+            validation_error(
+                InfluxDbQuery::write_query(Timestamp::NOW, "weather").add_tag("location", "us")
+            ),
+            "at least one field is required"
+        );
+    }
+
+    #[test]
+    fn test_validate_keys() {
+        let query = |tag: &str, field: &str| {
+            InfluxDbQuery::write_query(Timestamp::NOW, "weather")
+                .add_tag(tag, "us")
+                .add_field(field, 1)
+        };
+        assert_eq!(
+            validation_error(query("", "temperature")),
+            "tag keys cannot be empty"
+        );
+        assert_eq!(
+            validation_error(query("location", "")),
+            "field keys cannot be empty"
+        );
+        assert_eq!(
+            validation_error(query("loca\ntion", "temperature")),
+            "tag key \"loca\\ntion\" cannot contain a newline"
+        );
+        assert_eq!(
+            validation_error(query("location", "tempera\nture")),
+            "field key \"tempera\\nture\" cannot contain a newline"
+        );
+        assert_eq!(
+            validation_error(query("loca=tion", "temperature")),
+            "tag key \"loca=tion\" cannot contain ="
+        );
+        assert_eq!(
+            validation_error(query("location", "tempera=ture")),
+            "field key \"tempera=ture\" cannot contain ="
+        );
+    }
+
+    #[test]
+    fn test_validate_finite_fields() {
+        let query = |value: f64| {
+            InfluxDbQuery::write_query(Timestamp::NOW, "weather").add_field("a", value)
+        };
+        assert_eq!(
+            validation_error(query(f64::NAN)),
+            "field \"a\" must be finite, but is NaN"
+        );
+        assert_eq!(
+            validation_error(query(f64::INFINITY)),
+            "field \"a\" must be finite, but is inf"
+        );
+        assert_eq!(
+            validation_error(query(f64::NEG_INFINITY)),
+            "field \"a\" must be finite, but is -inf"
+        );
+        // Strings which look like non-finite floats are quoted
+        assert_eq!(
+            InfluxDbQuery::write_query(Timestamp::NOW, "weather")
+                .add_field("a", "NaN")
+                .validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_validate_timestamp() {
+        let query = |timestamp| InfluxDbQuery::write_query(timestamp, "weather").add_field("a", 1);
+        assert_eq!(
+            validation_error(query(Timestamp::SECONDS(0))),
+            "timestamp must be positive, but is 0"
+        );
+        assert_eq!(
+            validation_error(query(Timestamp::NANOSECONDS(-1))),
+            "timestamp must be positive, but is -1"
+        );
+        assert_eq!(query(Timestamp::NANOSECONDS(1)).validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_display() {
+        let query = InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather")
+            .add_tag("location", "us-midwest")
+            .add_field("temperature", 82);
+        assert_eq!(
+            query.to_string(),
+            "weather,location=us-midwest temperature=82i 11"
+        );
+        assert_eq!(
+            format!("{:?}", query),
+            "InfluxDbWriteQuery { line_protocol: \"weather,location=us-midwest temperature=82i 11\", measurement: \"weather\", tags: [(\"location\", \"us-midwest\")], fields: [(\"temperature\", SignedInteger(82))], timestamp: HOURS(11), retention_policy: None }"
+        );
+    }
 }
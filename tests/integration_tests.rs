@@ -1,7 +1,7 @@
 extern crate influxdb;
 
 use futures::prelude::*;
-use influxdb::client::InfluxDbClient;
+use influxdb::client::{InfluxDbClient, PingResponse};
 use influxdb::error::InfluxDbError;
 use influxdb::query::{InfluxDbQuery, Timestamp};
 use tokio::runtime::current_thread::Runtime;
@@ -56,7 +56,7 @@ fn test_ping_influx_db() {
         result.unwrap_err()
     );
 
-    let (build, version) = result.unwrap();
+    let PingResponse { build, version } = result.unwrap();
     assert!(!build.is_empty(), "Build should not be empty");
     assert!(!version.is_empty(), "Build should not be empty");
 
@@ -171,11 +171,11 @@ fn test_wrong_authed_write_and_read() {
         format!("Should be an error: {}", write_result.unwrap_err())
     );
     match write_result {
-        Err(InfluxDbError::AuthorizationError) => assert!(true),
+        Err(InfluxDbError::AuthenticationFailure) => assert!(true),
         _ => assert!(
             false,
             format!(
-                "Should be an AuthorizationError: {}",
+                "Should be an AuthenticationFailure: {}",
                 write_result.unwrap_err()
             )
         ),
@@ -188,11 +188,11 @@ fn test_wrong_authed_write_and_read() {
         format!("Should be an error: {}", read_result.unwrap_err())
     );
     match read_result {
-        Err(InfluxDbError::AuthorizationError) => assert!(true),
+        Err(InfluxDbError::AuthenticationFailure) => assert!(true),
         _ => assert!(
             false,
             format!(
-                "Should be an AuthorizationError: {}",
+                "Should be an AuthenticationFailure: {}",
                 read_result.unwrap_err()
             )
         ),
@@ -207,11 +207,11 @@ fn test_wrong_authed_write_and_read() {
         format!("Should be an error: {}", read_result.unwrap_err())
     );
     match read_result {
-        Err(InfluxDbError::AuthenticationError) => assert!(true),
+        Err(InfluxDbError::AuthorizationFailure) => assert!(true),
         _ => assert!(
             false,
             format!(
-                "Should be an AuthenticationError: {}",
+                "Should be an AuthorizationFailure: {}",
                 read_result.unwrap_err()
             )
         ),
@@ -251,11 +251,11 @@ fn test_non_authed_write_and_read() {
         format!("Should be an error: {}", write_result.unwrap_err())
     );
     match write_result {
-        Err(InfluxDbError::AuthorizationError) => assert!(true),
+        Err(InfluxDbError::AuthenticationFailure) => assert!(true),
         _ => assert!(
             false,
             format!(
-                "Should be an AuthorizationError: {}",
+                "Should be an AuthenticationFailure: {}",
                 write_result.unwrap_err()
             )
         ),
@@ -268,11 +268,11 @@ fn test_non_authed_write_and_read() {
         format!("Should be an error: {}", read_result.unwrap())
     );
     match read_result {
-        Err(InfluxDbError::AuthorizationError) => assert!(true),
+        Err(InfluxDbError::AuthenticationFailure) => assert!(true),
         _ => assert!(
             false,
             format!(
-                "Should be an AuthorizationError: {}",
+                "Should be an AuthenticationFailure: {}",
                 read_result.unwrap_err()
             )
         ),
@@ -351,7 +351,7 @@ fn test_json_query() {
 
     let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
     let future = client
-        .json_query(query)
+        .json_query(&query)
         .and_then(|mut db_result| db_result.deserialize_next::<Weather>());
     let result = get_runtime().block_on(future);
 
@@ -371,6 +371,70 @@ fn test_json_query() {
     delete_db(test_name).expect("could not clean up db");
 }
 
+#[test]
+#[cfg(feature = "use-serde")]
+/// INTEGRATION TEST
+///
+/// This test case tests creating, listing and dropping a database
+fn test_create_and_drop_database() {
+    let test_name = "test_create_and_drop_database";
+    let client = create_client(test_name);
+    let mut rt = get_runtime();
+
+    let result = rt.block_on(client.create_database(test_name));
+    assert!(result.is_ok(), "Should be no error: {:?}", result);
+    // Creating an existing database succeeds
+    let result = rt.block_on(client.create_database(test_name));
+    assert!(result.is_ok(), "Should be no error: {:?}", result);
+
+    let databases = rt
+        .block_on(client.show_databases())
+        .expect("could not show databases");
+    assert!(databases.contains(&test_name.to_string()));
+    assert!(rt.block_on(client.database_exists(test_name)).unwrap());
+
+    let result = rt.block_on(client.drop_database(test_name));
+    assert!(result.is_ok(), "Should be no error: {:?}", result);
+    assert!(!rt.block_on(client.database_exists(test_name)).unwrap());
+}
+
+#[test]
+#[cfg(feature = "use-serde")]
+/// INTEGRATION TEST
+///
+/// This test case tests whether a query matching no data deserializes into an empty series
+fn test_json_query_empty_result() {
+    use serde::Deserialize;
+
+    let test_name = "test_json_query_empty_result";
+    create_db(test_name).expect("could not setup db");
+    let _run_on_drop = RunOnDrop {
+        closure: Box::new(|| {
+            delete_db("test_json_query_empty_result").expect("could not clean up db");
+        }),
+    };
+
+    let client = create_client(test_name);
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Weather {
+        time: String,
+        temperature: i32,
+    }
+
+    let query = InfluxDbQuery::raw_read_query("SELECT * FROM weather");
+    let future = client
+        .json_query(&query)
+        .and_then(|mut db_result| db_result.deserialize_next::<Weather>());
+    let result = get_runtime().block_on(future);
+
+    assert!(
+        result.is_ok(),
+        format!("We couldn't read from the DB: {}", result.unwrap_err())
+    );
+    assert!(result.unwrap().series.is_empty());
+}
+
 #[test]
 #[cfg(feature = "use-serde")]
 /// INTEGRATION TEST
@@ -408,7 +472,7 @@ fn test_json_query_vec() {
 
     let query = InfluxDbQuery::raw_read_query("SELECT * FROM temperature_vec");
     let future = client
-        .json_query(query)
+        .json_query(&query)
         .and_then(|mut db_result| db_result.deserialize_next::<Weather>());
     let result = get_runtime().block_on(future);
 
@@ -471,8 +535,9 @@ fn test_serde_multi_query() {
 
     let future = client
         .json_query(
-            InfluxDbQuery::raw_read_query("SELECT * FROM temperature")
-                .add("SELECT * FROM humidity"),
+            &InfluxDbQuery::raw_read_query("SELECT * FROM temperature")
+                .add("SELECT * FROM humidity")
+                .unwrap(),
         )
         .and_then(|mut db_result| {
             let temp = db_result.deserialize_next::<Temperature>();
@@ -515,7 +580,7 @@ fn test_serde_multi_query() {
 /// This integration test tests whether using the wrong query method fails building the query
 fn test_wrong_query_errors() {
     let client = create_client("test_name");
-    let future = client.json_query(InfluxDbQuery::raw_read_query(
+    let future = client.json_query(&InfluxDbQuery::raw_read_query(
         "CREATE DATABASE this_should_fail",
     ));
     let result = get_runtime().block_on(future);
@@ -524,3 +589,52 @@ fn test_wrong_query_errors() {
         "Should only build SELECT and SHOW queries."
     );
 }
+
+#[test]
+#[cfg(feature = "use-serde")]
+/// INTEGRATION TEST
+///
+/// This test case sets up the fresh InfluxDB 2.x instance listening on port 9999 and writes to
+/// the created bucket with the created token
+fn test_setup_influx_db_2() {
+    let client = InfluxDbClient::new("http://localhost:9999", "test_setup_influx_db_2");
+    let required = get_runtime().block_on(client.is_setup_required());
+    assert_eq!(required, Ok(true), "Instance should not be set up yet");
+
+    let setup = get_runtime().block_on(client.setup(
+        "admin",
+        "password",
+        "openvelo",
+        "weather",
+        Some(3600),
+        Some("test_setup_token"),
+    ));
+    assert!(setup.is_ok(), "Should be no error: {}", setup.unwrap_err());
+    let setup = setup.unwrap();
+    assert_eq!(setup.auth_token, "test_setup_token");
+    assert!(!setup.user_id.is_empty());
+    assert!(!setup.org_id.is_empty());
+    assert!(!setup.bucket_id.is_empty());
+
+    let required = get_runtime().block_on(client.is_setup_required());
+    assert_eq!(required, Ok(false), "Instance should be set up");
+    let again = get_runtime()
+        .block_on(client.setup("admin", "password", "openvelo", "weather", None, None));
+    match again {
+        Err(InfluxDbError::HttpError { status: 422, .. }) => {}
+        _ => panic!("Should cause an HttpError: {:?}", again),
+    }
+
+    let client = client
+        .with_token(setup.auth_token)
+        .with_org("openvelo")
+        .with_bucket("weather");
+    let write_query =
+        InfluxDbQuery::write_query(Timestamp::HOURS(11), "weather").add_field("temperature", 82);
+    let write_result = get_runtime().block_on(client.query(&write_query));
+    assert!(
+        write_result.is_ok(),
+        "Should be no error: {}",
+        write_result.unwrap_err()
+    );
+}